@@ -1,4 +1,4 @@
-use erc_nft_metadata::Metadata;
+use erc_nft_metadata::{AttributeEntry, DisplayType, Metadata};
 use ethers_core::types::Address;
 use serde::Deserialize;
 
@@ -67,6 +67,164 @@ pub struct LineageNode {
     pub parents: [u64; 2],
 }
 
+impl Attributes {
+    /// The champion's metadata attributes, with the known traits parsed.
+    pub fn traits(&self) -> Traits {
+        Traits::from(self.attributes.attributes.as_slice())
+    }
+}
+
+/// Define an enum over the known values of a trait, falling back to `Other` for values that are
+/// not known yet so that new ones do not break parsing.
+macro_rules! trait_enum {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident => $value:literal,)* }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant,)*
+            /// A value not known to this crate.
+            Other(String),
+        }
+
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(Self::$variant => $value,)*
+                    Self::Other(value) => value,
+                }
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(value: &str) -> Self {
+                match value {
+                    $($value => Self::$variant,)*
+                    other => Self::Other(other.to_owned()),
+                }
+            }
+        }
+    };
+}
+
+trait_enum! {
+    /// `Bloodline` trait.
+    Bloodline {
+        Genesis => "Genesis",
+        Mystic => "Mystic",
+        Vanguard => "Vanguard",
+        Halfblood => "Halfblood",
+    }
+}
+
+trait_enum! {
+    /// `Character Class` trait.
+    CharacterClass {
+        Barbarian => "Barbarian",
+        Druid => "Druid",
+    }
+}
+
+trait_enum! {
+    /// `Breed` trait.
+    Breed {
+        Elf => "Elf",
+        HighBorn => "High Born",
+        Origin => "Origin",
+    }
+}
+
+trait_enum! {
+    /// `Armor Color` trait.
+    ArmorColor {
+        Charcoal => "Charcoal",
+        Frost => "Frost",
+        Greatwood => "Greatwood",
+        Spectral => "Spectral",
+    }
+}
+
+trait_enum! {
+    /// `Hair Style` trait.
+    HairStyle {
+        BlondeBarbarianMohawk => "Blonde Barbarian Mohawk",
+        DruidBald => "Druid Bald",
+        GrayBarbarianMohawk => "Gray Barbarian Mohawk",
+        TanBarbarianWartail => "Tan Barbarian Wartail",
+    }
+}
+
+trait_enum! {
+    /// `Warpaint` trait, `None` (the string) for champions without warpaint.
+    Warpaint {
+        Unpainted => "None",
+        RedRagnarok => "Red Ragnarok",
+        SilverRagnarok => "Silver Ragnarok",
+    }
+}
+
+/// `Genotype` trait, such as `R5`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Genotype(pub u32);
+
+impl Genotype {
+    fn parse(value: &str) -> Option<Self> {
+        value.strip_prefix('R')?.parse().ok().map(Self)
+    }
+}
+
+/// Champion traits, with the known ones typed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Traits {
+    pub bloodline: Option<Bloodline>,
+    pub genotype: Option<Genotype>,
+    pub character_class: Option<CharacterClass>,
+    pub breed: Option<Breed>,
+    pub armor_color: Option<ArmorColor>,
+    pub hair_style: Option<HairStyle>,
+    pub warpaint: Option<Warpaint>,
+    /// Any other attribute, such as `Background`, or a known one with an unexpected value.
+    pub other: Vec<AttributeEntry>,
+}
+
+impl From<&[AttributeEntry]> for Traits {
+    fn from(entries: &[AttributeEntry]) -> Self {
+        let mut traits = Traits::default();
+
+        for entry in entries {
+            let AttributeEntry::String { trait_type, value } = entry else {
+                traits.other.push(entry.clone());
+                continue;
+            };
+
+            match trait_type.as_str() {
+                "Bloodline" => traits.bloodline = Some(value.as_str().into()),
+                "Genotype" => match Genotype::parse(value) {
+                    Some(genotype) => traits.genotype = Some(genotype),
+                    None => traits.other.push(entry.clone()),
+                },
+                "Character Class" => traits.character_class = Some(value.as_str().into()),
+                "Breed" => traits.breed = Some(value.as_str().into()),
+                "Armor Color" => traits.armor_color = Some(value.as_str().into()),
+                "Hair Style" => traits.hair_style = Some(value.as_str().into()),
+                "Warpaint" => traits.warpaint = Some(value.as_str().into()),
+                _ => traits.other.push(entry.clone()),
+            }
+        }
+
+        traits
+    }
+}
+
+/// Name of a display type as it appears in metadata.
+pub fn display_type_name(display_type: DisplayType) -> &'static str {
+    match display_type {
+        DisplayType::Number => "number",
+        DisplayType::BoostPercentage => "boost_percentage",
+        DisplayType::BoostNumber => "boost_number",
+        DisplayType::Date => "date",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -95,4 +253,62 @@ mod tests {
     fn test_fighter_28787() {
         let _ = serde_json::from_str::<FighterResponse>(TEST_FIGHTER_28787).unwrap();
     }
+
+    #[test]
+    fn test_fighter_363_traits() {
+        let fighter = serde_json::from_str::<FighterResponse>(TEST_FIGHTER_363).unwrap();
+        let traits = fighter.attributes.traits();
+
+        assert_eq!(traits.bloodline, Some(Bloodline::Genesis));
+        assert_eq!(traits.genotype, Some(Genotype(1)));
+        assert_eq!(traits.character_class, Some(CharacterClass::Barbarian));
+        assert_eq!(traits.breed, Some(Breed::Elf));
+        assert_eq!(traits.armor_color, Some(ArmorColor::Frost));
+        assert_eq!(traits.hair_style, Some(HairStyle::TanBarbarianWartail));
+        assert_eq!(traits.warpaint, Some(Warpaint::RedRagnarok));
+        assert_eq!(
+            traits.other,
+            vec![AttributeEntry::String {
+                trait_type: "Background".to_owned(),
+                value: "Black".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_unknown_traits() {
+        let traits = Traits::from(
+            [
+                AttributeEntry::String {
+                    trait_type: "Bloodline".to_owned(),
+                    value: "Pureblood".to_owned(),
+                },
+                AttributeEntry::String {
+                    trait_type: "Genotype".to_owned(),
+                    value: "unknown".to_owned(),
+                },
+                AttributeEntry::String {
+                    trait_type: "Warpaint".to_owned(),
+                    value: "Gold Ragnarok".to_owned(),
+                },
+                AttributeEntry::Number {
+                    trait_type: "Generation".to_owned(),
+                    value: 2,
+                    display_type: Some(DisplayType::Number),
+                },
+            ]
+            .as_slice(),
+        );
+
+        assert_eq!(
+            traits.bloodline,
+            Some(Bloodline::Other("Pureblood".to_owned()))
+        );
+        assert_eq!(traits.genotype, None);
+        assert_eq!(
+            traits.warpaint.as_ref().map(Warpaint::as_str),
+            Some("Gold Ragnarok")
+        );
+        assert_eq!(traits.other.len(), 2);
+    }
 }
@@ -5,6 +5,12 @@ pub mod tournament;
 pub mod tournament_detail;
 pub mod util;
 
+/// Version of the response models in this crate.
+///
+/// Bump this whenever a parser changes so that stored payloads which previously failed to parse
+/// can be told apart from ones that failed with the current models.
+pub const SCHEMA_VERSION: i32 = 1;
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Pagination {
     pub total_count: u64,
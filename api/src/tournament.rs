@@ -150,6 +150,18 @@ impl Tournament {
         }
     }
 
+    pub fn modified(&self) -> DateTime<Utc> {
+        match self {
+            Tournament::OneVOne { modified, .. } => *modified,
+            Tournament::Blooding { modified, .. } => *modified,
+            Tournament::Bloodbath { modified, .. } => *modified,
+            Tournament::BloodElo { modified, .. } => *modified,
+            Tournament::DoubleUp { modified, .. } => *modified,
+            Tournament::DoubleUpReverse { modified, .. } => *modified,
+            Tournament::Traditional { modified, .. } => *modified,
+        }
+    }
+
     pub fn status(&self) -> Status {
         match self {
             Tournament::OneVOne { status, .. } => *status,
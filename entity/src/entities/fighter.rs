@@ -18,6 +18,14 @@ pub struct Model {
     pub omega_to: i32,
     pub mum: Option<i64>,
     pub meta_last_updated: DateTime,
+    pub elo: Option<i32>,
+    pub bloodline: Option<String>,
+    pub genotype: Option<i32>,
+    pub character_class: Option<String>,
+    pub breed: Option<String>,
+    pub armor_color: Option<String>,
+    pub hair_style: Option<String>,
+    pub warpaint: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -30,8 +38,18 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     SelfRef,
+    #[sea_orm(has_one = "super::fighter_combat::Entity")]
+    FighterCombat,
+    #[sea_orm(has_one = "super::fighter_rarity::Entity")]
+    FighterRarity,
+    #[sea_orm(has_many = "super::fighter_rating::Entity")]
+    FighterRating,
+    #[sea_orm(has_many = "super::fighter_round_damage::Entity")]
+    FighterRoundDamage,
     #[sea_orm(has_many = "super::fighter_trait::Entity")]
     FighterTrait,
+    #[sea_orm(has_many = "super::rating_delta::Entity")]
+    RatingDelta,
     #[sea_orm(has_many = "super::tournament_detail_attack::Entity")]
     TournamentDetailAttack,
     #[sea_orm(has_many = "super::tournament_detail_champion::Entity")]
@@ -40,12 +58,42 @@ pub enum Relation {
     TournamentFighter,
 }
 
+impl Related<super::fighter_combat::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::FighterCombat.def()
+    }
+}
+
+impl Related<super::fighter_rarity::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::FighterRarity.def()
+    }
+}
+
+impl Related<super::fighter_rating::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::FighterRating.def()
+    }
+}
+
+impl Related<super::fighter_round_damage::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::FighterRoundDamage.def()
+    }
+}
+
 impl Related<super::fighter_trait::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::FighterTrait.def()
     }
 }
 
+impl Related<super::rating_delta::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RatingDelta.def()
+    }
+}
+
 impl Related<super::tournament_detail_attack::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::TournamentDetailAttack.def()
@@ -9,6 +9,9 @@ pub struct Model {
     pub page_size: i64,
     #[sea_orm(primary_key, auto_increment = false)]
     pub page_index: i64,
+    pub error_kind: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub last_error: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
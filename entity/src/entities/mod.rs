@@ -2,13 +2,33 @@
 
 pub mod prelude;
 
+pub mod breeding_trait_frequency;
+pub mod breeding_wisdom_frequency;
+pub mod class_matchup;
 pub mod fighter;
+pub mod fighter_combat;
 pub mod fighter_parent;
+pub mod fighter_rarity;
+pub mod fighter_rating;
+pub mod fighter_round_damage;
 pub mod fighter_trait;
+pub mod head_to_head;
+pub mod matchup_tournament;
+pub mod meta_dead_letter;
+pub mod meta_failed_fighter_request;
 pub mod meta_failed_tournament_request;
 pub mod meta_last_page;
+pub mod meta_raw_response;
+pub mod meta_raw_response_blob;
+pub mod meta_tournament_detail;
+pub mod meta_tournament_page;
+pub mod rating_delta;
 pub mod sea_orm_active_enums;
+pub mod stance_matchup;
+pub mod token;
 pub mod tournament;
 pub mod tournament_detail_attack;
 pub mod tournament_detail_champion;
 pub mod tournament_fighter;
+pub mod tournament_usd;
+pub mod trait_frequency;
@@ -1,11 +1,31 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.3
 
+pub use super::breeding_trait_frequency::Entity as BreedingTraitFrequency;
+pub use super::breeding_wisdom_frequency::Entity as BreedingWisdomFrequency;
+pub use super::class_matchup::Entity as ClassMatchup;
 pub use super::fighter::Entity as Fighter;
+pub use super::fighter_combat::Entity as FighterCombat;
 pub use super::fighter_parent::Entity as FighterParent;
+pub use super::fighter_rarity::Entity as FighterRarity;
+pub use super::fighter_rating::Entity as FighterRating;
+pub use super::fighter_round_damage::Entity as FighterRoundDamage;
 pub use super::fighter_trait::Entity as FighterTrait;
+pub use super::head_to_head::Entity as HeadToHead;
+pub use super::matchup_tournament::Entity as MatchupTournament;
+pub use super::meta_dead_letter::Entity as MetaDeadLetter;
+pub use super::meta_failed_fighter_request::Entity as MetaFailedFighterRequest;
 pub use super::meta_failed_tournament_request::Entity as MetaFailedTournamentRequest;
 pub use super::meta_last_page::Entity as MetaLastPage;
+pub use super::meta_raw_response::Entity as MetaRawResponse;
+pub use super::meta_raw_response_blob::Entity as MetaRawResponseBlob;
+pub use super::meta_tournament_detail::Entity as MetaTournamentDetail;
+pub use super::meta_tournament_page::Entity as MetaTournamentPage;
+pub use super::rating_delta::Entity as RatingDelta;
+pub use super::stance_matchup::Entity as StanceMatchup;
+pub use super::token::Entity as Token;
 pub use super::tournament::Entity as Tournament;
 pub use super::tournament_detail_attack::Entity as TournamentDetailAttack;
 pub use super::tournament_detail_champion::Entity as TournamentDetailChampion;
 pub use super::tournament_fighter::Entity as TournamentFighter;
+pub use super::tournament_usd::Entity as TournamentUsd;
+pub use super::trait_frequency::Entity as TraitFrequency;
@@ -2,6 +2,23 @@
 
 use sea_orm::entity::prelude::*;
 
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(
+    rs_type = "String",
+    db_type = "Enum",
+    enum_name = "tournament_detail_state"
+)]
+pub enum TournamentDetailState {
+    #[sea_orm(string_value = "empty")]
+    Empty,
+    #[sea_orm(string_value = "failed")]
+    Failed,
+    #[sea_orm(string_value = "fetched")]
+    Fetched,
+    #[sea_orm(string_value = "pending")]
+    Pending,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "tournament_status")]
 pub enum TournamentStatus {
@@ -33,12 +33,46 @@ pub struct Model {
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::matchup_tournament::Entity")]
+    MatchupTournament,
+    #[sea_orm(has_many = "super::meta_tournament_detail::Entity")]
+    MetaTournamentDetail,
+    #[sea_orm(has_many = "super::meta_tournament_page::Entity")]
+    MetaTournamentPage,
+    #[sea_orm(has_many = "super::rating_delta::Entity")]
+    RatingDelta,
     #[sea_orm(has_many = "super::tournament_detail_attack::Entity")]
     TournamentDetailAttack,
     #[sea_orm(has_many = "super::tournament_detail_champion::Entity")]
     TournamentDetailChampion,
     #[sea_orm(has_many = "super::tournament_fighter::Entity")]
     TournamentFighter,
+    #[sea_orm(has_one = "super::tournament_usd::Entity")]
+    TournamentUsd,
+}
+
+impl Related<super::matchup_tournament::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MatchupTournament.def()
+    }
+}
+
+impl Related<super::meta_tournament_detail::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MetaTournamentDetail.def()
+    }
+}
+
+impl Related<super::meta_tournament_page::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MetaTournamentPage.def()
+    }
+}
+
+impl Related<super::rating_delta::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RatingDelta.def()
+    }
 }
 
 impl Related<super::tournament_detail_attack::Entity> for Entity {
@@ -59,4 +93,10 @@ impl Related<super::tournament_fighter::Entity> for Entity {
     }
 }
 
+impl Related<super::tournament_usd::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TournamentUsd.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
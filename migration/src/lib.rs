@@ -3,6 +3,24 @@ pub use sea_orm_migration::prelude::*;
 mod m20220101_000001_create_fighter_table;
 mod m20220101_000002_create_tournament_table;
 mod m20220101_000003_create_tournament_details_table;
+mod m20220101_000004_create_meta_failed_fighter_request_table;
+mod m20220101_000005_create_meta_dead_letter_table;
+mod m20220101_000006_create_meta_raw_response_tables;
+mod m20220101_000007_create_meta_tournament_detail_table;
+mod m20220101_000008_create_meta_tournament_page_table;
+mod m20220101_000009_add_error_kind_to_failure_tables;
+mod m20220101_000010_add_elo_to_fighter_table;
+mod m20220101_000011_create_breeding_frequency_tables;
+mod m20220101_000012_add_typed_traits;
+mod m20220101_000013_create_rarity_tables;
+mod m20220101_000014_create_rating_tables;
+mod m20220101_000015_create_matchup_tables;
+mod m20220101_000016_create_combat_tables;
+mod m20220101_000017_create_token_table;
+mod m20220101_000018_create_tournament_usd_table;
+mod m20220101_000019_rename_failed_fighter_error_kinds;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 pub struct Migrator;
 
@@ -13,6 +31,22 @@ impl MigratorTrait for Migrator {
             Box::new(m20220101_000001_create_fighter_table::Migration),
             Box::new(m20220101_000002_create_tournament_table::Migration),
             Box::new(m20220101_000003_create_tournament_details_table::Migration),
+            Box::new(m20220101_000004_create_meta_failed_fighter_request_table::Migration),
+            Box::new(m20220101_000005_create_meta_dead_letter_table::Migration),
+            Box::new(m20220101_000006_create_meta_raw_response_tables::Migration),
+            Box::new(m20220101_000007_create_meta_tournament_detail_table::Migration),
+            Box::new(m20220101_000008_create_meta_tournament_page_table::Migration),
+            Box::new(m20220101_000009_add_error_kind_to_failure_tables::Migration),
+            Box::new(m20220101_000010_add_elo_to_fighter_table::Migration),
+            Box::new(m20220101_000011_create_breeding_frequency_tables::Migration),
+            Box::new(m20220101_000012_add_typed_traits::Migration),
+            Box::new(m20220101_000013_create_rarity_tables::Migration),
+            Box::new(m20220101_000014_create_rating_tables::Migration),
+            Box::new(m20220101_000015_create_matchup_tables::Migration),
+            Box::new(m20220101_000016_create_combat_tables::Migration),
+            Box::new(m20220101_000017_create_token_table::Migration),
+            Box::new(m20220101_000018_create_tournament_usd_table::Migration),
+            Box::new(m20220101_000019_rename_failed_fighter_error_kinds::Migration),
         ]
     }
 }
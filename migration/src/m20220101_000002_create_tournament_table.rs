@@ -1,4 +1,4 @@
-use sea_orm_migration::{prelude::*, sea_query::extension::postgres::Type};
+use sea_orm_migration::{prelude::*, sea_orm::DbBackend, sea_query::extension::postgres::Type};
 
 use crate::m20220101_000001_create_fighter_table::Fighter;
 
@@ -8,19 +8,22 @@ pub struct Migration;
 #[async_trait::async_trait]
 impl MigrationTrait for Migration {
     async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
-        manager
-            .create_type(
-                Type::create()
-                    .as_enum(TournamentStatus::Type)
-                    .values([
-                        TournamentStatus::Completed,
-                        TournamentStatus::Cancelled,
-                        TournamentStatus::Created,
-                        TournamentStatus::Fought,
-                    ])
-                    .to_owned(),
-            )
-            .await?;
+        // Enum types only exist in Postgres, SQLite stores their values as text
+        if manager.get_database_backend() == DbBackend::Postgres {
+            manager
+                .create_type(
+                    Type::create()
+                        .as_enum(TournamentStatus::Type)
+                        .values([
+                            TournamentStatus::Completed,
+                            TournamentStatus::Cancelled,
+                            TournamentStatus::Created,
+                            TournamentStatus::Fought,
+                        ])
+                        .to_owned(),
+                )
+                .await?;
+        }
 
         manager
             .create_table(
@@ -190,9 +193,11 @@ impl MigrationTrait for Migration {
             .drop_table(Table::drop().table(MetaLastPage::Table).to_owned())
             .await?;
 
-        manager
-            .drop_type(Type::drop().name(TournamentStatus::Type).to_owned())
-            .await?;
+        if manager.get_database_backend() == DbBackend::Postgres {
+            manager
+                .drop_type(Type::drop().name(TournamentStatus::Type).to_owned())
+                .await?;
+        }
 
         Ok(())
     }
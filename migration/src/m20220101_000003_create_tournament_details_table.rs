@@ -168,7 +168,7 @@ impl MigrationTrait for Migration {
 
 /// Learn more at https://docs.rs/sea-query#iden
 #[derive(Iden)]
-enum TournamentDetailChampion {
+pub(crate) enum TournamentDetailChampion {
     Table,
     TournamentId,
     TournamentServiceId,
@@ -177,7 +177,7 @@ enum TournamentDetailChampion {
 }
 
 #[derive(Iden)]
-enum TournamentDetailAttack {
+pub(crate) enum TournamentDetailAttack {
     Table,
     TournamentId,        // p
     TournamentServiceId, // p
@@ -0,0 +1,69 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MetaFailedFighterRequest::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MetaFailedFighterRequest::FighterId)
+                            .big_unsigned()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(MetaFailedFighterRequest::ErrorKind)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MetaFailedFighterRequest::Attempts)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MetaFailedFighterRequest::LastError)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MetaFailedFighterRequest::LastAttempt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(MetaFailedFighterRequest::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum MetaFailedFighterRequest {
+    Table,
+    FighterId,
+    ErrorKind,
+    Attempts,
+    LastError,
+    LastAttempt,
+}
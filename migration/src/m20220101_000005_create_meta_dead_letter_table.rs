@@ -0,0 +1,69 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MetaDeadLetter::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(MetaDeadLetter::Kind).string().not_null())
+                    .col(
+                        ColumnDef::new(MetaDeadLetter::Reference)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(MetaDeadLetter::SourceUrl).text().not_null())
+                    .col(
+                        ColumnDef::new(MetaDeadLetter::Payload)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(MetaDeadLetter::Error).text().not_null())
+                    .col(
+                        ColumnDef::new(MetaDeadLetter::SchemaVersion)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MetaDeadLetter::LastSeen)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(MetaDeadLetter::Kind)
+                            .col(MetaDeadLetter::Reference),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MetaDeadLetter::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum MetaDeadLetter {
+    Table,
+    Kind,      // p
+    Reference, // p
+    SourceUrl,
+    Payload,
+    Error,
+    SchemaVersion,
+    LastSeen,
+}
@@ -0,0 +1,101 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MetaRawResponse::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MetaRawResponse::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(MetaRawResponse::Kind).string().not_null())
+                    .col(
+                        ColumnDef::new(MetaRawResponse::Reference)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(MetaRawResponse::Url).text().not_null())
+                    .col(ColumnDef::new(MetaRawResponse::Hash).string().not_null())
+                    .col(
+                        ColumnDef::new(MetaRawResponse::FetchedAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-fetched_at-meta_raw_response")
+                    .table(MetaRawResponse::Table)
+                    .col(MetaRawResponse::FetchedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(MetaRawResponseBlob::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MetaRawResponseBlob::Hash)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(MetaRawResponseBlob::Data)
+                            .binary()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MetaRawResponseBlob::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(MetaRawResponse::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum MetaRawResponse {
+    Table,
+    Id,
+    Kind,
+    Reference,
+    Url,
+    Hash,
+    FetchedAt,
+}
+
+#[derive(Iden)]
+enum MetaRawResponseBlob {
+    Table,
+    Hash,
+    Data,
+}
@@ -0,0 +1,191 @@
+use sea_orm_migration::{prelude::*, sea_orm::DbBackend, sea_query::extension::postgres::Type};
+
+use crate::m20220101_000002_create_tournament_table::Tournament;
+use crate::m20220101_000003_create_tournament_details_table::{
+    TournamentDetailAttack, TournamentDetailChampion,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Enum types only exist in Postgres, SQLite stores their values as text
+        if manager.get_database_backend() == DbBackend::Postgres {
+            manager
+                .create_type(
+                    Type::create()
+                        .as_enum(TournamentDetailState::Type)
+                        .values([
+                            TournamentDetailState::Pending,
+                            TournamentDetailState::Fetched,
+                            TournamentDetailState::Failed,
+                            TournamentDetailState::Empty,
+                        ])
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(MetaTournamentDetail::Table)
+                    .col(
+                        ColumnDef::new(MetaTournamentDetail::TournamentId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MetaTournamentDetail::TournamentServiceId)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MetaTournamentDetail::State)
+                            .custom(TournamentDetailState::Type)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MetaTournamentDetail::Attempts)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(MetaTournamentDetail::LastError).text())
+                    .col(ColumnDef::new(MetaTournamentDetail::LastAttempt).date_time())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-tournament_id-meta_tournament_detail")
+                            .from(
+                                MetaTournamentDetail::Table,
+                                (
+                                    MetaTournamentDetail::TournamentId,
+                                    MetaTournamentDetail::TournamentServiceId,
+                                ),
+                            )
+                            .to(Tournament::Table, (Tournament::Id, Tournament::ServiceId)),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(MetaTournamentDetail::TournamentId)
+                            .col(MetaTournamentDetail::TournamentServiceId),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Tournaments with attacks already have their detail, those with only champions had an
+        // empty one, and every other tournament still needs its detail checked
+        let state = |state: TournamentDetailState| {
+            Expr::val(state.to_string()).as_enum(TournamentDetailState::Type)
+        };
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(MetaTournamentDetail::Table)
+                    .columns([
+                        MetaTournamentDetail::TournamentId,
+                        MetaTournamentDetail::TournamentServiceId,
+                        MetaTournamentDetail::State,
+                        MetaTournamentDetail::Attempts,
+                    ])
+                    .select_from(
+                        Query::select()
+                            .column(Tournament::Id)
+                            .column(Tournament::ServiceId)
+                            .expr(
+                                Expr::case(
+                                    Expr::exists(detail_rows(
+                                        TournamentDetailAttack::Table,
+                                        TournamentDetailAttack::TournamentId,
+                                        TournamentDetailAttack::TournamentServiceId,
+                                    )),
+                                    state(TournamentDetailState::Fetched),
+                                )
+                                .case(
+                                    Expr::exists(detail_rows(
+                                        TournamentDetailChampion::Table,
+                                        TournamentDetailChampion::TournamentId,
+                                        TournamentDetailChampion::TournamentServiceId,
+                                    )),
+                                    state(TournamentDetailState::Empty),
+                                )
+                                .finally(state(TournamentDetailState::Pending)),
+                            )
+                            .expr(Expr::val(0))
+                            .from(Tournament::Table)
+                            .and_where(Expr::col(Tournament::Status).ne(
+                                Expr::val("cancelled").as_enum(Alias::new("tournament_status")),
+                            ))
+                            .to_owned(),
+                    )
+                    .map_err(|e| DbErr::Migration(e.to_string()))?
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MetaTournamentDetail::Table).to_owned())
+            .await?;
+
+        if manager.get_database_backend() == DbBackend::Postgres {
+            manager
+                .drop_type(Type::drop().name(TournamentDetailState::Type).to_owned())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The rows of a detail table that belong to the tournament being selected.
+fn detail_rows<T: Iden + 'static>(table: T, id: T, service_id: T) -> SelectStatement {
+    Query::select()
+        .expr(Expr::val(1))
+        .from(table)
+        .and_where(Expr::col(id).equals((Tournament::Table, Tournament::Id)))
+        .and_where(Expr::col(service_id).equals((Tournament::Table, Tournament::ServiceId)))
+        .to_owned()
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum MetaTournamentDetail {
+    Table,
+    TournamentId,        // p
+    TournamentServiceId, // p
+    State,
+    Attempts,
+    LastError,   // text, nullable
+    LastAttempt, // datetime, nullable
+}
+
+enum TournamentDetailState {
+    Type,
+    Pending,
+    Fetched,
+    Failed,
+    Empty,
+}
+
+impl Iden for TournamentDetailState {
+    fn unquoted(&self, s: &mut dyn std::fmt::Write) {
+        write!(
+            s,
+            "{}",
+            match self {
+                TournamentDetailState::Type => "tournament_detail_state",
+                TournamentDetailState::Pending => "pending",
+                TournamentDetailState::Fetched => "fetched",
+                TournamentDetailState::Failed => "failed",
+                TournamentDetailState::Empty => "empty",
+            }
+        )
+        .unwrap()
+    }
+}
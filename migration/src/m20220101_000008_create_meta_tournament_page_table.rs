@@ -0,0 +1,76 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20220101_000002_create_tournament_table::Tournament;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MetaTournamentPage::Table)
+                    .col(
+                        ColumnDef::new(MetaTournamentPage::TournamentId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MetaTournamentPage::TournamentServiceId)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MetaTournamentPage::PageSize)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MetaTournamentPage::PageIndex)
+                            .big_unsigned()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-tournament_id-meta_tournament_page")
+                            .from(
+                                MetaTournamentPage::Table,
+                                (
+                                    MetaTournamentPage::TournamentId,
+                                    MetaTournamentPage::TournamentServiceId,
+                                ),
+                            )
+                            .to(Tournament::Table, (Tournament::Id, Tournament::ServiceId)),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(MetaTournamentPage::TournamentId)
+                            .col(MetaTournamentPage::TournamentServiceId),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MetaTournamentPage::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum MetaTournamentPage {
+    Table,
+    TournamentId,        // p
+    TournamentServiceId, // p
+    PageSize,
+    PageIndex,
+}
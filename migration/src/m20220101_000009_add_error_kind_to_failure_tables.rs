@@ -0,0 +1,81 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // One column per statement, as SQLite can only alter a table a column at a time
+        for mut column in [
+            ColumnDef::new(MetaFailedTournamentRequest::ErrorKind)
+                .string()
+                .to_owned(),
+            ColumnDef::new(MetaFailedTournamentRequest::LastError)
+                .text()
+                .to_owned(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(MetaFailedTournamentRequest::Table)
+                        .add_column(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(MetaTournamentDetail::Table)
+                    .add_column(ColumnDef::new(MetaTournamentDetail::ErrorKind).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(MetaTournamentDetail::Table)
+                    .drop_column(MetaTournamentDetail::ErrorKind)
+                    .to_owned(),
+            )
+            .await?;
+
+        for column in [
+            MetaFailedTournamentRequest::ErrorKind,
+            MetaFailedTournamentRequest::LastError,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(MetaFailedTournamentRequest::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum MetaFailedTournamentRequest {
+    Table,
+    ErrorKind,
+    LastError,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum MetaTournamentDetail {
+    Table,
+    ErrorKind,
+}
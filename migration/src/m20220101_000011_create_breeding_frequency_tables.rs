@@ -0,0 +1,133 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BreedingTraitFrequency::Table)
+                    .col(
+                        ColumnDef::new(BreedingTraitFrequency::TraitType)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BreedingTraitFrequency::ParentValueA)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BreedingTraitFrequency::ParentValueB)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BreedingTraitFrequency::ChildValue)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BreedingTraitFrequency::Count)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(BreedingTraitFrequency::TraitType)
+                            .col(BreedingTraitFrequency::ParentValueA)
+                            .col(BreedingTraitFrequency::ParentValueB)
+                            .col(BreedingTraitFrequency::ChildValue),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(BreedingWisdomFrequency::Table)
+                    .col(
+                        ColumnDef::new(BreedingWisdomFrequency::Stat)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BreedingWisdomFrequency::ParentBucketA)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BreedingWisdomFrequency::ParentBucketB)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BreedingWisdomFrequency::ChildBucket)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(BreedingWisdomFrequency::Count)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(BreedingWisdomFrequency::Stat)
+                            .col(BreedingWisdomFrequency::ParentBucketA)
+                            .col(BreedingWisdomFrequency::ParentBucketB)
+                            .col(BreedingWisdomFrequency::ChildBucket),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(BreedingWisdomFrequency::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(BreedingTraitFrequency::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum BreedingTraitFrequency {
+    Table,
+    TraitType,    // p
+    ParentValueA, // p
+    ParentValueB, // p
+    ChildValue,   // p
+    Count,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum BreedingWisdomFrequency {
+    Table,
+    Stat,          // p
+    ParentBucketA, // p
+    ParentBucketB, // p
+    ChildBucket,   // p
+    Count,
+}
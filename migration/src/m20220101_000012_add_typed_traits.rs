@@ -0,0 +1,104 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // One column per statement, as SQLite can only alter a table a column at a time
+        for mut column in [
+            ColumnDef::new(Fighter::Bloodline).string().to_owned(),
+            ColumnDef::new(Fighter::Genotype).integer().to_owned(),
+            ColumnDef::new(Fighter::CharacterClass).string().to_owned(),
+            ColumnDef::new(Fighter::Breed).string().to_owned(),
+            ColumnDef::new(Fighter::ArmorColor).string().to_owned(),
+            ColumnDef::new(Fighter::HairStyle).string().to_owned(),
+            ColumnDef::new(Fighter::Warpaint).string().to_owned(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Fighter::Table)
+                        .add_column(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        for mut column in [
+            ColumnDef::new(FighterTrait::NumericValue)
+                .big_integer()
+                .to_owned(),
+            ColumnDef::new(FighterTrait::DisplayType)
+                .string()
+                .to_owned(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(FighterTrait::Table)
+                        .add_column(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [FighterTrait::NumericValue, FighterTrait::DisplayType] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(FighterTrait::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        for column in [
+            Fighter::Bloodline,
+            Fighter::Genotype,
+            Fighter::CharacterClass,
+            Fighter::Breed,
+            Fighter::ArmorColor,
+            Fighter::HairStyle,
+            Fighter::Warpaint,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Fighter::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Fighter {
+    Table,
+    Bloodline,
+    Genotype,
+    CharacterClass,
+    Breed,
+    ArmorColor,
+    HairStyle,
+    Warpaint,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum FighterTrait {
+    Table,
+    NumericValue,
+    DisplayType,
+}
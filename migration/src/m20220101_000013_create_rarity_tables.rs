@@ -0,0 +1,127 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20220101_000001_create_fighter_table::Fighter;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TraitFrequency::Table)
+                    .col(
+                        ColumnDef::new(TraitFrequency::TraitType)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TraitFrequency::Value).string().not_null())
+                    .col(
+                        ColumnDef::new(TraitFrequency::Count)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TraitFrequency::Frequency)
+                            .double()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(TraitFrequency::TraitType)
+                            .col(TraitFrequency::Value),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(FighterRarity::Table)
+                    .col(
+                        ColumnDef::new(FighterRarity::FighterId)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(FighterRarity::TraitCount)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(FighterRarity::Statistical)
+                            .double()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(FighterRarity::InformationContent)
+                            .double()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(FighterRarity::TraitCountNormalized)
+                            .double()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(FighterRarity::Rank).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-fighter_id-fighter_rarity")
+                            .from(FighterRarity::Table, FighterRarity::FighterId)
+                            .to(Fighter::Table, Fighter::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-rank-fighter_rarity")
+                    .table(FighterRarity::Table)
+                    .col(FighterRarity::Rank)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(FighterRarity::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(TraitFrequency::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum TraitFrequency {
+    Table,
+    TraitType, // p
+    Value,     // p
+    Count,
+    Frequency,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum FighterRarity {
+    Table,
+    FighterId, // p
+    TraitCount,
+    Statistical,
+    InformationContent,
+    TraitCountNormalized,
+    Rank,
+}
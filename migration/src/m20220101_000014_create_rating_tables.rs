@@ -0,0 +1,165 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20220101_000001_create_fighter_table::Fighter;
+use crate::m20220101_000002_create_tournament_table::Tournament;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RatingDelta::Table)
+                    .col(ColumnDef::new(RatingDelta::System).string().not_null())
+                    .col(
+                        ColumnDef::new(RatingDelta::FighterId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RatingDelta::TournamentId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RatingDelta::TournamentServiceId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RatingDelta::StartTime)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(RatingDelta::Games).integer().not_null())
+                    .col(ColumnDef::new(RatingDelta::Wins).integer().not_null())
+                    .col(ColumnDef::new(RatingDelta::Placement).integer().not_null())
+                    .col(
+                        ColumnDef::new(RatingDelta::RatingBefore)
+                            .double()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(RatingDelta::RatingAfter).double().not_null())
+                    .col(
+                        ColumnDef::new(RatingDelta::DeviationAfter)
+                            .double()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(RatingDelta::VolatilityAfter)
+                            .double()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-fighter_id-rating_delta")
+                            .from(RatingDelta::Table, RatingDelta::FighterId)
+                            .to(Fighter::Table, Fighter::Id),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-tournament_id-rating_delta")
+                            .from(
+                                RatingDelta::Table,
+                                (RatingDelta::TournamentId, RatingDelta::TournamentServiceId),
+                            )
+                            .to(Tournament::Table, (Tournament::Id, Tournament::ServiceId)),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(RatingDelta::System)
+                            .col(RatingDelta::FighterId)
+                            .col(RatingDelta::TournamentId)
+                            .col(RatingDelta::TournamentServiceId),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(FighterRating::Table)
+                    .col(ColumnDef::new(FighterRating::System).string().not_null())
+                    .col(
+                        ColumnDef::new(FighterRating::FighterId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(FighterRating::Rating).double().not_null())
+                    .col(ColumnDef::new(FighterRating::Deviation).double().not_null())
+                    .col(
+                        ColumnDef::new(FighterRating::Volatility)
+                            .double()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(FighterRating::Games).integer().not_null())
+                    .col(
+                        ColumnDef::new(FighterRating::LastPlayed)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-fighter_id-fighter_rating")
+                            .from(FighterRating::Table, FighterRating::FighterId)
+                            .to(Fighter::Table, Fighter::Id),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(FighterRating::System)
+                            .col(FighterRating::FighterId),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(FighterRating::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(RatingDelta::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum RatingDelta {
+    Table,
+    System,              // p
+    FighterId,           // p
+    TournamentId,        // p
+    TournamentServiceId, // p
+    StartTime,
+    Games,
+    Wins,
+    Placement,
+    RatingBefore,
+    RatingAfter,
+    DeviationAfter,
+    VolatilityAfter,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum FighterRating {
+    Table,
+    System,    // p
+    FighterId, // p
+    Rating,
+    Deviation,
+    Volatility,
+    Games,
+    LastPlayed,
+}
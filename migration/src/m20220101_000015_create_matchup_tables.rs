@@ -0,0 +1,226 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20220101_000001_create_fighter_table::Fighter;
+use crate::m20220101_000002_create_tournament_table::Tournament;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(HeadToHead::Table)
+                    .col(
+                        ColumnDef::new(HeadToHead::FighterId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(HeadToHead::OpponentId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(HeadToHead::Wins).integer().not_null())
+                    .col(ColumnDef::new(HeadToHead::Losses).integer().not_null())
+                    .col(ColumnDef::new(HeadToHead::Tournaments).integer().not_null())
+                    .col(
+                        ColumnDef::new(HeadToHead::FinishedAhead)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(HeadToHead::FinishedBehind)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(HeadToHead::LastMet).date_time().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-fighter_id-head_to_head")
+                            .from(HeadToHead::Table, HeadToHead::FighterId)
+                            .to(Fighter::Table, Fighter::Id),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-opponent_id-head_to_head")
+                            .from(HeadToHead::Table, HeadToHead::OpponentId)
+                            .to(Fighter::Table, Fighter::Id),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(HeadToHead::FighterId)
+                            .col(HeadToHead::OpponentId),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(StanceMatchup::Table)
+                    .col(ColumnDef::new(StanceMatchup::Stance).integer().not_null())
+                    .col(
+                        ColumnDef::new(StanceMatchup::OpponentStance)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(StanceMatchup::Wins).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(StanceMatchup::Losses)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(StanceMatchup::Stance)
+                            .col(StanceMatchup::OpponentStance),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ClassMatchup::Table)
+                    .col(
+                        ColumnDef::new(ClassMatchup::CharacterClass)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ClassMatchup::OpponentClass)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ClassMatchup::Wins).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(ClassMatchup::Losses)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(ClassMatchup::CharacterClass)
+                            .col(ClassMatchup::OpponentClass),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(MatchupTournament::Table)
+                    .col(
+                        ColumnDef::new(MatchupTournament::TournamentId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MatchupTournament::TournamentServiceId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MatchupTournament::Games)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(MatchupTournament::CountedAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-tournament_id-matchup_tournament")
+                            .from(
+                                MatchupTournament::Table,
+                                (
+                                    MatchupTournament::TournamentId,
+                                    MatchupTournament::TournamentServiceId,
+                                ),
+                            )
+                            .to(Tournament::Table, (Tournament::Id, Tournament::ServiceId)),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(MatchupTournament::TournamentId)
+                            .col(MatchupTournament::TournamentServiceId),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MatchupTournament::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(ClassMatchup::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(StanceMatchup::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(HeadToHead::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum HeadToHead {
+    Table,
+    FighterId,  // p
+    OpponentId, // p
+    Wins,
+    Losses,
+    Tournaments,
+    FinishedAhead,
+    FinishedBehind,
+    LastMet,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum StanceMatchup {
+    Table,
+    Stance,         // p
+    OpponentStance, // p
+    Wins,
+    Losses,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum ClassMatchup {
+    Table,
+    CharacterClass, // p
+    OpponentClass,  // p
+    Wins,
+    Losses,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum MatchupTournament {
+    Table,
+    TournamentId,        // p
+    TournamentServiceId, // p
+    Games,
+    CountedAt,
+}
@@ -0,0 +1,181 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20220101_000001_create_fighter_table::Fighter;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(FighterCombat::Table)
+                    .col(
+                        ColumnDef::new(FighterCombat::FighterId)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(FighterCombat::Attacks)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(FighterCombat::Hits).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(FighterCombat::Misses)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(FighterCombat::Damage)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(FighterCombat::AverageDamage).double())
+                    .col(ColumnDef::new(FighterCombat::MissRate).double())
+                    .col(
+                        ColumnDef::new(FighterCombat::SpecialAttacks)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(FighterCombat::SpecialAttackRate).double())
+                    .col(
+                        ColumnDef::new(FighterCombat::AttacksReceived)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(FighterCombat::SpecialDefends)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(FighterCombat::SpecialDefendRate).double())
+                    .col(
+                        ColumnDef::new(FighterCombat::DamageTaken)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(FighterCombat::HitsBelowAttackRange)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(FighterCombat::HitsAboveAttackRange)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(FighterCombat::AttackRangePosition).double())
+                    .col(
+                        ColumnDef::new(FighterCombat::MetaLastUpdated)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-fighter_id-fighter_combat")
+                            .from(FighterCombat::Table, FighterCombat::FighterId)
+                            .to(Fighter::Table, Fighter::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(FighterRoundDamage::Table)
+                    .col(
+                        ColumnDef::new(FighterRoundDamage::FighterId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(FighterRoundDamage::Round)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(FighterRoundDamage::Attacks)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(FighterRoundDamage::Hits)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(FighterRoundDamage::Damage)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(FighterRoundDamage::AverageDamage).double())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-fighter_id-fighter_round_damage")
+                            .from(FighterRoundDamage::Table, FighterRoundDamage::FighterId)
+                            .to(Fighter::Table, Fighter::Id),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(FighterRoundDamage::FighterId)
+                            .col(FighterRoundDamage::Round),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(FighterRoundDamage::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(FighterCombat::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum FighterCombat {
+    Table,
+    FighterId, // p
+    Attacks,
+    Hits,
+    Misses,
+    Damage,
+    AverageDamage,
+    MissRate,
+    SpecialAttacks,
+    SpecialAttackRate,
+    AttacksReceived,
+    SpecialDefends,
+    SpecialDefendRate,
+    DamageTaken,
+    HitsBelowAttackRange,
+    HitsAboveAttackRange,
+    AttackRangePosition,
+    MetaLastUpdated,
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum FighterRoundDamage {
+    Table,
+    FighterId, // p
+    Round,     // p
+    Attacks,
+    Hits,
+    Damage,
+    AverageDamage,
+}
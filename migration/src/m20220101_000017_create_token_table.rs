@@ -0,0 +1,157 @@
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ConnectionTrait, DbBackend, Statement},
+};
+
+/// Polygon tokens tournaments are known to be paid in, as address, symbol and decimals.
+const TOKENS: [(&str, &str, i32); 5] = [
+    ("7ceb23fd6bc0add59e62ac25578270cff1b9f619", "WETH", 18),
+    ("0d500b1d8e8ef31e21c99d1db9a6444d3adf1270", "WMATIC", 18),
+    ("2791bca1f2de4661ed88a30c99a7a9449aa84174", "USDC", 6),
+    ("c2132d05d31c914a87c6611c10748aeb04b58e8f", "USDT", 6),
+    ("8f3cf7ad23cd3cadbd9735aff958023239c6a063", "DAI", 18),
+];
+
+/// Decodes a 32-byte big-endian `uint256` into a number.
+const UINT256: &str = r#"
+CREATE FUNCTION uint256(value bytea) RETURNS numeric AS $$
+    SELECT trim_scale(COALESCE(SUM(get_byte(value, i) * 256::numeric ^ (length(value) - 1 - i)), 0))
+    FROM generate_series(0, length(value) - 1) AS i
+$$ LANGUAGE SQL IMMUTABLE STRICT
+"#;
+
+/// Tournament amounts in whole units of their token, or NULL if the token is not registered.
+const TOURNAMENT_AMOUNT: &str = r#"
+CREATE VIEW tournament_amount AS
+SELECT tournament.id AS tournament_id, tournament.service_id AS tournament_service_id,
+    '0x' || encode(tournament.currency, 'hex') AS currency, token.symbol, token.decimals,
+    uint256(tournament.buy_in) AS buy_in_units, uint256(tournament.top_up) AS top_up_units,
+    trim_scale(uint256(tournament.buy_in) / 10::numeric ^ token.decimals) AS buy_in,
+    trim_scale(uint256(tournament.top_up) / 10::numeric ^ token.decimals) AS top_up
+FROM tournament
+LEFT JOIN token ON token.address = tournament.currency
+"#;
+
+/// The same view on SQLite, which has neither `numeric` nor functions written in SQL. Amounts are
+/// decoded a hex digit at a time into doubles, so they lose precision past 2^53 units.
+fn sqlite_tournament_amount() -> String {
+    let units = |column: &str| {
+        (0..64)
+            .map(|i| {
+                format!(
+                    "(instr('0123456789ABCDEF', substr(hex({column}), -{}, 1)) - 1) * {:e}",
+                    i + 1,
+                    16f64.powi(i)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n        + ")
+    };
+
+    format!(
+        r#"
+CREATE VIEW tournament_amount AS
+SELECT tournament_id, tournament_service_id, currency, symbol, decimals,
+    buy_in_units, top_up_units,
+    buy_in_units / CAST('1e' || decimals AS REAL) AS buy_in,
+    top_up_units / CAST('1e' || decimals AS REAL) AS top_up
+FROM (
+    SELECT tournament.id AS tournament_id, tournament.service_id AS tournament_service_id,
+        '0x' || lower(hex(tournament.currency)) AS currency, token.symbol, token.decimals,
+        {} AS buy_in_units,
+        {} AS top_up_units
+    FROM tournament
+    LEFT JOIN token ON token.address = tournament.currency
+)
+"#,
+        units("tournament.buy_in"),
+        units("tournament.top_up")
+    )
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Token::Table)
+                    .col(
+                        ColumnDef::new(Token::Address)
+                            .binary()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Token::Symbol).string().not_null())
+                    .col(ColumnDef::new(Token::Decimals).integer().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        let mut insert = Query::insert();
+        insert
+            .into_table(Token::Table)
+            .columns([Token::Address, Token::Symbol, Token::Decimals]);
+        for (address, symbol, decimals) in TOKENS {
+            insert.values_panic([
+                address_bytes(address).into(),
+                symbol.into(),
+                decimals.into(),
+            ]);
+        }
+        manager.exec_stmt(insert).await?;
+
+        let backend = manager.get_database_backend();
+        let statements = match backend {
+            DbBackend::Sqlite => vec![sqlite_tournament_amount()],
+            _ => vec![UINT256.to_owned(), TOURNAMENT_AMOUNT.to_owned()],
+        };
+        let conn = manager.get_connection();
+        for sql in statements {
+            conn.execute(Statement::from_string(backend, sql)).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let backend = manager.get_database_backend();
+        let statements = match backend {
+            DbBackend::Sqlite => vec!["DROP VIEW tournament_amount"],
+            _ => vec![
+                "DROP VIEW tournament_amount",
+                "DROP FUNCTION uint256(bytea)",
+            ],
+        };
+        let conn = manager.get_connection();
+        for sql in statements {
+            conn.execute(Statement::from_string(backend, sql.to_owned()))
+                .await?;
+        }
+
+        manager
+            .drop_table(Table::drop().table(Token::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn address_bytes(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Token {
+    Table,
+    Address, // p
+    Symbol,
+    Decimals,
+}
@@ -0,0 +1,86 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20220101_000002_create_tournament_table::Tournament;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TournamentUsd::Table)
+                    .col(
+                        ColumnDef::new(TournamentUsd::TournamentId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TournamentUsd::TournamentServiceId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TournamentUsd::TokenUsd).double().not_null())
+                    .col(
+                        ColumnDef::new(TournamentUsd::PricedAt)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TournamentUsd::BuyInUsd).double().not_null())
+                    .col(
+                        ColumnDef::new(TournamentUsd::PrizePoolUsd)
+                            .double()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TournamentUsd::MetaLastUpdated)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-tournament_id-tournament_usd")
+                            .from(
+                                TournamentUsd::Table,
+                                (
+                                    TournamentUsd::TournamentId,
+                                    TournamentUsd::TournamentServiceId,
+                                ),
+                            )
+                            .to(Tournament::Table, (Tournament::Id, Tournament::ServiceId)),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(TournamentUsd::TournamentId)
+                            .col(TournamentUsd::TournamentServiceId),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TournamentUsd::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum TournamentUsd {
+    Table,
+    TournamentId,        // p
+    TournamentServiceId, // p
+    TokenUsd,
+    PricedAt,
+    BuyInUsd,
+    PrizePoolUsd,
+    MetaLastUpdated,
+}
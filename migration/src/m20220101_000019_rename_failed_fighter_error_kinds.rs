@@ -0,0 +1,46 @@
+use sea_orm_migration::prelude::*;
+
+/// Error kinds recorded for failed champions before scrape errors were classified, and the
+/// `ScrapeError::kind` each one now corresponds to. An old `status` may have been a 404, a 429 or
+/// a 5xx, so it becomes `unknown` rather than any one of them.
+const RENAMES: [(&str, &str); 5] = [
+    ("decode", "schema"),
+    ("status", "unknown"),
+    ("timeout", "network"),
+    ("connect", "network"),
+    ("request", "network"),
+];
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (old, new) in RENAMES {
+            manager
+                .exec_stmt(
+                    Query::update()
+                        .table(MetaFailedFighterRequest::Table)
+                        .value(MetaFailedFighterRequest::ErrorKind, new)
+                        .and_where(Expr::col(MetaFailedFighterRequest::ErrorKind).eq(old))
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // Several old kinds map onto "network", so there is nothing to map back to
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum MetaFailedFighterRequest {
+    Table,
+    ErrorKind,
+}
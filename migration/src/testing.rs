@@ -0,0 +1,81 @@
+//! Fixtures for tests that need a database, run against in-memory SQLite.
+
+use entity::entities::{fighter, sea_orm_active_enums::TournamentStatus, tournament};
+use sea_orm_migration::{
+    sea_orm::{ConnectOptions, ConnectionTrait, Database, DatabaseConnection, JsonValue},
+    MigratorTrait,
+};
+
+use crate::Migrator;
+
+/// A fresh, migrated in-memory database.
+pub async fn database() -> DatabaseConnection {
+    // Every connection to an in-memory database opens a new one
+    let conn = Database::connect(
+        ConnectOptions::new("sqlite::memory:".to_owned())
+            .max_connections(1)
+            .sqlx_logging(false)
+            .to_owned(),
+    )
+    .await
+    .unwrap();
+    Migrator::up(&conn, None).await.unwrap();
+
+    conn
+}
+
+/// Stop enforcing foreign keys, so that rows can reference fighters or tournaments that were
+/// never scraped.
+pub async fn without_foreign_keys(conn: &DatabaseConnection) {
+    conn.execute_unprepared("PRAGMA foreign_keys = OFF")
+        .await
+        .unwrap();
+}
+
+/// A fighter with every wisdom stat at 0 and no traits.
+pub fn fighter(id: i64) -> fighter::Model {
+    fighter::Model {
+        id,
+        wisdom_point: 0,
+        strength_from: 0,
+        strength_to: 0,
+        attack_from: 0,
+        attack_to: 0,
+        defence_from: 0,
+        defence_to: 0,
+        omega_from: 0,
+        omega_to: 0,
+        mum: None,
+        meta_last_updated: Default::default(),
+        elo: None,
+        bloodline: None,
+        genotype: None,
+        character_class: None,
+        breed: None,
+        armor_color: None,
+        hair_style: None,
+        warpaint: None,
+    }
+}
+
+/// A free tournament without restrictions that started at the epoch.
+pub fn tournament(id: i64, service_id: i32, status: TournamentStatus) -> tournament::Model {
+    tournament::Model {
+        id,
+        service_id,
+        currency: vec![],
+        fee_percentage: 0,
+        buy_in: vec![],
+        top_up: vec![],
+        key: "key".to_owned(),
+        legacy: None,
+        level: "1".to_owned(),
+        modified: Default::default(),
+        name: None,
+        restrictions: JsonValue::Object(Default::default()),
+        solo_optionals: None,
+        start_time: Default::default(),
+        status,
+        meta_last_updated: Default::default(),
+    }
+}
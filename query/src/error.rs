@@ -0,0 +1,52 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use sea_orm::DbErr;
+use serde_json::json;
+use std::fmt;
+use tracing::warn;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug)]
+pub enum Error {
+    NotFound,
+    BadRequest(String),
+    Database(DbErr),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotFound => write!(f, "not found"),
+            Error::BadRequest(message) => write!(f, "{message}"),
+            Error::Database(e) => write!(f, "database error: {e}"),
+        }
+    }
+}
+
+impl From<DbErr> for Error {
+    fn from(e: DbErr) -> Self {
+        Error::Database(e)
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            Error::NotFound => (StatusCode::NOT_FOUND, "not found".to_owned()),
+            Error::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            Error::Database(e) => {
+                warn!(e = ?e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "database error".to_owned(),
+                )
+            }
+        };
+
+        (status, Json(json!({ "error": message }))).into_response()
+    }
+}
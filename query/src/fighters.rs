@@ -0,0 +1,159 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::NaiveDateTime;
+use entity::entities::{fighter, fighter_parent, fighter_trait};
+use sea_orm::{
+    sea_query::Query as SeaQuery, ColumnTrait, Condition, DatabaseConnection, EntityTrait,
+    QueryFilter, QueryOrder, Select,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{
+    error::{Error, Result},
+    pagination::{Page, PageQuery},
+};
+
+#[derive(Debug, Serialize)]
+pub struct Fighter {
+    id: i64,
+    elo: Option<i32>,
+    wisdom_point: i32,
+    strength: [i32; 2],
+    attack: [i32; 2],
+    defence: [i32; 2],
+    omega: [i32; 2],
+    mum: Option<i64>,
+    last_updated: NaiveDateTime,
+}
+
+impl From<fighter::Model> for Fighter {
+    fn from(m: fighter::Model) -> Self {
+        Self {
+            id: m.id,
+            elo: m.elo,
+            wisdom_point: m.wisdom_point,
+            strength: [m.strength_from, m.strength_to],
+            attack: [m.attack_from, m.attack_to],
+            defence: [m.defence_from, m.defence_to],
+            omega: [m.omega_from, m.omega_to],
+            mum: m.mum,
+            last_updated: m.meta_last_updated,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct FighterDetail {
+    #[serde(flatten)]
+    fighter: Fighter,
+    traits: BTreeMap<String, String>,
+    parents: Vec<i64>,
+    children: Vec<i64>,
+}
+
+/// `GET /fighters/{id}`
+pub async fn get_fighter(
+    State(conn): State<DatabaseConnection>,
+    Path(id): Path<i64>,
+) -> Result<Json<FighterDetail>> {
+    let fighter = fighter::Entity::find_by_id(id)
+        .one(&conn)
+        .await?
+        .ok_or(Error::NotFound)?;
+
+    let traits = fighter_trait::Entity::find()
+        .filter(fighter_trait::Column::FighterId.eq(id))
+        .all(&conn)
+        .await?
+        .into_iter()
+        .map(|m| (m.trait_type, m.value))
+        .collect();
+
+    let parents = fighter_parent::Entity::find()
+        .filter(fighter_parent::Column::FighterId.eq(id))
+        .order_by_asc(fighter_parent::Column::ParentId)
+        .all(&conn)
+        .await?
+        .into_iter()
+        .map(|m| m.parent_id)
+        .collect();
+
+    let children = fighter_parent::Entity::find()
+        .filter(fighter_parent::Column::ParentId.eq(id))
+        .order_by_asc(fighter_parent::Column::FighterId)
+        .all(&conn)
+        .await?
+        .into_iter()
+        .map(|m| m.fighter_id)
+        .collect();
+
+    Ok(Json(FighterDetail {
+        fighter: fighter.into(),
+        traits,
+        parents,
+        children,
+    }))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct FighterFilter {
+    /// Either `value`, matching any trait type, or `type:value`.
+    #[serde(rename = "trait")]
+    pub trait_filter: Option<String>,
+    pub elo_min: Option<i32>,
+    pub elo_max: Option<i32>,
+}
+
+impl FighterFilter {
+    /// Fighters matching the filter, by ID.
+    pub fn select(&self) -> Select<fighter::Entity> {
+        let mut select = fighter::Entity::find().order_by_asc(fighter::Column::Id);
+
+        if let Some(trait_filter) = &self.trait_filter {
+            let condition = match trait_filter.split_once(':') {
+                Some((trait_type, value)) => Condition::all()
+                    .add(fighter_trait::Column::TraitType.eq(trait_type))
+                    .add(fighter_trait::Column::Value.eq(value)),
+                None => {
+                    Condition::all().add(fighter_trait::Column::Value.eq(trait_filter.as_str()))
+                }
+            };
+
+            select = select.filter(
+                fighter::Column::Id.in_subquery(
+                    SeaQuery::select()
+                        .column(fighter_trait::Column::FighterId)
+                        .from(fighter_trait::Entity)
+                        .cond_where(condition)
+                        .to_owned(),
+                ),
+            );
+        }
+        if let Some(elo_min) = self.elo_min {
+            select = select.filter(fighter::Column::Elo.gte(elo_min));
+        }
+        if let Some(elo_max) = self.elo_max {
+            select = select.filter(fighter::Column::Elo.lte(elo_max));
+        }
+
+        select
+    }
+}
+
+/// `GET /fighters`
+pub async fn list_fighters(
+    State(conn): State<DatabaseConnection>,
+    Query(filter): Query<FighterFilter>,
+    Query(page): Query<PageQuery>,
+) -> Result<Json<Page<Fighter>>> {
+    let select = filter.select();
+
+    let page = page
+        .fetch(&conn, select.into_model::<fighter::Model>(), Fighter::from)
+        .await?;
+
+    Ok(Json(page))
+}
@@ -0,0 +1,328 @@
+use async_graphql::dataloader::Loader;
+use async_trait::async_trait;
+use entity::entities::{
+    fighter, fighter_parent, fighter_trait, tournament, tournament_detail_attack,
+    tournament_detail_champion, tournament_fighter,
+};
+use itertools::Itertools;
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+};
+use std::{collections::HashMap, sync::Arc};
+
+/// Batches the lookups made while resolving a single GraphQL request, so traversing a relation
+/// over a list costs one query per relation rather than one per item.
+pub struct DbLoader {
+    conn: DatabaseConnection,
+}
+
+impl DbLoader {
+    pub fn new(conn: DatabaseConnection) -> Self {
+        Self { conn }
+    }
+}
+
+/// Primary key of a tournament.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TournamentKey {
+    pub id: i64,
+    pub service_id: i32,
+}
+
+/// Traits of a fighter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TraitsOf(pub i64);
+
+/// Parent IDs of a fighter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ParentsOf(pub i64);
+
+/// Child IDs of a fighter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ChildrenOf(pub i64);
+
+/// Tournaments a fighter entered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EntriesOf(pub i64);
+
+/// Fighters that entered a tournament.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EntrantsOf(pub TournamentKey);
+
+/// Champions that fought in a tournament.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ChampionsOf(pub TournamentKey);
+
+/// Attacks made in a tournament.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AttacksOf(pub TournamentKey);
+
+/// Match any of the tournament keys against a table's tournament ID and service ID columns.
+fn any_tournament<C, T>(
+    keys: impl Iterator<Item = TournamentKey>,
+    id: C,
+    service_id: C,
+) -> Condition
+where
+    C: ColumnTrait,
+    T: From<i32> + Into<sea_orm::Value>,
+{
+    keys.fold(Condition::any(), |condition, key| {
+        condition.add(
+            Condition::all()
+                .add(id.eq(key.id))
+                .add(service_id.eq(T::from(key.service_id))),
+        )
+    })
+}
+
+/// Group rows by key, making sure every requested key is present.
+fn group<K, V>(keys: &[K], rows: impl IntoIterator<Item = (K, V)>) -> HashMap<K, Vec<V>>
+where
+    K: Copy + Eq + std::hash::Hash,
+{
+    let mut map = rows.into_iter().into_group_map();
+    for key in keys {
+        map.entry(*key).or_default();
+    }
+    map.into_iter().collect()
+}
+
+#[async_trait]
+impl Loader<i64> for DbLoader {
+    type Value = fighter::Model;
+    type Error = Arc<DbErr>;
+
+    async fn load(&self, keys: &[i64]) -> Result<HashMap<i64, Self::Value>, Self::Error> {
+        Ok(fighter::Entity::find()
+            .filter(fighter::Column::Id.is_in(keys.iter().copied()))
+            .all(&self.conn)
+            .await?
+            .into_iter()
+            .map(|m| (m.id, m))
+            .collect())
+    }
+}
+
+#[async_trait]
+impl Loader<TraitsOf> for DbLoader {
+    type Value = Vec<fighter_trait::Model>;
+    type Error = Arc<DbErr>;
+
+    async fn load(&self, keys: &[TraitsOf]) -> Result<HashMap<TraitsOf, Self::Value>, Self::Error> {
+        let rows = fighter_trait::Entity::find()
+            .filter(fighter_trait::Column::FighterId.is_in(keys.iter().map(|k| k.0)))
+            .order_by_asc(fighter_trait::Column::TraitType)
+            .all(&self.conn)
+            .await?;
+
+        Ok(group(
+            keys,
+            rows.into_iter().map(|m| (TraitsOf(m.fighter_id), m)),
+        ))
+    }
+}
+
+#[async_trait]
+impl Loader<ParentsOf> for DbLoader {
+    type Value = Vec<i64>;
+    type Error = Arc<DbErr>;
+
+    async fn load(
+        &self,
+        keys: &[ParentsOf],
+    ) -> Result<HashMap<ParentsOf, Self::Value>, Self::Error> {
+        let rows = fighter_parent::Entity::find()
+            .filter(fighter_parent::Column::FighterId.is_in(keys.iter().map(|k| k.0)))
+            .order_by_asc(fighter_parent::Column::ParentId)
+            .all(&self.conn)
+            .await?;
+
+        Ok(group(
+            keys,
+            rows.into_iter()
+                .map(|m| (ParentsOf(m.fighter_id), m.parent_id)),
+        ))
+    }
+}
+
+#[async_trait]
+impl Loader<ChildrenOf> for DbLoader {
+    type Value = Vec<i64>;
+    type Error = Arc<DbErr>;
+
+    async fn load(
+        &self,
+        keys: &[ChildrenOf],
+    ) -> Result<HashMap<ChildrenOf, Self::Value>, Self::Error> {
+        let rows = fighter_parent::Entity::find()
+            .filter(fighter_parent::Column::ParentId.is_in(keys.iter().map(|k| k.0)))
+            .order_by_asc(fighter_parent::Column::FighterId)
+            .all(&self.conn)
+            .await?;
+
+        Ok(group(
+            keys,
+            rows.into_iter()
+                .map(|m| (ChildrenOf(m.parent_id), m.fighter_id)),
+        ))
+    }
+}
+
+#[async_trait]
+impl Loader<EntriesOf> for DbLoader {
+    type Value = Vec<TournamentKey>;
+    type Error = Arc<DbErr>;
+
+    async fn load(
+        &self,
+        keys: &[EntriesOf],
+    ) -> Result<HashMap<EntriesOf, Self::Value>, Self::Error> {
+        let rows = tournament_fighter::Entity::find()
+            .filter(tournament_fighter::Column::FighterId.is_in(keys.iter().map(|k| k.0)))
+            .order_by_desc(tournament_fighter::Column::TournamentId)
+            .all(&self.conn)
+            .await?;
+
+        Ok(group(
+            keys,
+            rows.into_iter().map(|m| {
+                (
+                    EntriesOf(m.fighter_id),
+                    TournamentKey {
+                        id: m.tournament_id,
+                        service_id: m.tournament_service_id,
+                    },
+                )
+            }),
+        ))
+    }
+}
+
+#[async_trait]
+impl Loader<TournamentKey> for DbLoader {
+    type Value = tournament::Model;
+    type Error = Arc<DbErr>;
+
+    async fn load(
+        &self,
+        keys: &[TournamentKey],
+    ) -> Result<HashMap<TournamentKey, Self::Value>, Self::Error> {
+        Ok(tournament::Entity::find()
+            .filter(any_tournament::<_, i32>(
+                keys.iter().copied(),
+                tournament::Column::Id,
+                tournament::Column::ServiceId,
+            ))
+            .all(&self.conn)
+            .await?
+            .into_iter()
+            .map(|m| {
+                (
+                    TournamentKey {
+                        id: m.id,
+                        service_id: m.service_id,
+                    },
+                    m,
+                )
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl Loader<EntrantsOf> for DbLoader {
+    type Value = Vec<tournament_fighter::Model>;
+    type Error = Arc<DbErr>;
+
+    async fn load(
+        &self,
+        keys: &[EntrantsOf],
+    ) -> Result<HashMap<EntrantsOf, Self::Value>, Self::Error> {
+        let rows = tournament_fighter::Entity::find()
+            .filter(any_tournament::<_, i32>(
+                keys.iter().map(|k| k.0),
+                tournament_fighter::Column::TournamentId,
+                tournament_fighter::Column::TournamentServiceId,
+            ))
+            .order_by_asc(tournament_fighter::Column::FighterId)
+            .all(&self.conn)
+            .await?;
+
+        Ok(group(
+            keys,
+            rows.into_iter().map(|m| {
+                let key = TournamentKey {
+                    id: m.tournament_id,
+                    service_id: m.tournament_service_id,
+                };
+                (EntrantsOf(key), m)
+            }),
+        ))
+    }
+}
+
+#[async_trait]
+impl Loader<ChampionsOf> for DbLoader {
+    type Value = Vec<tournament_detail_champion::Model>;
+    type Error = Arc<DbErr>;
+
+    async fn load(
+        &self,
+        keys: &[ChampionsOf],
+    ) -> Result<HashMap<ChampionsOf, Self::Value>, Self::Error> {
+        let rows = tournament_detail_champion::Entity::find()
+            .filter(any_tournament::<_, i64>(
+                keys.iter().map(|k| k.0),
+                tournament_detail_champion::Column::TournamentId,
+                tournament_detail_champion::Column::TournamentServiceId,
+            ))
+            .order_by_asc(tournament_detail_champion::Column::FighterId)
+            .all(&self.conn)
+            .await?;
+
+        Ok(group(
+            keys,
+            rows.into_iter().map(|m| {
+                let key = TournamentKey {
+                    id: m.tournament_id,
+                    service_id: m.tournament_service_id as i32,
+                };
+                (ChampionsOf(key), m)
+            }),
+        ))
+    }
+}
+
+#[async_trait]
+impl Loader<AttacksOf> for DbLoader {
+    type Value = Vec<tournament_detail_attack::Model>;
+    type Error = Arc<DbErr>;
+
+    async fn load(
+        &self,
+        keys: &[AttacksOf],
+    ) -> Result<HashMap<AttacksOf, Self::Value>, Self::Error> {
+        let rows = tournament_detail_attack::Entity::find()
+            .filter(any_tournament::<_, i64>(
+                keys.iter().map(|k| k.0),
+                tournament_detail_attack::Column::TournamentId,
+                tournament_detail_attack::Column::TournamentServiceId,
+            ))
+            .order_by_asc(tournament_detail_attack::Column::Round)
+            .order_by_asc(tournament_detail_attack::Column::Order)
+            .all(&self.conn)
+            .await?;
+
+        Ok(group(
+            keys,
+            rows.into_iter().map(|m| {
+                let key = TournamentKey {
+                    id: m.tournament_id,
+                    service_id: m.tournament_service_id as i32,
+                };
+                (AttacksOf(key), m)
+            }),
+        ))
+    }
+}
@@ -0,0 +1,643 @@
+//! GraphQL schema over the entity relations, so fighters, their lineage, the tournaments they
+//! entered and the battles fought there can be traversed in a single query.
+//!
+//! Relations are resolved through [`DbLoader`], which batches the lookups made for every item of a
+//! list into one query per relation.
+
+use async_graphql::{
+    dataloader::DataLoader,
+    http::{playground_source, GraphQLPlaygroundConfig},
+    Context, EmptyMutation, EmptySubscription, InputObject, Object, Result, SimpleObject,
+};
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use axum::{
+    response::{Html, IntoResponse},
+    Extension,
+};
+use chrono::NaiveDateTime;
+use entity::entities::{
+    fighter, fighter_trait, tournament, tournament_detail_attack, tournament_detail_champion,
+    tournament_fighter,
+};
+use itertools::Itertools;
+use sea_orm::{ActiveEnum, DatabaseConnection};
+
+use crate::{
+    fighters::FighterFilter,
+    hex,
+    pagination::{Page, PageQuery, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE},
+    tournaments::TournamentFilter,
+};
+
+use loader::{
+    AttacksOf, ChampionsOf, ChildrenOf, DbLoader, EntrantsOf, EntriesOf, ParentsOf, TournamentKey,
+    TraitsOf,
+};
+
+pub mod loader;
+
+/// Deepest nesting a query may use, to bound the work a single request can cause.
+const MAX_DEPTH: usize = 16;
+/// Most fields a query may resolve, counting every field once per item of the lists it is in.
+const MAX_COMPLEXITY: usize = 10_000;
+/// Items returned by list fields when `first` is not given.
+const DEFAULT_LIST_SIZE: usize = 20;
+/// Most items a list field may return at once.
+const MAX_LIST_SIZE: usize = 100;
+
+pub type Schema = async_graphql::Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+pub fn schema(conn: DatabaseConnection) -> Schema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(DataLoader::new(DbLoader::new(conn.clone()), tokio::spawn))
+        .data(conn)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+/// `POST /graphql`
+pub async fn execute(Extension(schema): Extension<Schema>, req: GraphQLRequest) -> GraphQLResponse {
+    schema.execute(req.into_inner()).await.into()
+}
+
+/// `GET /graphql`
+pub async fn playground() -> impl IntoResponse {
+    Html(playground_source(GraphQLPlaygroundConfig::new("/graphql")))
+}
+
+fn loader<'a>(ctx: &Context<'a>) -> &'a DataLoader<DbLoader> {
+    ctx.data_unchecked::<DataLoader<DbLoader>>()
+}
+
+async fn load_fighters(ctx: &Context<'_>, ids: Vec<i64>) -> Result<Vec<Fighter>> {
+    let mut fighters = loader(ctx).load_many(ids.iter().copied()).await?;
+    Ok(ids
+        .into_iter()
+        .filter_map(|id| fighters.remove(&id))
+        .map(Fighter)
+        .collect())
+}
+
+#[derive(Debug, InputObject)]
+pub struct PageInput {
+    page_size: Option<u64>,
+    #[graphql(default)]
+    page_index: u64,
+}
+
+/// Items a page may hold, for estimating the complexity of paged fields.
+fn page_size(page: &Option<PageInput>) -> usize {
+    page.as_ref()
+        .and_then(|page| page.page_size)
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .min(MAX_PAGE_SIZE) as usize
+}
+
+/// Items a list field may hold, for estimating its complexity.
+fn list_size(first: Option<usize>) -> usize {
+    first.unwrap_or(DEFAULT_LIST_SIZE).min(MAX_LIST_SIZE)
+}
+
+/// The `first` items of a list field after skipping `after` of them.
+fn slice<T>(items: Vec<T>, first: Option<usize>, after: Option<usize>) -> Result<Vec<T>> {
+    let first = first.unwrap_or(DEFAULT_LIST_SIZE);
+    if first > MAX_LIST_SIZE {
+        return Err(format!("first must be at most {MAX_LIST_SIZE}").into());
+    }
+
+    Ok(items
+        .into_iter()
+        .skip(after.unwrap_or_default())
+        .take(first)
+        .collect())
+}
+
+impl From<Option<PageInput>> for PageQuery {
+    fn from(page: Option<PageInput>) -> Self {
+        page.map_or_else(
+            || PageQuery::new(None, 0),
+            |page| PageQuery::new(page.page_size, page.page_index),
+        )
+    }
+}
+
+#[derive(Debug, SimpleObject)]
+pub struct PageInfo {
+    total_count: u64,
+    total_pages: u64,
+    has_next_page: bool,
+    current_page: u64,
+    item_count: u64,
+}
+
+#[derive(Debug, SimpleObject)]
+pub struct FighterPage {
+    page_info: PageInfo,
+    items: Vec<Fighter>,
+}
+
+#[derive(Debug, SimpleObject)]
+pub struct TournamentPage {
+    page_info: PageInfo,
+    items: Vec<Tournament>,
+}
+
+fn page_info<T>(page: &Page<T>) -> PageInfo {
+    PageInfo {
+        total_count: page.pagination.total_count,
+        total_pages: page.pagination.total_pages,
+        has_next_page: page.pagination.has_next_page,
+        current_page: page.pagination.current_page,
+        item_count: page.pagination.item_count,
+    }
+}
+
+#[derive(Debug, InputObject)]
+pub struct FighterFilterInput {
+    /// Either `value`, matching any trait type, or `type:value`.
+    r#trait: Option<String>,
+    elo_min: Option<i32>,
+    elo_max: Option<i32>,
+}
+
+#[derive(Debug, InputObject)]
+pub struct TournamentFilterInput {
+    service_id: Option<i32>,
+    /// One of `created`, `fought`, `completed` or `cancelled`.
+    status: Option<String>,
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    async fn fighter(&self, ctx: &Context<'_>, id: i64) -> Result<Option<Fighter>> {
+        Ok(loader(ctx).load_one(id).await?.map(Fighter))
+    }
+
+    #[graphql(complexity = "page_size(&page) * child_complexity")]
+    async fn fighters(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<FighterFilterInput>,
+        page: Option<PageInput>,
+    ) -> Result<FighterPage> {
+        let filter = filter.map_or_else(FighterFilter::default, |f| FighterFilter {
+            trait_filter: f.r#trait,
+            elo_min: f.elo_min,
+            elo_max: f.elo_max,
+        });
+
+        let page = PageQuery::from(page)
+            .fetch(
+                ctx.data_unchecked::<DatabaseConnection>(),
+                filter.select().into_model::<fighter::Model>(),
+                Fighter,
+            )
+            .await?;
+
+        Ok(FighterPage {
+            page_info: page_info(&page),
+            items: page.items,
+        })
+    }
+
+    async fn tournament(
+        &self,
+        ctx: &Context<'_>,
+        service_id: i32,
+        id: i64,
+    ) -> Result<Option<Tournament>> {
+        Ok(loader(ctx)
+            .load_one(TournamentKey { id, service_id })
+            .await?
+            .map(Tournament))
+    }
+
+    #[graphql(complexity = "page_size(&page) * child_complexity")]
+    async fn tournaments(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<TournamentFilterInput>,
+        page: Option<PageInput>,
+    ) -> Result<TournamentPage> {
+        let filter = filter.map_or_else(TournamentFilter::default, |f| TournamentFilter {
+            service: f.service_id,
+            status: f.status,
+        });
+
+        let page = PageQuery::from(page)
+            .fetch(
+                ctx.data_unchecked::<DatabaseConnection>(),
+                filter.select()?.into_model::<tournament::Model>(),
+                Tournament,
+            )
+            .await?;
+
+        Ok(TournamentPage {
+            page_info: page_info(&page),
+            items: page.items,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Fighter(fighter::Model);
+
+#[Object]
+impl Fighter {
+    async fn id(&self) -> i64 {
+        self.0.id
+    }
+
+    async fn elo(&self) -> Option<i32> {
+        self.0.elo
+    }
+
+    async fn wisdom_point(&self) -> i32 {
+        self.0.wisdom_point
+    }
+
+    async fn strength(&self) -> [i32; 2] {
+        [self.0.strength_from, self.0.strength_to]
+    }
+
+    async fn attack(&self) -> [i32; 2] {
+        [self.0.attack_from, self.0.attack_to]
+    }
+
+    async fn defence(&self) -> [i32; 2] {
+        [self.0.defence_from, self.0.defence_to]
+    }
+
+    async fn omega(&self) -> [i32; 2] {
+        [self.0.omega_from, self.0.omega_to]
+    }
+
+    async fn mum(&self) -> Option<i64> {
+        self.0.mum
+    }
+
+    async fn last_updated(&self) -> NaiveDateTime {
+        self.0.meta_last_updated
+    }
+
+    async fn traits(&self, ctx: &Context<'_>) -> Result<Vec<Trait>> {
+        Ok(loader(ctx)
+            .load_one(TraitsOf(self.0.id))
+            .await?
+            .unwrap_or_default()
+            .into_iter()
+            .map(Trait)
+            .collect())
+    }
+
+    async fn parents(&self, ctx: &Context<'_>) -> Result<Vec<Fighter>> {
+        let ids = loader(ctx).load_one(ParentsOf(self.0.id)).await?;
+        load_fighters(ctx, ids.unwrap_or_default()).await
+    }
+
+    /// Children by ID, `first` of them after skipping `after`.
+    #[graphql(complexity = "list_size(first) * child_complexity")]
+    async fn children(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<usize>,
+        after: Option<usize>,
+    ) -> Result<Vec<Fighter>> {
+        let ids = loader(ctx).load_one(ChildrenOf(self.0.id)).await?;
+        load_fighters(ctx, slice(ids.unwrap_or_default(), first, after)?).await
+    }
+
+    /// Tournaments the fighter entered, most recent first, `first` of them after skipping `after`.
+    #[graphql(complexity = "list_size(first) * child_complexity")]
+    async fn tournaments(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<usize>,
+        after: Option<usize>,
+    ) -> Result<Vec<Tournament>> {
+        let keys = loader(ctx)
+            .load_one(EntriesOf(self.0.id))
+            .await?
+            .unwrap_or_default();
+        let mut tournaments = loader(ctx).load_many(keys.iter().copied()).await?;
+
+        let tournaments = keys
+            .into_iter()
+            .filter_map(|key| tournaments.remove(&key))
+            .sorted_by(|a, b| b.start_time.cmp(&a.start_time))
+            .map(Tournament)
+            .collect();
+        slice(tournaments, first, after)
+    }
+}
+
+#[derive(Debug)]
+pub struct Trait(fighter_trait::Model);
+
+#[Object]
+impl Trait {
+    async fn trait_type(&self) -> &str {
+        &self.0.trait_type
+    }
+
+    async fn value(&self) -> &str {
+        &self.0.value
+    }
+}
+
+#[derive(Debug)]
+pub struct Tournament(tournament::Model);
+
+impl Tournament {
+    fn key(&self) -> TournamentKey {
+        TournamentKey {
+            id: self.0.id,
+            service_id: self.0.service_id,
+        }
+    }
+}
+
+#[Object]
+impl Tournament {
+    async fn id(&self) -> i64 {
+        self.0.id
+    }
+
+    async fn service_id(&self) -> i32 {
+        self.0.service_id
+    }
+
+    async fn name(&self) -> Option<&str> {
+        self.0.name.as_deref()
+    }
+
+    #[graphql(name = "key")]
+    async fn tournament_key(&self) -> &str {
+        &self.0.key
+    }
+
+    async fn level(&self) -> &str {
+        &self.0.level
+    }
+
+    async fn legacy(&self) -> Option<bool> {
+        self.0.legacy
+    }
+
+    async fn status(&self) -> String {
+        self.0.status.to_value()
+    }
+
+    async fn start_time(&self) -> NaiveDateTime {
+        self.0.start_time
+    }
+
+    async fn modified(&self) -> NaiveDateTime {
+        self.0.modified
+    }
+
+    /// Address of the currency token.
+    async fn currency(&self) -> String {
+        hex(&self.0.currency)
+    }
+
+    async fn fee_percentage(&self) -> i32 {
+        self.0.fee_percentage
+    }
+
+    /// Raw `uint256` amount in the currency's smallest unit.
+    async fn buy_in(&self) -> String {
+        hex(&self.0.buy_in)
+    }
+
+    /// Raw `uint256` amount in the currency's smallest unit.
+    async fn top_up(&self) -> String {
+        hex(&self.0.top_up)
+    }
+
+    async fn restrictions(&self) -> &serde_json::Value {
+        &self.0.restrictions
+    }
+
+    /// Entrants, `first` of them after skipping `after`.
+    #[graphql(complexity = "list_size(first) * child_complexity")]
+    async fn entrants(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<usize>,
+        after: Option<usize>,
+    ) -> Result<Vec<Entrant>> {
+        let entrants = loader(ctx)
+            .load_one(EntrantsOf(self.key()))
+            .await?
+            .unwrap_or_default()
+            .into_iter()
+            .map(Entrant)
+            .collect();
+        slice(entrants, first, after)
+    }
+
+    /// Champions that took part in the battles, along with the stance they took, `first` of them
+    /// after skipping `after`.
+    #[graphql(complexity = "list_size(first) * child_complexity")]
+    async fn champions(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<usize>,
+        after: Option<usize>,
+    ) -> Result<Vec<Champion>> {
+        let champions = loader(ctx)
+            .load_one(ChampionsOf(self.key()))
+            .await?
+            .unwrap_or_default()
+            .into_iter()
+            .map(Champion)
+            .collect();
+        slice(champions, first, after)
+    }
+
+    /// One on one battles in round order, optionally only those `fighter_id` fought in, `first`
+    /// of them after skipping `after`.
+    #[graphql(complexity = "list_size(first) * child_complexity")]
+    async fn battles(
+        &self,
+        ctx: &Context<'_>,
+        fighter_id: Option<i64>,
+        first: Option<usize>,
+        after: Option<usize>,
+    ) -> Result<Vec<Battle>> {
+        let attacks = loader(ctx)
+            .load_one(AttacksOf(self.key()))
+            .await?
+            .unwrap_or_default();
+
+        let battles = Battle::split(attacks)
+            .into_iter()
+            .filter(|battle| fighter_id.is_none_or(|id| battle.fighter_ids().contains(&id)))
+            .collect();
+        slice(battles, first, after)
+    }
+}
+
+#[derive(Debug)]
+pub struct Entrant(tournament_fighter::Model);
+
+#[Object]
+impl Entrant {
+    async fn fighter(&self, ctx: &Context<'_>) -> Result<Option<Fighter>> {
+        Ok(loader(ctx).load_one(self.0.fighter_id).await?.map(Fighter))
+    }
+
+    async fn account(&self) -> Option<String> {
+        self.0.account.as_deref().map(hex)
+    }
+}
+
+#[derive(Debug)]
+pub struct Champion(tournament_detail_champion::Model);
+
+#[Object]
+impl Champion {
+    async fn fighter(&self, ctx: &Context<'_>) -> Result<Option<Fighter>> {
+        Ok(loader(ctx).load_one(self.0.fighter_id).await?.map(Fighter))
+    }
+
+    async fn stance(&self) -> i32 {
+        self.0.stance
+    }
+}
+
+#[derive(Debug)]
+pub struct Battle {
+    round: i32,
+    attacks: Vec<tournament_detail_attack::Model>,
+}
+
+impl Battle {
+    /// Split attacks ordered by round and order into battles. The attacks of a round are stored
+    /// one battle after another with a gap in `order` between them, and a battle also ends when a
+    /// third fighter attacks for details stored before the gap.
+    fn split(attacks: Vec<tournament_detail_attack::Model>) -> Vec<Battle> {
+        let mut battles: Vec<Battle> = Vec::new();
+
+        for attack in attacks {
+            match battles.last_mut() {
+                Some(battle)
+                    if battle.round == attack.round
+                        && battle
+                            .attacks
+                            .last()
+                            .is_some_and(|last| last.order + 1 == attack.order)
+                        && (battle.fighter_ids().contains(&attack.fighter_id)
+                            || battle.fighter_ids().len() < 2) =>
+                {
+                    battle.attacks.push(attack)
+                }
+                _ => battles.push(Battle {
+                    round: attack.round,
+                    attacks: vec![attack],
+                }),
+            }
+        }
+
+        battles
+    }
+
+    fn fighter_ids(&self) -> Vec<i64> {
+        self.attacks.iter().map(|m| m.fighter_id).unique().collect()
+    }
+}
+
+#[Object]
+impl Battle {
+    async fn round(&self) -> i32 {
+        self.round
+    }
+
+    /// The two fighters, in the order they first attacked.
+    async fn fighters(&self, ctx: &Context<'_>) -> Result<Vec<Fighter>> {
+        load_fighters(ctx, self.fighter_ids()).await
+    }
+
+    async fn attacks(&self) -> Vec<Attack> {
+        self.attacks.iter().cloned().map(Attack).collect()
+    }
+}
+
+#[derive(Debug)]
+pub struct Attack(tournament_detail_attack::Model);
+
+#[Object]
+impl Attack {
+    async fn order(&self) -> i32 {
+        self.0.order
+    }
+
+    async fn fighter(&self, ctx: &Context<'_>) -> Result<Option<Fighter>> {
+        Ok(loader(ctx).load_one(self.0.fighter_id).await?.map(Fighter))
+    }
+
+    async fn damage(&self) -> i32 {
+        self.0.damage
+    }
+
+    async fn special_attack(&self) -> bool {
+        self.0.special_attack
+    }
+
+    async fn special_defend(&self) -> bool {
+        self.0.speical_defend
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_fields_are_sliced() {
+        let items = (0..30).collect::<Vec<_>>();
+
+        assert_eq!(
+            slice(items.clone(), None, None).unwrap(),
+            (0..20).collect_vec()
+        );
+        assert_eq!(
+            slice(items.clone(), Some(5), Some(27)).unwrap(),
+            [27, 28, 29]
+        );
+        assert!(slice(items, Some(MAX_LIST_SIZE + 1), None).is_err());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn complex_queries_are_rejected() {
+        // Only whether the query validates matters, so the database does not need any tables
+        let conn = sea_orm::Database::connect("sqlite::memory:").await.unwrap();
+        let schema = schema(conn);
+        let too_complex = |resp: async_graphql::Response| {
+            resp.errors
+                .iter()
+                .any(|e| e.message.contains("too complex"))
+        };
+
+        let resp = schema
+            .execute("{ fighters(page: { pageSize: 500 }) { items { tournaments(first: 100) { id } } } }")
+            .await;
+        assert!(too_complex(resp));
+
+        // Page sizes past the maximum are rejected when fetched, but must not overflow first
+        let resp = schema
+            .execute("{ fighters(page: { pageSize: 18446744073709551615 }) { items { id } } }")
+            .await;
+        assert!(!resp.errors.is_empty());
+
+        let resp = schema
+            .execute("{ fighters { items { tournaments(first: 5) { id } } } }")
+            .await;
+        assert!(!too_complex(resp));
+    }
+}
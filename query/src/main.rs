@@ -0,0 +1,212 @@
+use anyhow::{Context, Result};
+use axum::{routing::get, Extension, Router};
+use sea_orm::{ConnectOptions, Database, DatabaseConnection};
+use std::{env, net::SocketAddr};
+use tracing::info;
+
+pub mod error;
+pub mod fighters;
+pub mod graphql;
+pub mod pagination;
+pub mod tournaments;
+
+const DEFAULT_ADDR: &str = "0.0.0.0:8080";
+
+/// Read-only JSON API over the scraped database.
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv::dotenv().ok();
+    tracing_subscriber::fmt::init();
+
+    let opt = ConnectOptions::new(env::var("DATABASE_URL").context("DATABASE_URL not set")?)
+        .sqlx_logging(false)
+        .to_owned();
+    let database = Database::connect(opt).await?;
+
+    let addr = env::var("QUERY_ADDR")
+        .ok()
+        .filter(|addr| !addr.is_empty())
+        .unwrap_or_else(|| DEFAULT_ADDR.to_owned())
+        .parse::<SocketAddr>()
+        .context("invalid QUERY_ADDR")?;
+
+    let app = app(database);
+
+    info!(addr = ?addr, "serving queries");
+    axum::Server::try_bind(&addr)?
+        .serve(app.into_make_service())
+        .await?;
+
+    Ok(())
+}
+
+fn app(database: DatabaseConnection) -> Router {
+    let schema = graphql::schema(database.clone());
+
+    Router::new()
+        .route("/graphql", get(graphql::playground).post(graphql::execute))
+        .route("/fighters", get(fighters::list_fighters))
+        .route("/fighters/:id", get(fighters::get_fighter))
+        .route("/tournaments", get(tournaments::list_tournaments))
+        .route(
+            "/tournaments/:service/:id",
+            get(tournaments::get_tournament),
+        )
+        .layer(Extension(schema))
+        .with_state(database)
+}
+
+/// `0x` prefixed hex encoding of raw bytes such as addresses and `uint256` amounts.
+pub fn hex(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+
+    bytes.iter().fold("0x".to_owned(), |mut s, b| {
+        let _ = write!(s, "{b:02x}");
+        s
+    })
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use chrono::NaiveDate;
+    use entity::entities::{
+        fighter, fighter_trait, sea_orm_active_enums::TournamentStatus, tournament,
+    };
+    use migration::testing::{self, database};
+    use sea_orm::{EntityTrait, IntoActiveModel};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    async fn get(conn: &DatabaseConnection, uri: &str) -> (StatusCode, Value) {
+        let resp = app(conn.clone())
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = resp.status();
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn fighter(id: i64, elo: i32) -> fighter::Model {
+        fighter::Model {
+            strength_from: 1,
+            strength_to: 2,
+            elo: Some(elo),
+            ..testing::fighter(id)
+        }
+    }
+
+    fn tournament(id: i64, status: TournamentStatus) -> tournament::Model {
+        tournament::Model {
+            currency: vec![0xab],
+            fee_percentage: 5,
+            buy_in: vec![0x01, 0x00],
+            name: Some(format!("tournament {id}")),
+            start_time: NaiveDate::from_ymd_opt(2023, 1, id as u32)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
+            ..testing::tournament(id, 0, status)
+        }
+    }
+
+    fn ork(fighter_id: i64) -> fighter_trait::ActiveModel {
+        fighter_trait::Model {
+            fighter_id,
+            trait_type: "Breed".to_owned(),
+            value: "Ork".to_owned(),
+            numeric_value: None,
+            display_type: None,
+        }
+        .into_active_model()
+    }
+
+    #[tokio::test]
+    async fn fighters_are_filtered_and_paged() {
+        let conn = database().await;
+        fighter::Entity::insert_many(
+            (1..=3).map(|id| fighter(id, id as i32 * 100).into_active_model()),
+        )
+        .exec(&conn)
+        .await
+        .unwrap();
+        fighter_trait::Entity::insert(ork(2))
+            .exec(&conn)
+            .await
+            .unwrap();
+
+        let (status, page) = get(&conn, "/fighters?elo_min=200&page_size=1").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page["total_count"], 2);
+        assert_eq!(page["has_next_page"], true);
+        assert_eq!(page["items"][0]["id"], 2);
+
+        let (_, page) = get(&conn, "/fighters?trait=Breed:Ork").await;
+        assert_eq!(page["total_count"], 1);
+        assert_eq!(page["items"][0]["strength"], json!([1, 2]));
+
+        let (status, body) = get(&conn, "/fighters?page_size=0").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].is_string());
+    }
+
+    #[tokio::test]
+    async fn fighters_are_fetched_with_their_traits() {
+        let conn = database().await;
+        fighter::Entity::insert(fighter(1, 1000).into_active_model())
+            .exec(&conn)
+            .await
+            .unwrap();
+        fighter_trait::Entity::insert(ork(1))
+            .exec(&conn)
+            .await
+            .unwrap();
+
+        let (status, body) = get(&conn, "/fighters/1").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["traits"], json!({ "Breed": "Ork" }));
+        assert_eq!(body["parents"], json!([]));
+
+        let (status, body) = get(&conn, "/fighters/2").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "not found");
+    }
+
+    #[tokio::test]
+    async fn tournaments_are_listed_most_recent_first() {
+        let conn = database().await;
+        tournament::Entity::insert_many([
+            tournament(1, TournamentStatus::Completed).into_active_model(),
+            tournament(2, TournamentStatus::Created).into_active_model(),
+        ])
+        .exec(&conn)
+        .await
+        .unwrap();
+
+        let (status, page) = get(&conn, "/tournaments").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page["total_count"], 2);
+        assert_eq!(page["items"][0]["id"], 2);
+
+        let (_, page) = get(&conn, "/tournaments?status=completed").await;
+        assert_eq!(page["total_count"], 1);
+        assert_eq!(page["items"][0]["buy_in"], "0x0100");
+
+        let (status, _) = get(&conn, "/tournaments?status=bogus").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = get(&conn, "/tournaments/0/1").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["entrants"], json!([]));
+        assert_eq!(body["battles"], json!([]));
+
+        let (status, _) = get(&conn, "/tournaments/1/1").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,73 @@
+use api::Pagination;
+use sea_orm::{ConnectionTrait, PaginatorTrait, Selector, SelectorTrait};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+pub const DEFAULT_PAGE_SIZE: u64 = 50;
+pub const MAX_PAGE_SIZE: u64 = 500;
+
+/// `page_size` and `page_index` query parameters, as used by the federation API.
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct PageQuery {
+    page_size: Option<u64>,
+    #[serde(default)]
+    page_index: u64,
+}
+
+/// A page of items, shaped like the federation API's paginated responses.
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    #[serde(flatten)]
+    pub pagination: Pagination,
+    pub items: Vec<T>,
+}
+
+impl PageQuery {
+    pub fn new(page_size: Option<u64>, page_index: u64) -> Self {
+        Self {
+            page_size,
+            page_index,
+        }
+    }
+
+    /// Fetch the requested page of `select`, converting each row with `f`.
+    pub async fn fetch<'db, C, S, T>(
+        self,
+        conn: &'db C,
+        select: Selector<S>,
+        f: impl FnMut(S::Item) -> T,
+    ) -> Result<Page<T>>
+    where
+        C: ConnectionTrait,
+        S: SelectorTrait + Send + Sync + 'db,
+        Selector<S>: PaginatorTrait<'db, C, Selector = S>,
+    {
+        let page_size = self.page_size.unwrap_or(DEFAULT_PAGE_SIZE);
+        if page_size == 0 || page_size > MAX_PAGE_SIZE {
+            return Err(Error::BadRequest(format!(
+                "page_size must be between 1 and {MAX_PAGE_SIZE}"
+            )));
+        }
+
+        let paginator = select.paginate(conn, page_size);
+        let counts = paginator.num_items_and_pages().await?;
+        let items = paginator
+            .fetch_page(self.page_index)
+            .await?
+            .into_iter()
+            .map(f)
+            .collect::<Vec<_>>();
+
+        Ok(Page {
+            pagination: Pagination {
+                total_count: counts.number_of_items,
+                total_pages: counts.number_of_pages,
+                has_next_page: self.page_index + 1 < counts.number_of_pages,
+                current_page: self.page_index,
+                item_count: items.len() as u64,
+            },
+            items,
+        })
+    }
+}
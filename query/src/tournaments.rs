@@ -0,0 +1,216 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::NaiveDateTime;
+use entity::entities::{
+    sea_orm_active_enums::TournamentStatus, tournament, tournament_detail_attack,
+    tournament_detail_champion, tournament_fighter,
+};
+use itertools::Itertools;
+use sea_orm::{
+    ActiveEnum, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Select,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    error::{Error, Result},
+    hex,
+    pagination::{Page, PageQuery},
+};
+
+#[derive(Debug, Serialize)]
+pub struct Tournament {
+    id: i64,
+    service_id: i32,
+    name: Option<String>,
+    key: String,
+    level: String,
+    legacy: Option<bool>,
+    status: String,
+    start_time: NaiveDateTime,
+    modified: NaiveDateTime,
+    /// Address of the currency token.
+    currency: String,
+    fee_percentage: i32,
+    /// Raw `uint256` amounts in the currency's smallest unit.
+    buy_in: String,
+    top_up: String,
+    restrictions: Value,
+    solo_optionals: Option<Value>,
+}
+
+impl From<tournament::Model> for Tournament {
+    fn from(m: tournament::Model) -> Self {
+        Self {
+            id: m.id,
+            service_id: m.service_id,
+            name: m.name,
+            key: m.key,
+            level: m.level,
+            legacy: m.legacy,
+            status: m.status.to_value(),
+            start_time: m.start_time,
+            modified: m.modified,
+            currency: hex(&m.currency),
+            fee_percentage: m.fee_percentage,
+            buy_in: hex(&m.buy_in),
+            top_up: hex(&m.top_up),
+            restrictions: m.restrictions,
+            solo_optionals: m.solo_optionals,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct TournamentFilter {
+    pub service: Option<i32>,
+    /// One of `created`, `fought`, `completed` or `cancelled`.
+    pub status: Option<String>,
+}
+
+impl TournamentFilter {
+    /// Tournaments matching the filter, most recent first.
+    pub fn select(&self) -> Result<Select<tournament::Entity>> {
+        let mut select = tournament::Entity::find()
+            .order_by_desc(tournament::Column::StartTime)
+            .order_by_asc(tournament::Column::ServiceId)
+            .order_by_asc(tournament::Column::Id);
+
+        if let Some(service) = self.service {
+            select = select.filter(tournament::Column::ServiceId.eq(service));
+        }
+        if let Some(status) = &self.status {
+            let status = TournamentStatus::try_from_value(status)
+                .map_err(|_| Error::BadRequest(format!("unknown status {status}")))?;
+            select = select.filter(tournament::Column::Status.eq(status));
+        }
+
+        Ok(select)
+    }
+}
+
+/// `GET /tournaments`
+pub async fn list_tournaments(
+    State(conn): State<DatabaseConnection>,
+    Query(filter): Query<TournamentFilter>,
+    Query(page): Query<PageQuery>,
+) -> Result<Json<Page<Tournament>>> {
+    let select = filter.select()?;
+
+    let page = page
+        .fetch(
+            &conn,
+            select.into_model::<tournament::Model>(),
+            Tournament::from,
+        )
+        .await?;
+
+    Ok(Json(page))
+}
+
+#[derive(Debug, Serialize)]
+pub struct TournamentDetail {
+    #[serde(flatten)]
+    tournament: Tournament,
+    entrants: Vec<Entrant>,
+    /// Champions that took part in the battles, along with the stance they took.
+    champions: Vec<Champion>,
+    battles: Vec<Round>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Entrant {
+    fighter_id: i64,
+    account: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Champion {
+    fighter_id: i64,
+    stance: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Round {
+    round: i32,
+    attacks: Vec<Attack>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Attack {
+    order: i32,
+    fighter_id: i64,
+    damage: i32,
+    special_attack: bool,
+    special_defend: bool,
+}
+
+/// `GET /tournaments/{service}/{id}`
+pub async fn get_tournament(
+    State(conn): State<DatabaseConnection>,
+    Path((service, id)): Path<(i32, i64)>,
+) -> Result<Json<TournamentDetail>> {
+    let tournament = tournament::Entity::find_by_id((id, service))
+        .one(&conn)
+        .await?
+        .ok_or(Error::NotFound)?;
+
+    let entrants = tournament_fighter::Entity::find()
+        .filter(tournament_fighter::Column::TournamentId.eq(id))
+        .filter(tournament_fighter::Column::TournamentServiceId.eq(service))
+        .order_by_asc(tournament_fighter::Column::FighterId)
+        .all(&conn)
+        .await?
+        .into_iter()
+        .map(|m| Entrant {
+            fighter_id: m.fighter_id,
+            account: m.account.as_deref().map(hex),
+        })
+        .collect();
+
+    let champions = tournament_detail_champion::Entity::find()
+        .filter(tournament_detail_champion::Column::TournamentId.eq(id))
+        .filter(tournament_detail_champion::Column::TournamentServiceId.eq(service))
+        .order_by_asc(tournament_detail_champion::Column::FighterId)
+        .all(&conn)
+        .await?
+        .into_iter()
+        .map(|m| Champion {
+            fighter_id: m.fighter_id,
+            stance: m.stance,
+        })
+        .collect();
+
+    let battles = tournament_detail_attack::Entity::find()
+        .filter(tournament_detail_attack::Column::TournamentId.eq(id))
+        .filter(tournament_detail_attack::Column::TournamentServiceId.eq(service))
+        .order_by_asc(tournament_detail_attack::Column::Round)
+        .order_by_asc(tournament_detail_attack::Column::Order)
+        .all(&conn)
+        .await?
+        .into_iter()
+        .group_by(|m| m.round)
+        .into_iter()
+        .map(|(round, attacks)| Round {
+            round,
+            attacks: attacks
+                .map(|m| Attack {
+                    order: m.order,
+                    fighter_id: m.fighter_id,
+                    damage: m.damage,
+                    special_attack: m.special_attack,
+                    special_defend: m.speical_defend,
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(Json(TournamentDetail {
+        tournament: tournament.into(),
+        entrants,
+        champions,
+        battles,
+    }))
+}
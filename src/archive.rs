@@ -0,0 +1,358 @@
+use anyhow::{anyhow, Result};
+use api::{
+    fighter::FighterResponse, tournament::Tournament, tournament_detail::TournamentDetailResponse,
+};
+use chrono::{DateTime, Utc};
+use entity::entities::{meta_raw_response, meta_raw_response_blob};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use itertools::Itertools;
+use sea_orm::{
+    sea_query::OnConflict, DatabaseConnection, EntityTrait, PaginatorTrait, QueryOrder, Set,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::{
+    io::{Read, Write},
+    path::PathBuf,
+};
+use tracing::{debug, info, instrument, warn};
+
+use crate::{
+    dead_letter::{parse_detail_reference, PayloadKind},
+    task::{fighter::ChampionTask, tournament::TournamentTask},
+};
+
+/// Number of archived responses replayed at a time by [`reingest`].
+const REINGEST_PAGE_SIZE: u64 = 1000;
+
+/// Where compressed response bodies are kept.
+#[derive(Clone, Debug)]
+pub enum ArchiveStore {
+    /// One file per body under the given directory.
+    Disk(PathBuf),
+    /// The `meta_raw_response_blob` table.
+    Database,
+}
+
+impl ArchiveStore {
+    /// Parse the `ARCHIVE` setting: either `database` or a directory.
+    pub fn from_setting(setting: &str) -> Self {
+        match setting {
+            "database" => ArchiveStore::Database,
+            dir => ArchiveStore::Disk(PathBuf::from(dir)),
+        }
+    }
+}
+
+/// Archive of every API response body, kept so that the database can be rebuilt offline.
+///
+/// Bodies are gzip compressed and addressed by the SHA-256 of their JSON, so identical responses are
+/// only stored once. Every fetch is recorded in `meta_raw_response` along with when it happened.
+#[derive(Clone, Debug)]
+pub struct Archive {
+    conn: DatabaseConnection,
+    store: ArchiveStore,
+}
+
+impl Archive {
+    pub fn new(conn: DatabaseConnection, store: ArchiveStore) -> Self {
+        Self { conn, store }
+    }
+
+    /// Archive a response body. Failures are logged but otherwise ignored.
+    pub async fn store(&self, kind: PayloadKind, reference: String, url: String, payload: &Value) {
+        if let Err(e) = self.try_store(kind, &reference, url, payload).await {
+            warn!(kind = ?kind, reference = ?reference, e = ?e, "could not archive response");
+        }
+    }
+
+    async fn try_store(
+        &self,
+        kind: PayloadKind,
+        reference: &str,
+        url: String,
+        payload: &Value,
+    ) -> Result<()> {
+        let (hash, data) = compress(payload)?;
+
+        match &self.store {
+            ArchiveStore::Disk(dir) => {
+                let path = self.path(dir, &hash);
+                if !path.exists() {
+                    if let Some(parent) = path.parent() {
+                        tokio::fs::create_dir_all(parent).await?;
+                    }
+                    tokio::fs::write(&path, data).await?;
+                }
+            }
+            ArchiveStore::Database => {
+                meta_raw_response_blob::Entity::insert(meta_raw_response_blob::ActiveModel {
+                    hash: Set(hash.clone()),
+                    data: Set(data),
+                })
+                .on_conflict(
+                    OnConflict::column(meta_raw_response_blob::Column::Hash)
+                        .do_nothing()
+                        .to_owned(),
+                )
+                .exec_without_returning(&self.conn)
+                .await?;
+            }
+        }
+
+        meta_raw_response::Entity::insert(meta_raw_response::ActiveModel {
+            kind: Set(kind.as_str().to_owned()),
+            reference: Set(reference.to_owned()),
+            url: Set(url),
+            hash: Set(hash),
+            fetched_at: Set(Utc::now().naive_utc()),
+            ..Default::default()
+        })
+        .exec(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Load an archived response body.
+    async fn load(&self, hash: &str) -> Result<Value> {
+        let data = match &self.store {
+            ArchiveStore::Disk(dir) => tokio::fs::read(self.path(dir, hash)).await?,
+            ArchiveStore::Database => {
+                meta_raw_response_blob::Entity::find_by_id(hash.to_owned())
+                    .one(&self.conn)
+                    .await?
+                    .ok_or_else(|| anyhow!("blob {hash} not found"))?
+                    .data
+            }
+        };
+
+        decompress(&data)
+    }
+
+    fn path(&self, dir: &std::path::Path, hash: &str) -> PathBuf {
+        dir.join(&hash[..2]).join(format!("{hash}.json.gz"))
+    }
+}
+
+/// The SHA-256 of a payload's JSON, and the JSON gzip compressed.
+fn compress(payload: &Value) -> Result<(String, Vec<u8>)> {
+    let body = serde_json::to_vec(payload)?;
+    let hash = format!("{:x}", Sha256::digest(&body));
+
+    let mut encoder = GzEncoder::new(vec![], Compression::default());
+    encoder.write_all(&body)?;
+    Ok((hash, encoder.finish()?))
+}
+
+fn decompress(data: &[u8]) -> Result<Value> {
+    let mut body = vec![];
+    GzDecoder::new(data).read_to_end(&mut body)?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+/// Rebuild the fighter, tournament and tournament detail tables from the archive.
+///
+/// Responses are replayed in the order they were fetched, without touching the network.
+#[instrument(skip_all)]
+pub async fn reingest(
+    conn: &DatabaseConnection,
+    archive: &Archive,
+    champion_task: &ChampionTask,
+    tournament_task: &TournamentTask,
+) -> Result<()> {
+    info!("beginning reingest");
+
+    let mut pages = meta_raw_response::Entity::find()
+        .order_by_asc(meta_raw_response::Column::FetchedAt)
+        .order_by_asc(meta_raw_response::Column::Id)
+        .paginate(conn, REINGEST_PAGE_SIZE);
+
+    let mut count = 0;
+
+    while let Some(responses) = pages.fetch_and_next().await? {
+        let mut champions = vec![];
+        let mut tournaments = vec![];
+        let mut details = vec![];
+
+        for response in responses {
+            let payload = match archive.load(&response.hash).await {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!(id = response.id, hash = ?response.hash, e = ?e, "could not load archived response");
+                    continue;
+                }
+            };
+
+            let fetched_at = DateTime::<Utc>::from_naive_utc_and_offset(response.fetched_at, Utc);
+            let parsed = match response.kind.parse() {
+                Ok(PayloadKind::Fighter) => <FighterResponse as Deserialize>::deserialize(&payload)
+                    .map(|f| champions.push((f, fetched_at))),
+                Ok(PayloadKind::Tournament) => <Tournament as Deserialize>::deserialize(&payload)
+                    .map(|t| tournaments.push((t, fetched_at))),
+                Ok(PayloadKind::TournamentDetail) => {
+                    let Some((service_id, id)) = parse_detail_reference(&response.reference) else {
+                        warn!(id = response.id, reference = ?response.reference, "invalid tournament detail reference");
+                        continue;
+                    };
+
+                    <TournamentDetailResponse as Deserialize>::deserialize(&payload)
+                        .map(|d| details.push((id, service_id, d)))
+                }
+                Err(e) => {
+                    warn!(id = response.id, e = ?e, "unknown archived response kind");
+                    continue;
+                }
+            };
+
+            if let Err(e) = parsed {
+                warn!(id = response.id, kind = ?response.kind, reference = ?response.reference, e = ?e, "could not deserialize archived response");
+            }
+        }
+
+        count += champions.len() + tournaments.len() + details.len();
+
+        // A response may have been fetched several times within a page. Keep the latest of each
+        // so that the upserts below don't touch the same row twice.
+        let champions = champions
+            .into_iter()
+            .rev()
+            .unique_by(|(f, _)| f.attributes.id)
+            .collect::<Vec<_>>();
+        let tournaments = tournaments
+            .into_iter()
+            .rev()
+            .unique_by(|(t, _)| (t.service_id(), t.id()))
+            .collect::<Vec<_>>();
+
+        // Tournaments reference champions and details reference tournaments, so insert in that
+        // order
+        if let Err(e) = champion_task.insert_champions(champions).await {
+            warn!(e = ?e, "could not insert champions");
+        }
+        if let Err(e) = tournament_task.insert_tournament_batch(tournaments).await {
+            warn!(e = ?e, "could not insert tournaments");
+        }
+        for (id, service_id, detail) in details {
+            if let Err(e) = tournament_task
+                .ingest_tournament_detail(id, service_id, detail)
+                .await
+            {
+                warn!(e = ?e, id = id, service_id = service_id, "could not insert tournament detail");
+            }
+        }
+
+        debug!(count = count, "reingested");
+    }
+
+    info!(count = count, "reingest complete");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn payloads_are_addressed_by_their_json() {
+        let payload = json!({"id": 5118, "name": "Champion"});
+        let (hash, data) = compress(&payload).unwrap();
+
+        assert_eq!(
+            hash,
+            format!("{:x}", Sha256::digest(br#"{"id":5118,"name":"Champion"}"#))
+        );
+        assert_eq!(compress(&payload).unwrap().0, hash);
+        assert_ne!(compress(&json!({"id": 5119})).unwrap().0, hash);
+        assert_eq!(decompress(&data).unwrap(), payload);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn identical_responses_are_stored_once() {
+        let conn = crate::testing::database().await;
+        let dir = std::env::temp_dir().join(format!("archive-{}", std::process::id()));
+        let payload = json!({"id": 5118});
+        let (hash, _) = compress(&payload).unwrap();
+
+        for store in [ArchiveStore::Database, ArchiveStore::Disk(dir.clone())] {
+            let archive = Archive::new(conn.clone(), store);
+            for reference in ["5118", "5118", "again"] {
+                archive
+                    .try_store(PayloadKind::Fighter, reference, String::new(), &payload)
+                    .await
+                    .unwrap();
+            }
+            assert_eq!(archive.load(&hash).await.unwrap(), payload);
+        }
+
+        let blobs = meta_raw_response_blob::Entity::find()
+            .all(&conn)
+            .await
+            .unwrap();
+        assert_eq!(blobs.len(), 1);
+        assert_eq!(blobs[0].hash, hash);
+        assert_eq!(std::fs::read_dir(dir.join(&hash[..2])).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let responses = meta_raw_response::Entity::find().all(&conn).await.unwrap();
+        assert_eq!(responses.len(), 6);
+        assert!(responses.iter().all(|r| r.hash == hash));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn reingest_keeps_when_responses_were_fetched() {
+        use chrono::NaiveDate;
+        use entity::entities::tournament;
+        use sea_orm::{ActiveModelTrait, IntoActiveModel};
+
+        let conn = crate::testing::database().await;
+        let archive = Archive::new(conn.clone(), ArchiveStore::Database);
+        let mut page: Value =
+            serde_json::from_str(include_str!("../api/src/tests/blooding.json")).unwrap();
+        let mut payload = page["items"][0].take();
+        payload["warriors"] = json!([]);
+        archive
+            .try_store(
+                PayloadKind::Tournament,
+                "1/-1003252",
+                String::new(),
+                &payload,
+            )
+            .await
+            .unwrap();
+
+        let fetched_at = NaiveDate::from_ymd_opt(2022, 3, 28)
+            .unwrap()
+            .and_hms_opt(4, 0, 0)
+            .unwrap();
+        let mut response = meta_raw_response::Entity::find()
+            .one(&conn)
+            .await
+            .unwrap()
+            .unwrap()
+            .into_active_model();
+        response.fetched_at = Set(fetched_at);
+        response.update(&conn).await.unwrap();
+
+        let client = crate::testing::client();
+        reingest(
+            &conn,
+            &archive,
+            &ChampionTask::new(client.clone(), conn.clone(), String::new(), None),
+            &TournamentTask::new(client, conn.clone(), 100, None),
+        )
+        .await
+        .unwrap();
+
+        let tournament = tournament::Entity::find_by_id((-1003252, 1))
+            .one(&conn)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(tournament.meta_last_updated, fetched_at);
+    }
+}
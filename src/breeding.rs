@@ -0,0 +1,314 @@
+use anyhow::Result;
+use entity::entities::{
+    breeding_trait_frequency, breeding_wisdom_frequency, fighter, fighter_trait,
+};
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    QueryOrder, Statement, TransactionTrait,
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tracing::{info, instrument};
+
+/// Width of the buckets wisdom stats are grouped into.
+pub const WISDOM_BUCKET_WIDTH: i32 = 10;
+
+/// Wisdom columns of `fighter` that are compared between parents and children.
+const WISDOM_STATS: [&str; 9] = [
+    "wisdom_point",
+    "strength_from",
+    "strength_to",
+    "attack_from",
+    "attack_to",
+    "defence_from",
+    "defence_to",
+    "omega_from",
+    "omega_to",
+];
+
+/// Children with exactly two known parents, the lower parent ID first.
+const PAIRS: &str = r#"
+    pairs (child, a, b) AS (
+        SELECT fighter_id, MIN(parent_id), MAX(parent_id)
+        FROM fighter_parent
+        GROUP BY fighter_id
+        HAVING COUNT(*) = 2
+    )"#;
+
+/// Rebuild the inheritance frequency tables from every summon in the database.
+///
+/// Parent values are stored as an unordered pair (`a <= b`), so a pair of parents can be looked
+/// up without caring which one was the mum.
+#[instrument(skip_all)]
+pub async fn refresh(conn: &DatabaseConnection) -> Result<()> {
+    let backend = conn.get_database_backend();
+
+    let traits = format!(
+        r#"INSERT INTO breeding_trait_frequency
+            (trait_type, parent_value_a, parent_value_b, child_value, count)
+        WITH {PAIRS}
+        SELECT child.trait_type,
+            CASE WHEN a.value <= b.value THEN a.value ELSE b.value END,
+            CASE WHEN a.value <= b.value THEN b.value ELSE a.value END,
+            child.value, COUNT(*)
+        FROM pairs
+        JOIN fighter_trait child ON child.fighter_id = pairs.child
+        JOIN fighter_trait a ON a.fighter_id = pairs.a AND a.trait_type = child.trait_type
+        JOIN fighter_trait b ON b.fighter_id = pairs.b AND b.trait_type = child.trait_type
+        GROUP BY 1, 2, 3, 4"#
+    );
+
+    let stats = WISDOM_STATS
+        .iter()
+        .map(|stat| {
+            format!(
+                "SELECT id, '{stat}' AS stat, {stat} / {WISDOM_BUCKET_WIDTH} * {WISDOM_BUCKET_WIDTH} AS bucket FROM fighter"
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n        UNION ALL ");
+    let wisdom = format!(
+        r#"INSERT INTO breeding_wisdom_frequency
+            (stat, parent_bucket_a, parent_bucket_b, child_bucket, count)
+        WITH {PAIRS},
+        stats (id, stat, bucket) AS (
+            {stats}
+        )
+        SELECT child.stat,
+            CASE WHEN a.bucket <= b.bucket THEN a.bucket ELSE b.bucket END,
+            CASE WHEN a.bucket <= b.bucket THEN b.bucket ELSE a.bucket END,
+            child.bucket, COUNT(*)
+        FROM pairs
+        JOIN stats child ON child.id = pairs.child
+        JOIN stats a ON a.id = pairs.a AND a.stat = child.stat
+        JOIN stats b ON b.id = pairs.b AND b.stat = child.stat
+        GROUP BY 1, 2, 3, 4"#
+    );
+
+    conn.transaction::<_, (), DbErr>(|txn| {
+        Box::pin(async move {
+            breeding_trait_frequency::Entity::delete_many()
+                .exec(txn)
+                .await?;
+            breeding_wisdom_frequency::Entity::delete_many()
+                .exec(txn)
+                .await?;
+
+            txn.execute(Statement::from_string(backend, traits)).await?;
+            txn.execute(Statement::from_string(backend, wisdom)).await?;
+
+            Ok(())
+        })
+    })
+    .await?;
+
+    info!("refreshed breeding frequency tables");
+
+    Ok(())
+}
+
+/// How often a child ended up with `value`, out of every child of a similar pair.
+#[derive(Clone, Debug, Serialize)]
+pub struct Outcome<T> {
+    pub value: T,
+    pub count: i64,
+    pub probability: f64,
+}
+
+/// Estimated outcomes of summoning with two parents, based on past summons from parents with the
+/// same trait values and wisdom buckets.
+#[derive(Debug, Serialize)]
+pub struct Estimate {
+    pub parents: [i64; 2],
+    /// Child trait values by trait type.
+    pub traits: BTreeMap<String, Vec<Outcome<String>>>,
+    /// Lower bounds of child wisdom buckets by stat.
+    pub wisdom: BTreeMap<String, Vec<Outcome<i32>>>,
+}
+
+fn outcomes<T>(rows: impl IntoIterator<Item = (T, i64)>) -> Vec<Outcome<T>> {
+    let rows = rows.into_iter().collect::<Vec<_>>();
+    let total = rows.iter().map(|(_, count)| count).sum::<i64>() as f64;
+
+    rows.into_iter()
+        .map(|(value, count)| Outcome {
+            value,
+            count,
+            probability: count as f64 / total,
+        })
+        .collect()
+}
+
+fn bucket(value: i32) -> i32 {
+    value / WISDOM_BUCKET_WIDTH * WISDOM_BUCKET_WIDTH
+}
+
+fn wisdom_stats(m: &fighter::Model) -> [i32; 9] {
+    [
+        m.wisdom_point,
+        m.strength_from,
+        m.strength_to,
+        m.attack_from,
+        m.attack_to,
+        m.defence_from,
+        m.defence_to,
+        m.omega_from,
+        m.omega_to,
+    ]
+}
+
+/// Estimate the outcome probabilities of summoning with parents `a` and `b`. Trait types or stats
+/// without any comparable past summon are left out.
+pub async fn estimate(conn: &DatabaseConnection, a: i64, b: i64) -> Result<Estimate> {
+    let traits_of = |id: i64| {
+        fighter_trait::Entity::find()
+            .filter(fighter_trait::Column::FighterId.eq(id))
+            .all(conn)
+    };
+    let traits_a = traits_of(a).await?;
+    let traits_b = traits_of(b).await?;
+
+    let mut traits = BTreeMap::new();
+    for trait_a in &traits_a {
+        let Some(trait_b) = traits_b.iter().find(|t| t.trait_type == trait_a.trait_type) else {
+            continue;
+        };
+        // The pair was ordered by the database's collation, which need not agree with Rust's
+        let pair = |low: &str, high: &str| {
+            Condition::all()
+                .add(breeding_trait_frequency::Column::ParentValueA.eq(low))
+                .add(breeding_trait_frequency::Column::ParentValueB.eq(high))
+        };
+
+        let rows = breeding_trait_frequency::Entity::find()
+            .filter(breeding_trait_frequency::Column::TraitType.eq(trait_a.trait_type.as_str()))
+            .filter(
+                Condition::any()
+                    .add(pair(&trait_a.value, &trait_b.value))
+                    .add(pair(&trait_b.value, &trait_a.value)),
+            )
+            .order_by_desc(breeding_trait_frequency::Column::Count)
+            .order_by_asc(breeding_trait_frequency::Column::ChildValue)
+            .all(conn)
+            .await?;
+        if !rows.is_empty() {
+            traits.insert(
+                trait_a.trait_type.clone(),
+                outcomes(rows.into_iter().map(|m| (m.child_value, m.count))),
+            );
+        }
+    }
+
+    let mut wisdom = BTreeMap::new();
+    let fighters = fighter::Entity::find()
+        .filter(fighter::Column::Id.is_in([a, b]))
+        .all(conn)
+        .await?;
+    if let [fighter_a, fighter_b] = fighters.as_slice() {
+        let stats_a = wisdom_stats(fighter_a);
+        let stats_b = wisdom_stats(fighter_b);
+
+        for (i, stat) in WISDOM_STATS.iter().enumerate() {
+            let (low, high) = (
+                bucket(stats_a[i].min(stats_b[i])),
+                bucket(stats_a[i].max(stats_b[i])),
+            );
+
+            let rows = breeding_wisdom_frequency::Entity::find()
+                .filter(breeding_wisdom_frequency::Column::Stat.eq(*stat))
+                .filter(breeding_wisdom_frequency::Column::ParentBucketA.eq(low))
+                .filter(breeding_wisdom_frequency::Column::ParentBucketB.eq(high))
+                .order_by_desc(breeding_wisdom_frequency::Column::Count)
+                .order_by_asc(breeding_wisdom_frequency::Column::ChildBucket)
+                .all(conn)
+                .await?;
+            if !rows.is_empty() {
+                wisdom.insert(
+                    stat.to_string(),
+                    outcomes(rows.into_iter().map(|m| (m.child_bucket, m.count))),
+                );
+            }
+        }
+    }
+
+    Ok(Estimate {
+        parents: [a, b],
+        traits,
+        wisdom,
+    })
+}
+
+/// Print the estimated outcomes of summoning with `a` and `b` to stdout as JSON.
+pub async fn print_estimate(conn: &DatabaseConnection, a: i64, b: i64) -> Result<()> {
+    let estimate = estimate(conn, a, b).await?;
+    println!("{}", serde_json::to_string_pretty(&estimate)?);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outcomes_are_relative_to_all_children_of_the_pair() {
+        let outcomes = outcomes([("Elf", 3), ("Human", 1)]);
+
+        assert_eq!(outcomes[0].probability, 0.75);
+        assert_eq!(outcomes[1].probability, 0.25);
+    }
+
+    #[test]
+    fn buckets_round_down() {
+        assert_eq!(bucket(0), 0);
+        assert_eq!(bucket(39), 30);
+        assert_eq!(bucket(40), 40);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn pairs_are_found_whatever_order_they_were_stored_in() {
+        use crate::testing;
+        use sea_orm::IntoActiveModel;
+
+        let conn = testing::database().await;
+        fighter::Entity::insert_many([1, 2].map(|id| testing::fighter(id).into_active_model()))
+            .exec(&conn)
+            .await
+            .unwrap();
+        fighter_trait::Entity::insert_many([(1, "Darkblade"), (2, "Dark Elf")].map(
+            |(fighter_id, value)| {
+                fighter_trait::Model {
+                    fighter_id,
+                    trait_type: "Breed".to_owned(),
+                    value: value.to_owned(),
+                    numeric_value: None,
+                    display_type: None,
+                }
+                .into_active_model()
+            },
+        ))
+        .exec(&conn)
+        .await
+        .unwrap();
+
+        // Ordered as a case and space insensitive collation would, unlike Rust's byte order
+        breeding_trait_frequency::Entity::insert(
+            breeding_trait_frequency::Model {
+                trait_type: "Breed".to_owned(),
+                parent_value_a: "Darkblade".to_owned(),
+                parent_value_b: "Dark Elf".to_owned(),
+                child_value: "Dark Elf".to_owned(),
+                count: 3,
+            }
+            .into_active_model(),
+        )
+        .exec(&conn)
+        .await
+        .unwrap();
+
+        let estimate = estimate(&conn, 1, 2).await.unwrap();
+        assert_eq!(estimate.traits["Breed"][0].value, "Dark Elf");
+        assert_eq!(estimate.traits["Breed"][0].count, 3);
+    }
+}
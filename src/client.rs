@@ -0,0 +1,406 @@
+use backoff::{Error, ExponentialBackoff};
+use chrono::{DateTime, Utc};
+use reqwest::{header::RETRY_AFTER, Response, Url};
+use serde::de::DeserializeOwned;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::Notify;
+use tracing::{debug, instrument, warn};
+
+use crate::{error::ScrapeError, metrics};
+
+pub const FEDERATION_HOST: &str = "federation22.theredvillage.com";
+pub const ALCHEMY_HOST: &str = "polygon-mainnet.g.alchemy.com";
+
+/// How hard a single host may be hit.
+#[derive(Clone, Copy, Debug)]
+pub struct HostLimit {
+    /// Sustained requests per second.
+    pub requests_per_second: f64,
+    /// Requests that may be sent at once after a quiet period.
+    pub burst: f64,
+    /// Upper bound for the number of requests in flight. The actual bound adapts between 1 and
+    /// this depending on how the host is coping.
+    pub max_concurrency: usize,
+}
+
+/// HTTP client shared by all tasks.
+///
+/// Requests to hosts with a [`HostLimit`] go through that host's token bucket and concurrency
+/// limit. Transient [`ScrapeError`]s are retried, honouring `Retry-After`, while anything else is
+/// returned straight away.
+#[derive(Clone, Debug)]
+pub struct Client {
+    inner: reqwest::Client,
+    limiters: Arc<HashMap<String, HostLimiter>>,
+    origins: Arc<HashMap<String, Url>>,
+}
+
+impl Client {
+    pub fn new(inner: reqwest::Client, limits: HashMap<String, HostLimit>) -> Self {
+        let limiters = limits
+            .into_iter()
+            .map(|(host, limit)| (host, HostLimiter::new(limit)))
+            .collect();
+
+        Self {
+            inner,
+            limiters: Arc::new(limiters),
+            origins: Default::default(),
+        }
+    }
+
+    /// Send requests for `host` to `origin` instead, such as a local stand-in for it. The host's
+    /// limits still apply.
+    pub fn with_origin(mut self, host: &str, origin: Url) -> Self {
+        Arc::make_mut(&mut self.origins).insert(host.to_owned(), origin);
+        self
+    }
+
+    /// GET `url` and deserialize the JSON response. `endpoint` names the request in metrics.
+    #[instrument(skip(self, query))]
+    pub async fn get_json<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        url: &str,
+        query: &[(&str, String)],
+    ) -> Result<T, ScrapeError> {
+        let limiter = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().and_then(|host| self.limiters.get(host)));
+
+        backoff::future::retry(ExponentialBackoff::default(), || async {
+            let result = self.try_get_json(limiter, endpoint, url, query).await;
+
+            if let Some(limiter) = limiter {
+                match &result {
+                    Ok(_) => limiter.succeeded(),
+                    Err(e) if e.is_transient() => {
+                        limiter.overloaded();
+                        if let Some(duration) = e.retry_after() {
+                            limiter.pause(duration);
+                        }
+                    }
+                    Err(_) => {}
+                }
+            }
+
+            result.map_err(|e| {
+                if !e.is_transient() {
+                    return Error::Permanent(e);
+                }
+
+                warn!(url = url, e = ?e, "request failed, trying again");
+                metrics::RETRIES.with_label_values(&[endpoint]).inc();
+                match e.retry_after() {
+                    Some(duration) => Error::retry_after(e, duration),
+                    None => Error::transient(e),
+                }
+            })
+        })
+        .await
+    }
+
+    async fn try_get_json<T: DeserializeOwned>(
+        &self,
+        limiter: Option<&HostLimiter>,
+        endpoint: &str,
+        url: &str,
+        query: &[(&str, String)],
+    ) -> Result<T, ScrapeError> {
+        let _permit = match limiter {
+            Some(limiter) => Some(limiter.acquire().await),
+            None => None,
+        };
+
+        debug!(url = url, "sending request");
+        let resp = self
+            .inner
+            .get(self.resolve(url))
+            .query(query)
+            .send()
+            .await
+            .map_err(|e| {
+                metrics::REQUESTS
+                    .with_label_values(&[endpoint, "error"])
+                    .inc();
+                ScrapeError::Network(e)
+            })?;
+
+        metrics::REQUESTS
+            .with_label_values(&[endpoint, resp.status().as_str()])
+            .inc();
+
+        let retry_after = retry_after(&resp);
+        let body = resp
+            .error_for_status()
+            .map_err(|e| ScrapeError::from_status(e, retry_after))?
+            .bytes()
+            .await
+            .map_err(ScrapeError::Network)?;
+
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// `url` with its origin replaced if its host was given another one.
+    fn resolve(&self, url: &str) -> String {
+        let Ok(mut resolved) = Url::parse(url) else {
+            return url.to_owned();
+        };
+        let Some(origin) = resolved.host_str().and_then(|host| self.origins.get(host)) else {
+            return url.to_owned();
+        };
+
+        let _ = resolved.set_scheme(origin.scheme());
+        let _ = resolved.set_host(origin.host_str());
+        let _ = resolved.set_port(origin.port());
+        resolved.into()
+    }
+}
+
+/// How long the host asked us to wait, either as a number of seconds or as an HTTP date.
+pub(crate) fn retry_after(resp: &Response) -> Option<Duration> {
+    let value = resp.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let at = DateTime::parse_from_rfc2822(value)
+        .ok()?
+        .with_timezone(&Utc);
+    (at - Utc::now()).to_std().ok()
+}
+
+/// Rate and concurrency limits for a single host.
+#[derive(Debug)]
+struct HostLimiter {
+    bucket: Mutex<Bucket>,
+    concurrency: Mutex<Concurrency>,
+    released: Notify,
+}
+
+impl HostLimiter {
+    fn new(limit: HostLimit) -> Self {
+        Self {
+            bucket: Mutex::new(Bucket::new(limit, Instant::now())),
+            concurrency: Mutex::new(Concurrency::new(limit.max_concurrency)),
+            released: Notify::new(),
+        }
+    }
+
+    /// Wait for a free slot and a token.
+    async fn acquire(&self) -> Permit<'_> {
+        loop {
+            let released = self.released.notified();
+            if self.concurrency.lock().unwrap().try_acquire() {
+                break;
+            }
+            released.await;
+        }
+        let permit = Permit(self);
+
+        loop {
+            let wait = self.bucket.lock().unwrap().take(Instant::now());
+            match wait {
+                Ok(()) => return permit,
+                Err(duration) => tokio::time::sleep(duration).await,
+            }
+        }
+    }
+
+    fn succeeded(&self) {
+        self.concurrency.lock().unwrap().succeeded();
+        self.released.notify_waiters();
+    }
+
+    fn overloaded(&self) {
+        let limit = self.concurrency.lock().unwrap().overloaded();
+        debug!(limit = limit, "reduced concurrency");
+    }
+
+    fn pause(&self, duration: Duration) {
+        let now = Instant::now();
+        self.bucket.lock().unwrap().pause(now, now + duration);
+    }
+}
+
+/// A request slot, given back when dropped.
+struct Permit<'a>(&'a HostLimiter);
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.0.concurrency.lock().unwrap().release();
+        self.0.released.notify_waiters();
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    updated: Instant,
+    paused_until: Option<Instant>,
+}
+
+impl Bucket {
+    fn new(limit: HostLimit, now: Instant) -> Self {
+        Self {
+            rate: limit.requests_per_second,
+            burst: limit.burst.max(1.0),
+            tokens: limit.burst.max(1.0),
+            updated: now,
+            paused_until: None,
+        }
+    }
+
+    /// Take a token, or return how long to wait before one is available.
+    fn take(&mut self, now: Instant) -> Result<(), Duration> {
+        if let Some(until) = self.paused_until {
+            if now < until {
+                return Err(until - now);
+            }
+            self.paused_until = None;
+        }
+
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.updated = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+        }
+    }
+
+    /// Hand out no tokens until `until`.
+    fn pause(&mut self, now: Instant, until: Instant) {
+        self.paused_until = self.paused_until.max(Some(until));
+        // Don't let tokens pile up while paused
+        self.tokens = 0.0;
+        self.updated = now;
+    }
+}
+
+/// Additive increase, multiplicative decrease limit on requests in flight.
+#[derive(Debug)]
+struct Concurrency {
+    limit: usize,
+    max: usize,
+    in_flight: usize,
+    successes: usize,
+}
+
+impl Concurrency {
+    fn new(max: usize) -> Self {
+        let max = max.max(1);
+        Self {
+            limit: max,
+            max,
+            in_flight: 0,
+            successes: 0,
+        }
+    }
+
+    fn try_acquire(&mut self) -> bool {
+        if self.in_flight < self.limit {
+            self.in_flight += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn release(&mut self) {
+        self.in_flight -= 1;
+    }
+
+    /// Raise the limit by one once a full limit's worth of requests have succeeded.
+    fn succeeded(&mut self) {
+        self.successes += 1;
+        if self.successes >= self.limit {
+            self.successes = 0;
+            self.limit = (self.limit + 1).min(self.max);
+        }
+    }
+
+    /// Halve the limit.
+    fn overloaded(&mut self) -> usize {
+        self.successes = 0;
+        self.limit = (self.limit / 2).max(1);
+        self.limit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: HostLimit = HostLimit {
+        requests_per_second: 2.0,
+        burst: 2.0,
+        max_concurrency: 8,
+    };
+
+    #[test]
+    fn bucket_refills_at_rate() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(LIMIT, start);
+
+        assert!(bucket.take(start).is_ok());
+        assert!(bucket.take(start).is_ok());
+        assert_eq!(bucket.take(start), Err(Duration::from_millis(500)));
+        assert!(bucket.take(start + Duration::from_millis(500)).is_ok());
+    }
+
+    #[test]
+    fn bucket_waits_out_pause() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(LIMIT, start);
+
+        bucket.pause(start, start + Duration::from_secs(3));
+        assert_eq!(bucket.take(start), Err(Duration::from_secs(3)));
+        assert!(bucket.take(start + Duration::from_secs(3)).is_ok());
+    }
+
+    #[test]
+    fn hosts_are_sent_to_their_origin() {
+        let client = Client::new(reqwest::Client::new(), HashMap::new())
+            .with_origin(FEDERATION_HOST, "http://127.0.0.1:8080".parse().unwrap());
+
+        assert_eq!(
+            client.resolve(&format!("https://{FEDERATION_HOST}/api/v2/champions/id/1")),
+            "http://127.0.0.1:8080/api/v2/champions/id/1"
+        );
+        assert_eq!(
+            client.resolve(&format!("https://{ALCHEMY_HOST}/nft/v2")),
+            format!("https://{ALCHEMY_HOST}/nft/v2")
+        );
+    }
+
+    #[test]
+    fn concurrency_adapts() {
+        let mut concurrency = Concurrency::new(8);
+
+        assert_eq!(concurrency.overloaded(), 4);
+        assert_eq!(concurrency.overloaded(), 2);
+
+        for _ in 0..2 {
+            concurrency.succeeded();
+        }
+        assert_eq!(concurrency.limit, 3);
+
+        assert!(concurrency.try_acquire());
+        assert!(concurrency.try_acquire());
+        assert!(concurrency.try_acquire());
+        assert!(!concurrency.try_acquire());
+        concurrency.release();
+        assert!(concurrency.try_acquire());
+    }
+}
@@ -0,0 +1,705 @@
+use anyhow::Result;
+use chrono::Utc;
+use entity::entities::{fighter, fighter_combat, fighter_round_damage, tournament_detail_attack};
+use itertools::Itertools;
+use sea_orm::{
+    sea_query::{Expr, OnConflict},
+    ActiveValue::Set,
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, FromQueryResult,
+    QueryFilter, QueryOrder, Statement, TransactionTrait, Value,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tracing::{info, instrument};
+
+use crate::{metrics, rating};
+
+/// Fighters and tournaments loaded per query.
+const CHUNK: usize = 100;
+
+/// Fighters with attacks whose aggregates are missing, e.g. from before they were kept.
+const MISSING: &str = r#"
+    SELECT DISTINCT fighter_id AS id FROM tournament_detail_attack
+    JOIN fighter ON fighter.id = tournament_detail_attack.fighter_id
+    WHERE NOT EXISTS (
+        SELECT 1 FROM fighter_combat
+        WHERE fighter_combat.fighter_id = tournament_detail_attack.fighter_id
+    )"#;
+
+#[derive(Debug, FromQueryResult)]
+struct Id {
+    id: i64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct TournamentKey {
+    tournament_id: i64,
+    tournament_service_id: i64,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RoundDamage {
+    pub attacks: i64,
+    pub hits: i64,
+    pub damage: i64,
+}
+
+/// Counts behind a fighter's aggregates. An attack without damage is a miss, and an attack
+/// flagged `speical_defend` was met with a special defend by the fighter it targeted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub attacks: i64,
+    pub hits: i64,
+    pub damage: i64,
+    pub special_attacks: i64,
+    pub attacks_received: i64,
+    pub special_defends: i64,
+    pub damage_taken: i64,
+    pub hits_below_attack_range: i64,
+    pub hits_above_attack_range: i64,
+    pub rounds: BTreeMap<i32, RoundDamage>,
+}
+
+impl Counts {
+    /// Take `other` away from these counts.
+    fn subtract(&mut self, other: &Counts) {
+        self.attacks -= other.attacks;
+        self.hits -= other.hits;
+        self.damage -= other.damage;
+        self.special_attacks -= other.special_attacks;
+        self.attacks_received -= other.attacks_received;
+        self.special_defends -= other.special_defends;
+        self.damage_taken -= other.damage_taken;
+        self.hits_below_attack_range -= other.hits_below_attack_range;
+        self.hits_above_attack_range -= other.hits_above_attack_range;
+        for (round, damage) in &other.rounds {
+            let round = self.rounds.entry(*round).or_default();
+            round.attacks -= damage.attacks;
+            round.hits -= damage.hits;
+            round.damage -= damage.damage;
+        }
+    }
+}
+
+fn rate(count: i64, total: i64) -> Option<f64> {
+    (total > 0).then(|| count as f64 / total as f64)
+}
+
+/// Add one tournament's `attacks` to `counts`, comparing hits with the `attack_from..=attack_to`
+/// range of the fighters in `ranges`.
+pub fn count(
+    counts: &mut HashMap<i64, Counts>,
+    ranges: &HashMap<i64, (i32, i32)>,
+    attacks: &[tournament_detail_attack::Model],
+) {
+    for battle in rating::battles(attacks) {
+        let fighters = battle.iter().map(|a| a.fighter_id).unique().collect_vec();
+
+        for attack in battle {
+            let damage = i64::from(attack.damage);
+
+            let attacker = counts.entry(attack.fighter_id).or_default();
+            attacker.attacks += 1;
+            attacker.damage += damage;
+            attacker.special_attacks += i64::from(attack.special_attack);
+            let round = attacker.rounds.entry(attack.round).or_default();
+            round.attacks += 1;
+            round.damage += damage;
+            if attack.damage > 0 {
+                attacker.hits += 1;
+                round.hits += 1;
+
+                if let Some((from, to)) = ranges.get(&attack.fighter_id) {
+                    attacker.hits_below_attack_range += i64::from(attack.damage < *from);
+                    attacker.hits_above_attack_range += i64::from(attack.damage > *to);
+                }
+            }
+
+            if let Some(target) = fighters.iter().find(|id| **id != attack.fighter_id) {
+                let target = counts.entry(*target).or_default();
+                target.attacks_received += 1;
+                target.special_defends += i64::from(attack.speical_defend);
+                target.damage_taken += damage;
+            }
+        }
+    }
+}
+
+fn combat_model(fighter: &fighter::Model, counts: &Counts) -> fighter_combat::ActiveModel {
+    let average_damage = rate(counts.damage, counts.hits);
+    let attack_range_position = average_damage
+        .filter(|_| fighter.attack_to > fighter.attack_from)
+        .map(|average| {
+            (average - f64::from(fighter.attack_from))
+                / f64::from(fighter.attack_to - fighter.attack_from)
+        });
+
+    fighter_combat::ActiveModel {
+        fighter_id: Set(fighter.id),
+        attacks: Set(counts.attacks),
+        hits: Set(counts.hits),
+        misses: Set(counts.attacks - counts.hits),
+        damage: Set(counts.damage),
+        average_damage: Set(average_damage),
+        miss_rate: Set(rate(counts.attacks - counts.hits, counts.attacks)),
+        special_attacks: Set(counts.special_attacks),
+        special_attack_rate: Set(rate(counts.special_attacks, counts.attacks)),
+        attacks_received: Set(counts.attacks_received),
+        special_defends: Set(counts.special_defends),
+        special_defend_rate: Set(rate(counts.special_defends, counts.attacks_received)),
+        damage_taken: Set(counts.damage_taken),
+        hits_below_attack_range: Set(counts.hits_below_attack_range),
+        hits_above_attack_range: Set(counts.hits_above_attack_range),
+        attack_range_position: Set(attack_range_position),
+        meta_last_updated: Set(Utc::now().naive_utc()),
+    }
+}
+
+/// Recompute the aggregates of `fighters` from every tournament they attacked in.
+pub async fn update(conn: &DatabaseConnection, fighters: &[i64]) -> Result<(), DbErr> {
+    if fighters.is_empty() {
+        return Ok(());
+    }
+
+    let fighters = fighter::Entity::find()
+        .filter(fighter::Column::Id.is_in(fighters.iter().copied()))
+        .all(conn)
+        .await?;
+    // Fighters that have not been scraped yet have no ranges to compare against
+    if fighters.is_empty() {
+        return Ok(());
+    }
+    let ids = fighters.iter().map(|f| f.id).collect::<HashSet<_>>();
+    let ranges = fighters
+        .iter()
+        .map(|f| (f.id, (f.attack_from, f.attack_to)))
+        .collect::<HashMap<_, _>>();
+
+    let placeholders = (1..=ids.len()).map(|i| format!("${i}")).join(", ");
+    let tournaments = TournamentKey::find_by_statement(Statement::from_sql_and_values(
+        conn.get_database_backend(),
+        &format!(
+            "SELECT DISTINCT tournament_id, tournament_service_id FROM tournament_detail_attack WHERE fighter_id IN ({placeholders})"
+        ),
+        ids.iter().map(|id| Value::from(*id)),
+    ))
+    .all(conn)
+    .await?;
+
+    let mut counts = HashMap::new();
+    for chunk in &tournaments.iter().chunks(CHUNK) {
+        let attacks = rating::attacks(
+            conn,
+            chunk.map(|t| (t.tournament_id, t.tournament_service_id as i32)),
+        )
+        .await?;
+        for attacks in attacks.values() {
+            count(&mut counts, &ranges, attacks);
+        }
+    }
+
+    let combat = fighters
+        .iter()
+        .map(|f| combat_model(f, counts.get(&f.id).unwrap_or(&Counts::default())))
+        .collect::<Vec<_>>();
+    let rounds = fighters
+        .iter()
+        .filter_map(|f| Some((f.id, counts.get(&f.id)?)))
+        .flat_map(|(id, counts)| {
+            counts
+                .rounds
+                .iter()
+                .map(move |(round, damage)| fighter_round_damage::ActiveModel {
+                    fighter_id: Set(id),
+                    round: Set(*round),
+                    attacks: Set(damage.attacks),
+                    hits: Set(damage.hits),
+                    damage: Set(damage.damage),
+                    average_damage: Set(rate(damage.damage, damage.hits)),
+                })
+        })
+        .collect::<Vec<_>>();
+
+    let txn = conn.begin().await?;
+
+    let rows = combat.len();
+    fighter_combat::Entity::insert_many(combat)
+        .on_conflict(
+            OnConflict::column(fighter_combat::Column::FighterId)
+                .update_columns([
+                    fighter_combat::Column::Attacks,
+                    fighter_combat::Column::Hits,
+                    fighter_combat::Column::Misses,
+                    fighter_combat::Column::Damage,
+                    fighter_combat::Column::AverageDamage,
+                    fighter_combat::Column::MissRate,
+                    fighter_combat::Column::SpecialAttacks,
+                    fighter_combat::Column::SpecialAttackRate,
+                    fighter_combat::Column::AttacksReceived,
+                    fighter_combat::Column::SpecialDefends,
+                    fighter_combat::Column::SpecialDefendRate,
+                    fighter_combat::Column::DamageTaken,
+                    fighter_combat::Column::HitsBelowAttackRange,
+                    fighter_combat::Column::HitsAboveAttackRange,
+                    fighter_combat::Column::AttackRangePosition,
+                    fighter_combat::Column::MetaLastUpdated,
+                ])
+                .to_owned(),
+        )
+        .exec_without_returning(&txn)
+        .await?;
+    metrics::upserted("fighter_combat", rows);
+
+    fighter_round_damage::Entity::delete_many()
+        .filter(fighter_round_damage::Column::FighterId.is_in(ids))
+        .exec(&txn)
+        .await?;
+    for rounds in &rounds.into_iter().chunks(CHUNK) {
+        let rounds = rounds.collect::<Vec<_>>();
+        let rows = rounds.len();
+        fighter_round_damage::Entity::insert_many(rounds)
+            .exec_without_returning(&txn)
+            .await?;
+        metrics::upserted("fighter_round_damage", rows);
+    }
+
+    txn.commit().await
+}
+
+/// Count the attacks stored for a tournament, to be handed to [`add_tournament`] once its
+/// detail has been inserted.
+pub async fn tournament_counts(
+    conn: &DatabaseConnection,
+    id: i64,
+    service_id: u64,
+) -> Result<HashMap<i64, Counts>, DbErr> {
+    let attacks = rating::attacks(conn, [(id, service_id as i32)]).await?;
+    let Some(attacks) = attacks.values().next() else {
+        return Ok(HashMap::new());
+    };
+
+    let ranges = fighter::Entity::find()
+        .filter(fighter::Column::Id.is_in(attacks.iter().map(|a| a.fighter_id).unique()))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|f| (f.id, (f.attack_from, f.attack_to)))
+        .collect();
+
+    let mut counts = HashMap::new();
+    count(&mut counts, &ranges, attacks);
+    Ok(counts)
+}
+
+/// Add the attacks stored for a tournament since `before` was counted onto the aggregates of its
+/// fighters, so that fetching a detail again only adds the attacks that are new. Fighters without
+/// aggregates yet are left to [`backfill`].
+pub async fn add_tournament(
+    conn: &DatabaseConnection,
+    id: i64,
+    service_id: u64,
+    before: &HashMap<i64, Counts>,
+) -> Result<(), DbErr> {
+    let after = tournament_counts(conn, id, service_id).await?;
+    if after.is_empty() {
+        return Ok(());
+    }
+
+    let fighters = fighter_combat::Entity::find()
+        .filter(fighter_combat::Column::FighterId.is_in(after.keys().copied()))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|c| c.fighter_id)
+        .collect::<Vec<_>>();
+    if fighters.is_empty() {
+        return Ok(());
+    }
+
+    // The counters are added in place so that tournaments inserted at the same time do not
+    // overwrite each other, and the rates follow from the sums afterwards
+    let txn = conn.begin().await?;
+    for fighter in &fighters {
+        let mut added = after[fighter].clone();
+        if let Some(before) = before.get(fighter) {
+            added.subtract(before);
+        }
+        add(&txn, *fighter, &added).await?;
+    }
+    txn.commit().await?;
+
+    update_rates(conn, &fighters).await
+}
+
+/// Add `counts` onto the stored aggregates of `fighter_id`.
+async fn add<C: ConnectionTrait>(conn: &C, fighter_id: i64, counts: &Counts) -> Result<(), DbErr> {
+    use fighter_combat::Column;
+
+    let added = |column: Column, count: i64| (column, Expr::col(column).add(count));
+    let mut update = fighter_combat::Entity::update_many();
+    for (column, expr) in [
+        added(Column::Attacks, counts.attacks),
+        added(Column::Hits, counts.hits),
+        added(Column::Misses, counts.attacks - counts.hits),
+        added(Column::Damage, counts.damage),
+        added(Column::SpecialAttacks, counts.special_attacks),
+        added(Column::AttacksReceived, counts.attacks_received),
+        added(Column::SpecialDefends, counts.special_defends),
+        added(Column::DamageTaken, counts.damage_taken),
+        added(Column::HitsBelowAttackRange, counts.hits_below_attack_range),
+        added(Column::HitsAboveAttackRange, counts.hits_above_attack_range),
+    ] {
+        update = update.col_expr(column, expr);
+    }
+    update
+        .col_expr(Column::MetaLastUpdated, Expr::value(Utc::now().naive_utc()))
+        .filter(Column::FighterId.eq(fighter_id))
+        .exec(conn)
+        .await?;
+
+    for (round, damage) in &counts.rounds {
+        use fighter_round_damage::{Column, Entity};
+
+        let added = |column: Column, count: i64| (column, Expr::col((Entity, column)).add(count));
+        Entity::insert(fighter_round_damage::ActiveModel {
+            fighter_id: Set(fighter_id),
+            round: Set(*round),
+            attacks: Set(damage.attacks),
+            hits: Set(damage.hits),
+            damage: Set(damage.damage),
+            average_damage: Set(None),
+        })
+        .on_conflict(
+            OnConflict::columns([Column::FighterId, Column::Round])
+                .values([
+                    added(Column::Attacks, damage.attacks),
+                    added(Column::Hits, damage.hits),
+                    added(Column::Damage, damage.damage),
+                ])
+                .to_owned(),
+        )
+        .exec_without_returning(conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Recompute the rates of `fighters` from their stored sums.
+async fn update_rates(conn: &DatabaseConnection, fighters: &[i64]) -> Result<(), DbErr> {
+    let combat = fighter::Entity::find()
+        .find_also_related(fighter_combat::Entity)
+        .filter(fighter::Column::Id.is_in(fighters.iter().copied()))
+        .all(conn)
+        .await?;
+
+    for (fighter, combat) in combat {
+        let Some(combat) = combat else {
+            continue;
+        };
+        let counts = Counts {
+            attacks: combat.attacks,
+            hits: combat.hits,
+            damage: combat.damage,
+            special_attacks: combat.special_attacks,
+            attacks_received: combat.attacks_received,
+            special_defends: combat.special_defends,
+            damage_taken: combat.damage_taken,
+            hits_below_attack_range: combat.hits_below_attack_range,
+            hits_above_attack_range: combat.hits_above_attack_range,
+            rounds: BTreeMap::new(),
+        };
+        let model = combat_model(&fighter, &counts);
+
+        fighter_combat::Entity::update_many()
+            .set(fighter_combat::ActiveModel {
+                average_damage: model.average_damage,
+                miss_rate: model.miss_rate,
+                special_attack_rate: model.special_attack_rate,
+                special_defend_rate: model.special_defend_rate,
+                attack_range_position: model.attack_range_position,
+                ..Default::default()
+            })
+            .filter(fighter_combat::Column::FighterId.eq(fighter.id))
+            .exec(conn)
+            .await?;
+    }
+
+    let rounds = fighter_round_damage::Entity::find()
+        .filter(fighter_round_damage::Column::FighterId.is_in(fighters.iter().copied()))
+        .all(conn)
+        .await?;
+    for round in rounds {
+        fighter_round_damage::Entity::update(fighter_round_damage::ActiveModel {
+            average_damage: Set(rate(round.damage, round.hits)),
+            ..round.into()
+        })
+        .exec(conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Compute the aggregates of fighters with attacks but none stored yet. Returns how many fighters
+/// were updated.
+#[instrument(skip_all)]
+pub async fn backfill(conn: &DatabaseConnection) -> Result<usize> {
+    let fighters = Id::find_by_statement(Statement::from_string(
+        conn.get_database_backend(),
+        MISSING.to_owned(),
+    ))
+    .all(conn)
+    .await?
+    .into_iter()
+    .map(|f| f.id)
+    .collect::<Vec<_>>();
+
+    for chunk in fighters.chunks(CHUNK) {
+        update(conn, chunk).await?;
+    }
+
+    if !fighters.is_empty() {
+        info!(fighters = fighters.len(), "backfilled combat aggregates");
+    }
+
+    Ok(fighters.len())
+}
+
+/// A fighter's combat aggregates next to its wisdom ranges.
+#[derive(Debug, Serialize)]
+pub struct Summary {
+    pub fighter_id: i64,
+    pub attack_range: (i32, i32),
+    pub defence_range: (i32, i32),
+    pub strength_range: (i32, i32),
+    pub omega_range: (i32, i32),
+    pub attacks: i64,
+    pub hits: i64,
+    pub damage: i64,
+    pub average_damage: Option<f64>,
+    pub miss_rate: Option<f64>,
+    pub special_attack_rate: Option<f64>,
+    pub special_defend_rate: Option<f64>,
+    pub damage_taken: i64,
+    pub hits_below_attack_range: i64,
+    pub hits_above_attack_range: i64,
+    pub attack_range_position: Option<f64>,
+    /// Average damage per hit by round.
+    pub rounds: BTreeMap<i32, Option<f64>>,
+}
+
+/// Print the combat aggregates of fighter `id` to stdout as JSON.
+pub async fn print_summary(conn: &DatabaseConnection, id: i64) -> Result<()> {
+    let Some((fighter, Some(combat))) = fighter::Entity::find_by_id(id)
+        .find_also_related(fighter_combat::Entity)
+        .one(conn)
+        .await?
+    else {
+        anyhow::bail!("no combat aggregates for fighter {id}");
+    };
+    let rounds = fighter_round_damage::Entity::find()
+        .filter(fighter_round_damage::Column::FighterId.eq(id))
+        .order_by_asc(fighter_round_damage::Column::Round)
+        .all(conn)
+        .await?;
+
+    let summary = Summary {
+        fighter_id: id,
+        attack_range: (fighter.attack_from, fighter.attack_to),
+        defence_range: (fighter.defence_from, fighter.defence_to),
+        strength_range: (fighter.strength_from, fighter.strength_to),
+        omega_range: (fighter.omega_from, fighter.omega_to),
+        attacks: combat.attacks,
+        hits: combat.hits,
+        damage: combat.damage,
+        average_damage: combat.average_damage,
+        miss_rate: combat.miss_rate,
+        special_attack_rate: combat.special_attack_rate,
+        special_defend_rate: combat.special_defend_rate,
+        damage_taken: combat.damage_taken,
+        hits_below_attack_range: combat.hits_below_attack_range,
+        hits_above_attack_range: combat.hits_above_attack_range,
+        attack_range_position: combat.attack_range_position,
+        rounds: rounds
+            .into_iter()
+            .map(|r| (r.round, r.average_damage))
+            .collect(),
+    };
+    println!("{}", serde_json::to_string_pretty(&summary)?);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attack(
+        round: i32,
+        order: i32,
+        fighter_id: i64,
+        damage: i32,
+        speical_defend: bool,
+    ) -> tournament_detail_attack::Model {
+        tournament_detail_attack::Model {
+            tournament_id: 1,
+            tournament_service_id: 0,
+            fighter_id,
+            round,
+            special_attack: false,
+            speical_defend,
+            damage,
+            order,
+        }
+    }
+
+    #[test]
+    fn defends_count_for_the_target() {
+        let attacks = [
+            attack(1, 0, 1, 100, false),
+            attack(1, 1, 2, 0, false),
+            attack(1, 2, 1, 40, true),
+            attack(2, 0, 1, 150, false),
+            attack(2, 1, 3, 60, false),
+        ];
+        let ranges = HashMap::from([(1, (50, 120))]);
+
+        let mut counts = HashMap::new();
+        count(&mut counts, &ranges, &attacks);
+
+        let one = &counts[&1];
+        assert_eq!((one.attacks, one.hits, one.damage), (3, 3, 290));
+        assert_eq!(
+            (one.hits_below_attack_range, one.hits_above_attack_range),
+            (1, 1)
+        );
+        assert_eq!((one.attacks_received, one.damage_taken), (2, 60));
+        assert_eq!(one.rounds[&2].damage, 150);
+
+        let two = &counts[&2];
+        assert_eq!((two.attacks, two.hits), (1, 0));
+        assert_eq!((two.attacks_received, two.special_defends), (2, 1));
+        assert_eq!(rate(two.special_defends, two.attacks_received), Some(0.5));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn attackers_that_were_not_scraped_are_skipped() {
+        use crate::testing;
+        use sea_orm::IntoActiveModel;
+
+        let conn = testing::database().await;
+        testing::without_foreign_keys(&conn).await;
+
+        let fighter = fighter::Model {
+            attack_from: 50,
+            attack_to: 120,
+            ..testing::fighter(1)
+        };
+        fighter::Entity::insert(fighter.into_active_model())
+            .exec(&conn)
+            .await
+            .unwrap();
+        tournament_detail_attack::Entity::insert_many([
+            attack(1, 0, 1, 100, false).into_active_model(),
+            attack(1, 1, 2, 60, false).into_active_model(),
+        ])
+        .exec(&conn)
+        .await
+        .unwrap();
+
+        update(&conn, &[2]).await.unwrap();
+        assert_eq!(backfill(&conn).await.unwrap(), 1);
+        let before = tournament_counts(&conn, 1, 0).await.unwrap();
+        add_tournament(&conn, 1, 0, &before).await.unwrap();
+
+        let combat = fighter_combat::Entity::find().all(&conn).await.unwrap();
+        assert_eq!(combat.len(), 1);
+        assert_eq!((combat[0].fighter_id, combat[0].damage_taken), (1, 60));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn tournaments_are_added_onto_stored_aggregates() {
+        use crate::testing;
+        use sea_orm::IntoActiveModel;
+
+        let conn = testing::database().await;
+        testing::without_foreign_keys(&conn).await;
+
+        for id in [1, 2] {
+            let fighter = fighter::Model {
+                attack_from: 50,
+                attack_to: 120,
+                ..testing::fighter(id)
+            };
+            fighter::Entity::insert(fighter.into_active_model())
+                .exec(&conn)
+                .await
+                .unwrap();
+        }
+        let tournament = |id, attacks: &[tournament_detail_attack::Model]| {
+            attacks
+                .iter()
+                .map(|a| {
+                    tournament_detail_attack::Model {
+                        tournament_id: id,
+                        ..a.clone()
+                    }
+                    .into_active_model()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        tournament_detail_attack::Entity::insert_many(tournament(
+            1,
+            &[attack(1, 0, 1, 100, false), attack(1, 1, 2, 60, true)],
+        ))
+        .exec(&conn)
+        .await
+        .unwrap();
+        assert_eq!(backfill(&conn).await.unwrap(), 2);
+
+        // The second tournament's first round was stored while it was being fought, and its second
+        // round once it had completed
+        for attacks in [
+            vec![attack(1, 0, 2, 80, false), attack(1, 1, 1, 0, false)],
+            vec![attack(2, 0, 1, 130, true), attack(2, 1, 2, 40, false)],
+        ] {
+            let before = tournament_counts(&conn, 2, 0).await.unwrap();
+            tournament_detail_attack::Entity::insert_many(tournament(2, &attacks))
+                .exec(&conn)
+                .await
+                .unwrap();
+            add_tournament(&conn, 2, 0, &before).await.unwrap();
+        }
+
+        let stored = || async {
+            let combat = fighter_combat::Entity::find()
+                .all(&conn)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|c| fighter_combat::Model {
+                    meta_last_updated: Default::default(),
+                    ..c
+                })
+                .collect::<Vec<_>>();
+            let rounds = fighter_round_damage::Entity::find()
+                .order_by_asc(fighter_round_damage::Column::FighterId)
+                .order_by_asc(fighter_round_damage::Column::Round)
+                .all(&conn)
+                .await
+                .unwrap();
+            (combat, rounds)
+        };
+
+        // Adding matches counting every tournament again
+        let added = stored().await;
+        update(&conn, &[1, 2]).await.unwrap();
+        assert_eq!(added, stored().await);
+        assert_eq!(added.0[0].damage, 230);
+        assert_eq!(added.1.len(), 4);
+    }
+}
@@ -0,0 +1,111 @@
+use entity::entities::token;
+use ethers_core::{
+    types::{Address, U256},
+    utils::format_units,
+};
+use sea_orm::{ConnectionTrait, DbErr, EntityTrait};
+use std::collections::HashMap;
+
+/// The ERC-20 tokens tournaments are paid in, from the `token` table.
+#[derive(Clone, Debug, Default)]
+pub struct Tokens(HashMap<Vec<u8>, token::Model>);
+
+impl Tokens {
+    /// Every registered token.
+    pub async fn load<C: ConnectionTrait>(conn: &C) -> Result<Self, DbErr> {
+        Ok(token::Entity::find().all(conn).await?.into_iter().collect())
+    }
+
+    /// The token at `address`, stored as raw bytes.
+    pub fn get(&self, address: &[u8]) -> Option<&token::Model> {
+        self.0.get(address)
+    }
+
+    /// A token's symbol, or its address if it is not registered.
+    pub fn symbol(&self, address: &[u8]) -> String {
+        match self.get(address) {
+            Some(token) => token.symbol.clone(),
+            None if address.len() == 20 => format!("{:?}", Address::from_slice(address)),
+            None => format!(
+                "0x{}",
+                address
+                    .iter()
+                    .map(|b| format!("{b:02x}"))
+                    .collect::<String>()
+            ),
+        }
+    }
+
+    /// `amount` of the token at `address` in whole units, or in its smallest units if the token
+    /// is not registered.
+    pub fn format(&self, address: &[u8], amount: U256) -> String {
+        self.get(address)
+            .and_then(|t| format_units(amount, t.decimals as u32).ok())
+            .unwrap_or_else(|| amount.to_string())
+    }
+
+    /// `amount` of the token at `address` in whole units, if the token is registered.
+    pub fn units(&self, address: &[u8], amount: U256) -> Option<f64> {
+        self.get(address)
+            .and_then(|t| format_units(amount, t.decimals as u32).ok())
+            .and_then(|units| units.parse().ok())
+    }
+
+    /// `positive - negative` of the token at `address`, formatted like [`Tokens::format`].
+    pub fn format_signed(&self, address: &[u8], positive: U256, negative: U256) -> String {
+        if positive >= negative {
+            self.format(address, positive - negative)
+        } else {
+            format!("-{}", self.format(address, negative - positive))
+        }
+    }
+}
+
+impl FromIterator<token::Model> for Tokens {
+    fn from_iter<I: IntoIterator<Item = token::Model>>(iter: I) -> Self {
+        Tokens(
+            iter.into_iter()
+                .map(|token| (token.address.clone(), token))
+                .collect(),
+        )
+    }
+}
+
+/// A `uint256` amount, stored as 32 big-endian bytes.
+pub fn amount(bytes: &[u8]) -> U256 {
+    U256::from_big_endian(bytes)
+}
+
+/// What a tournament pays out: the buy-ins of its `entrants` less the fee, plus the top-up.
+pub fn prize_pool(entrants: usize, buy_in: U256, top_up: U256, fee_percentage: i32) -> U256 {
+    let fee = U256::from(fee_percentage.clamp(0, 100));
+    buy_in * entrants * (U256::from(100) - fee) / 100 + top_up
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WETH: [u8; 20] =
+        *b"\x7c\xeb\x23\xfd\x6b\xc0\xad\xd5\x9e\x62\xac\x25\x57\x82\x70\xcf\xf1\xb9\xf6\x19";
+
+    #[test]
+    fn amounts_use_token_decimals() {
+        let tokens = Tokens::from_iter([token::Model {
+            address: WETH.to_vec(),
+            symbol: "WETH".to_owned(),
+            decimals: 18,
+        }]);
+        let amount = U256::exp10(18) * 3 / 2;
+
+        assert_eq!(tokens.symbol(&WETH), "WETH");
+        assert_eq!(tokens.units(&WETH, amount), Some(1.5));
+        assert_eq!(tokens.units(&[0; 20], amount), None);
+        assert_eq!(tokens.format(&WETH, amount), "1.500000000000000000");
+        assert_eq!(tokens.format(&[0; 20], amount), "1500000000000000000");
+        assert_eq!(
+            tokens.format_signed(&WETH, U256::zero(), amount),
+            "-1.500000000000000000"
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use anyhow::{anyhow, Result};
+use api::{
+    fighter::FighterResponse, tournament::Tournament, tournament_detail::TournamentDetailResponse,
+    SCHEMA_VERSION,
+};
+use chrono::Utc;
+use entity::entities::meta_dead_letter;
+use sea_orm::{
+    sea_query::OnConflict, ActiveModelTrait, DatabaseConnection, DbErr, EntityTrait, ModelTrait,
+    Set,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::str::FromStr;
+use tracing::{info, instrument, warn};
+
+use crate::{
+    metrics,
+    task::{fighter::ChampionTask, tournament::TournamentTask},
+};
+
+/// The kind of response a dead letter was taken from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PayloadKind {
+    Fighter,
+    Tournament,
+    TournamentDetail,
+}
+
+impl PayloadKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PayloadKind::Fighter => "fighter",
+            PayloadKind::Tournament => "tournament",
+            PayloadKind::TournamentDetail => "tournament_detail",
+        }
+    }
+}
+
+impl FromStr for PayloadKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fighter" => Ok(PayloadKind::Fighter),
+            "tournament" => Ok(PayloadKind::Tournament),
+            "tournament_detail" => Ok(PayloadKind::TournamentDetail),
+            _ => Err(anyhow!("{s} not a valid payload kind")),
+        }
+    }
+}
+
+/// Store a payload that could not be deserialized so that it can be re-parsed later.
+///
+/// `reference` identifies the payload within its kind, e.g. the fighter ID or
+/// `{service_id}/{tournament_id}` for tournaments and their details.
+pub async fn insert(
+    conn: &DatabaseConnection,
+    kind: PayloadKind,
+    reference: String,
+    source_url: String,
+    payload: Value,
+    error: &serde_json::Error,
+) -> Result<(), DbErr> {
+    use meta_dead_letter::*;
+
+    metrics::PARSE_FAILURES
+        .with_label_values(&[kind.as_str()])
+        .inc();
+
+    Entity::insert(ActiveModel {
+        kind: Set(kind.as_str().to_owned()),
+        reference: Set(reference),
+        source_url: Set(source_url),
+        payload: Set(payload),
+        error: Set(error.to_string()),
+        schema_version: Set(SCHEMA_VERSION),
+        last_seen: Set(Utc::now().naive_utc()),
+    })
+    .on_conflict(
+        OnConflict::columns([Column::Kind, Column::Reference])
+            .update_columns([
+                Column::SourceUrl,
+                Column::Payload,
+                Column::Error,
+                Column::SchemaVersion,
+                Column::LastSeen,
+            ])
+            .to_owned(),
+    )
+    .exec(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Re-parse every stored payload with the current models and ingest the ones that now succeed.
+#[instrument(skip_all)]
+pub async fn reparse(
+    conn: &DatabaseConnection,
+    champion_task: &ChampionTask,
+    tournament_task: &TournamentTask,
+) -> Result<()> {
+    info!(schema_version = SCHEMA_VERSION, "re-parsing dead letters");
+
+    let mut recovered = 0;
+    let mut failed = 0;
+
+    for letter in meta_dead_letter::Entity::find().all(conn).await? {
+        let ingested = match letter.kind.parse() {
+            Ok(PayloadKind::Fighter) => {
+                match <FighterResponse as Deserialize>::deserialize(&letter.payload) {
+                    Ok(fighter) => Ok(champion_task
+                        .insert_champions(vec![(fighter, Utc::now())])
+                        .await),
+                    Err(e) => Err(e),
+                }
+            }
+            Ok(PayloadKind::Tournament) => {
+                match <Tournament as Deserialize>::deserialize(&letter.payload) {
+                    Ok(tournament) => {
+                        Ok(tournament_task.ingest_tournaments(vec![tournament]).await)
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            Ok(PayloadKind::TournamentDetail) => {
+                let Some((service_id, id)) = parse_detail_reference(&letter.reference) else {
+                    warn!(reference = ?letter.reference, "invalid tournament detail reference");
+                    continue;
+                };
+
+                match <TournamentDetailResponse as Deserialize>::deserialize(&letter.payload) {
+                    Ok(detail) => Ok(tournament_task
+                        .ingest_tournament_detail(id, service_id, detail)
+                        .await
+                        .map_err(anyhow::Error::from)),
+                    Err(e) => Err(e),
+                }
+            }
+            Err(e) => {
+                warn!(e = ?e, "unknown dead letter kind");
+                continue;
+            }
+        };
+
+        match ingested {
+            Ok(Ok(())) => {
+                recovered += 1;
+                let _ = letter.delete(conn).await.map_err(|e| {
+                    warn!(e = ?e);
+                    e
+                });
+            }
+            Ok(Err(e)) => {
+                warn!(kind = ?letter.kind, reference = ?letter.reference, e = ?e, "could not ingest re-parsed payload");
+            }
+            Err(e) => {
+                failed += 1;
+                let mut letter: meta_dead_letter::ActiveModel = letter.into();
+                letter.error = Set(e.to_string());
+                letter.schema_version = Set(SCHEMA_VERSION);
+                let _ = letter.update(conn).await.map_err(|e| {
+                    warn!(e = ?e);
+                    e
+                });
+            }
+        }
+    }
+
+    info!(recovered = recovered, failed = failed, "re-parse complete");
+    Ok(())
+}
+
+/// Parse a `{service_id}/{tournament_id}` reference.
+pub(crate) fn parse_detail_reference(reference: &str) -> Option<(u64, i64)> {
+    let (service_id, id) = reference.split_once('/')?;
+    Some((service_id.parse().ok()?, id.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detail_references_are_service_then_tournament() {
+        assert_eq!(parse_detail_reference("0/12"), Some((0, 12)));
+        assert_eq!(parse_detail_reference("3/-1"), Some((3, -1)));
+        assert_eq!(parse_detail_reference("12"), None);
+        assert_eq!(parse_detail_reference("-1/12"), None);
+        assert_eq!(parse_detail_reference("0/x"), None);
+        assert_eq!(parse_detail_reference("0/12/1"), None);
+    }
+
+    #[test]
+    fn payload_kinds_round_trip() {
+        for kind in [
+            PayloadKind::Fighter,
+            PayloadKind::Tournament,
+            PayloadKind::TournamentDetail,
+        ] {
+            assert_eq!(kind.as_str().parse::<PayloadKind>().unwrap(), kind);
+        }
+        assert!("champion".parse::<PayloadKind>().is_err());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn reparse_ingests_letters_that_now_parse() {
+        use entity::entities::fighter;
+
+        let conn = crate::testing::database().await;
+        let champion_task =
+            ChampionTask::new(crate::testing::client(), conn.clone(), String::new(), None);
+        let tournament_task =
+            TournamentTask::new(crate::testing::client(), conn.clone(), 100, None);
+        let error = serde_json::from_str::<u8>("x").unwrap_err();
+        for (reference, payload) in [
+            ("5118", include_str!("../api/src/tests/fighter.json")),
+            ("2", r#"{"id": 2}"#),
+        ] {
+            insert(
+                &conn,
+                PayloadKind::Fighter,
+                reference.to_owned(),
+                String::new(),
+                serde_json::from_str(payload).unwrap(),
+                &error,
+            )
+            .await
+            .unwrap();
+        }
+
+        reparse(&conn, &champion_task, &tournament_task)
+            .await
+            .unwrap();
+
+        assert!(fighter::Entity::find_by_id(5118)
+            .one(&conn)
+            .await
+            .unwrap()
+            .is_some());
+        let letters = meta_dead_letter::Entity::find().all(&conn).await.unwrap();
+        assert_eq!(letters.len(), 1);
+        assert_eq!(letters[0].reference, "2");
+        assert_ne!(letters[0].error, error.to_string());
+    }
+}
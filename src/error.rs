@@ -0,0 +1,191 @@
+use reqwest::StatusCode;
+use sea_orm::{DbErr, TransactionError};
+use std::time::Duration;
+use thiserror::Error;
+
+/// Why fetching or storing something failed.
+///
+/// The variant decides whether a request is worth retrying, and [`ScrapeError::kind`] is what gets
+/// recorded in the failure tables.
+#[derive(Debug, Error)]
+pub enum ScrapeError {
+    /// The token or tournament does not exist (yet).
+    #[error("not found: {0}")]
+    NotFound(#[source] reqwest::Error),
+    /// The host asked us to slow down.
+    #[error("rate limited: {source}")]
+    RateLimited {
+        source: reqwest::Error,
+        retry_after: Option<Duration>,
+    },
+    /// The host failed to answer, or answered with a 5xx.
+    #[error("server error: {source}")]
+    Server {
+        source: reqwest::Error,
+        retry_after: Option<Duration>,
+    },
+    /// Any other rejected request.
+    #[error("request rejected: {0}")]
+    Rejected(#[source] reqwest::Error),
+    /// The request never got an answer.
+    #[error("network error: {0}")]
+    Network(#[source] reqwest::Error),
+    /// The response does not look like what we expect.
+    #[error("schema mismatch: {0}")]
+    Schema(#[from] serde_json::Error),
+    #[error("database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl From<TransactionError<DbErr>> for ScrapeError {
+    fn from(e: TransactionError<DbErr>) -> Self {
+        match e {
+            TransactionError::Connection(e) | TransactionError::Transaction(e) => e.into(),
+        }
+    }
+}
+
+impl ScrapeError {
+    /// Classify an error response, along with how long the host asked us to wait.
+    pub fn from_status(e: reqwest::Error, retry_after: Option<Duration>) -> Self {
+        match e.status() {
+            Some(StatusCode::NOT_FOUND) => ScrapeError::NotFound(e),
+            Some(StatusCode::TOO_MANY_REQUESTS) => ScrapeError::RateLimited {
+                source: e,
+                retry_after,
+            },
+            Some(status) if status.is_server_error() => ScrapeError::Server {
+                source: e,
+                retry_after,
+            },
+            _ => ScrapeError::Rejected(e),
+        }
+    }
+
+    /// Short name of the variant, stored alongside failures.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ScrapeError::NotFound(_) => "not_found",
+            ScrapeError::RateLimited { .. } => "rate_limited",
+            ScrapeError::Server { .. } => "server",
+            ScrapeError::Rejected(_) => "rejected",
+            ScrapeError::Network(_) => "network",
+            ScrapeError::Schema(_) => "schema",
+            ScrapeError::Database(_) => "database",
+        }
+    }
+
+    /// Whether trying again straight away could help.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            ScrapeError::RateLimited { .. } | ScrapeError::Server { .. } | ScrapeError::Network(_)
+        )
+    }
+
+    /// How long the host asked us to wait before trying again, if it did.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ScrapeError::RateLimited { retry_after, .. }
+            | ScrapeError::Server { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http;
+    use reqwest::header::RETRY_AFTER;
+
+    /// Classify a response the same way the client does.
+    fn classify(status: u16, retry_after: Option<&str>) -> ScrapeError {
+        let mut builder = http::Response::builder().status(status);
+        if let Some(value) = retry_after {
+            builder = builder.header(RETRY_AFTER, value);
+        }
+        let resp = reqwest::Response::from(builder.body("").unwrap());
+
+        let retry_after = crate::client::retry_after(&resp);
+        ScrapeError::from_status(resp.error_for_status().unwrap_err(), retry_after)
+    }
+
+    /// An error that never got a response.
+    fn network() -> ScrapeError {
+        ScrapeError::Network(reqwest::Client::new().get("not a url").build().unwrap_err())
+    }
+
+    #[test]
+    fn statuses_are_classified() {
+        assert!(matches!(classify(404, None), ScrapeError::NotFound(_)));
+        assert!(matches!(
+            classify(429, Some("30")),
+            ScrapeError::RateLimited {
+                retry_after: Some(d),
+                ..
+            } if d == Duration::from_secs(30)
+        ));
+        assert!(matches!(
+            classify(429, None),
+            ScrapeError::RateLimited {
+                retry_after: None,
+                ..
+            }
+        ));
+        assert!(matches!(classify(500, None), ScrapeError::Server { .. }));
+        assert!(matches!(classify(503, None), ScrapeError::Server { .. }));
+        assert!(matches!(classify(400, None), ScrapeError::Rejected(_)));
+        assert!(matches!(classify(403, None), ScrapeError::Rejected(_)));
+    }
+
+    #[test]
+    fn only_retry_after_responses_carry_a_wait() {
+        assert_eq!(
+            classify(503, Some("5")).retry_after(),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(classify(429, Some("soon")).retry_after(), None);
+        assert_eq!(classify(404, Some("5")).retry_after(), None);
+    }
+
+    #[test]
+    fn transient_errors_are_retried() {
+        assert!(classify(429, None).is_transient());
+        assert!(classify(502, None).is_transient());
+        assert!(network().is_transient());
+
+        assert!(!classify(404, None).is_transient());
+        assert!(!classify(400, None).is_transient());
+        let schema = serde_json::from_str::<u64>("{}").unwrap_err();
+        assert!(!ScrapeError::from(schema).is_transient());
+        assert!(!ScrapeError::from(DbErr::RecordNotFound(String::new())).is_transient());
+    }
+
+    #[test]
+    fn kinds_name_the_variant() {
+        let schema = serde_json::from_str::<u64>("{}").unwrap_err();
+        let kinds = [
+            classify(404, None).kind(),
+            classify(429, None).kind(),
+            classify(500, None).kind(),
+            classify(400, None).kind(),
+            network().kind(),
+            ScrapeError::from(schema).kind(),
+            ScrapeError::from(DbErr::RecordNotFound(String::new())).kind(),
+        ];
+
+        assert_eq!(
+            kinds,
+            [
+                "not_found",
+                "rate_limited",
+                "server",
+                "rejected",
+                "network",
+                "schema",
+                "database"
+            ]
+        );
+    }
+}
@@ -0,0 +1,511 @@
+//! Dumps of the scraped tables as CSV or Parquet files, one per table.
+//!
+//! Addresses are exported as `0x` hex and `uint256` amounts both in the token's smallest units and
+//! in whole tokens. Exports can be limited to rows updated since a time: fighters and their traits
+//! and parents by the fighter's `meta_last_updated`, tournaments and their entrants by the
+//! tournament's, and battles by when the tournament's detail was last fetched.
+//!
+//! Every table is read from the same snapshot, so rows written by a running scrape are neither
+//! skipped nor repeated between pages, and tables agree with each other.
+
+use anyhow::{Context, Result};
+use chrono::{NaiveDateTime, Utc};
+use clap::ValueEnum;
+use sea_orm::{
+    AccessMode, ConnectionTrait, DatabaseConnection, DbBackend, IsolationLevel, Statement,
+    TransactionTrait, Value,
+};
+use serde::Serialize;
+use std::{collections::BTreeMap, fs, fs::File, path::Path};
+use tracing::{info, instrument};
+
+#[cfg(feature = "parquet")]
+mod parquet;
+
+/// Rows read and written at a time.
+const PAGE_SIZE: u64 = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    /// Requires building with the `parquet` feature.
+    Parquet,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+/// How a column is typed in Parquet files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Int,
+    Float,
+    Bool,
+    Text,
+    Timestamp,
+    /// Raw bytes, exported as `0x` hex text.
+    Address,
+}
+
+/// An exported column, and the SQL expression it is read from.
+#[derive(Clone, Copy, Debug)]
+pub struct Column {
+    pub name: &'static str,
+    pub kind: Kind,
+    pub expr: &'static str,
+}
+
+const fn column(name: &'static str, kind: Kind, expr: &'static str) -> Column {
+    Column { name, kind, expr }
+}
+
+/// An exported table.
+#[derive(Clone, Copy, Debug)]
+pub struct Table {
+    pub name: &'static str,
+    pub columns: &'static [Column],
+    from: &'static str,
+    /// When a row last changed, to export incrementally.
+    updated: &'static str,
+    order: &'static str,
+}
+
+pub const TABLES: [Table; 7] = [
+    Table {
+        name: "fighter",
+        columns: &[
+            column("id", Kind::Int, "fighter.id"),
+            column("wisdom_point", Kind::Int, "fighter.wisdom_point"),
+            column("strength_from", Kind::Int, "fighter.strength_from"),
+            column("strength_to", Kind::Int, "fighter.strength_to"),
+            column("attack_from", Kind::Int, "fighter.attack_from"),
+            column("attack_to", Kind::Int, "fighter.attack_to"),
+            column("defence_from", Kind::Int, "fighter.defence_from"),
+            column("defence_to", Kind::Int, "fighter.defence_to"),
+            column("omega_from", Kind::Int, "fighter.omega_from"),
+            column("omega_to", Kind::Int, "fighter.omega_to"),
+            column("mum", Kind::Int, "fighter.mum"),
+            column("elo", Kind::Int, "fighter.elo"),
+            column("bloodline", Kind::Text, "fighter.bloodline"),
+            column("genotype", Kind::Int, "fighter.genotype"),
+            column("character_class", Kind::Text, "fighter.character_class"),
+            column("breed", Kind::Text, "fighter.breed"),
+            column("armor_color", Kind::Text, "fighter.armor_color"),
+            column("hair_style", Kind::Text, "fighter.hair_style"),
+            column("warpaint", Kind::Text, "fighter.warpaint"),
+            column(
+                "meta_last_updated",
+                Kind::Timestamp,
+                "fighter.meta_last_updated",
+            ),
+        ],
+        from: "fighter",
+        updated: "fighter.meta_last_updated",
+        order: "fighter.id",
+    },
+    Table {
+        name: "fighter_trait",
+        columns: &[
+            column("fighter_id", Kind::Int, "fighter_trait.fighter_id"),
+            column("trait_type", Kind::Text, "fighter_trait.trait_type"),
+            column("value", Kind::Text, "fighter_trait.value"),
+            column("numeric_value", Kind::Int, "fighter_trait.numeric_value"),
+            column("display_type", Kind::Text, "fighter_trait.display_type"),
+        ],
+        from: "fighter_trait JOIN fighter ON fighter.id = fighter_trait.fighter_id",
+        updated: "fighter.meta_last_updated",
+        order: "fighter_trait.fighter_id, fighter_trait.trait_type, fighter_trait.value",
+    },
+    Table {
+        name: "fighter_parent",
+        columns: &[
+            column("fighter_id", Kind::Int, "fighter_parent.fighter_id"),
+            column("parent_id", Kind::Int, "fighter_parent.parent_id"),
+        ],
+        from: "fighter_parent JOIN fighter ON fighter.id = fighter_parent.fighter_id",
+        updated: "fighter.meta_last_updated",
+        order: "fighter_parent.fighter_id, fighter_parent.parent_id",
+    },
+    Table {
+        name: "tournament",
+        columns: &[
+            column("id", Kind::Int, "tournament.id"),
+            column("service_id", Kind::Int, "tournament.service_id"),
+            column("currency", Kind::Text, "tournament_amount.currency"),
+            column("currency_symbol", Kind::Text, "tournament_amount.symbol"),
+            column("fee_percentage", Kind::Int, "tournament.fee_percentage"),
+            column("buy_in_units", Kind::Text, "tournament_amount.buy_in_units"),
+            column("buy_in", Kind::Float, "tournament_amount.buy_in"),
+            column("top_up_units", Kind::Text, "tournament_amount.top_up_units"),
+            column("top_up", Kind::Float, "tournament_amount.top_up"),
+            column("key", Kind::Text, "tournament.key"),
+            column("legacy", Kind::Bool, "tournament.legacy"),
+            column("level", Kind::Text, "tournament.level"),
+            column("modified", Kind::Timestamp, "tournament.modified"),
+            column("name", Kind::Text, "tournament.name"),
+            column("restrictions", Kind::Text, "tournament.restrictions"),
+            column("solo_optionals", Kind::Text, "tournament.solo_optionals"),
+            column("start_time", Kind::Timestamp, "tournament.start_time"),
+            column("status", Kind::Text, "tournament.status"),
+            column(
+                "meta_last_updated",
+                Kind::Timestamp,
+                "tournament.meta_last_updated",
+            ),
+        ],
+        from: "tournament JOIN tournament_amount
+            ON tournament_amount.tournament_id = tournament.id
+            AND tournament_amount.tournament_service_id = tournament.service_id",
+        updated: "tournament.meta_last_updated",
+        order: "tournament.service_id, tournament.id",
+    },
+    Table {
+        name: "tournament_fighter",
+        columns: &[
+            column(
+                "tournament_id",
+                Kind::Int,
+                "tournament_fighter.tournament_id",
+            ),
+            column(
+                "tournament_service_id",
+                Kind::Int,
+                "tournament_fighter.tournament_service_id",
+            ),
+            column("fighter_id", Kind::Int, "tournament_fighter.fighter_id"),
+            column("account", Kind::Address, "tournament_fighter.account"),
+        ],
+        from: "tournament_fighter JOIN tournament
+            ON tournament.id = tournament_fighter.tournament_id
+            AND tournament.service_id = tournament_fighter.tournament_service_id",
+        updated: "tournament.meta_last_updated",
+        order: "tournament_fighter.tournament_service_id, tournament_fighter.tournament_id,
+            tournament_fighter.fighter_id",
+    },
+    Table {
+        name: "tournament_detail_champion",
+        columns: &[
+            column(
+                "tournament_id",
+                Kind::Int,
+                "tournament_detail_champion.tournament_id",
+            ),
+            column(
+                "tournament_service_id",
+                Kind::Int,
+                "tournament_detail_champion.tournament_service_id",
+            ),
+            column(
+                "fighter_id",
+                Kind::Int,
+                "tournament_detail_champion.fighter_id",
+            ),
+            column("stance", Kind::Int, "tournament_detail_champion.stance"),
+        ],
+        from: "tournament_detail_champion JOIN meta_tournament_detail
+            ON meta_tournament_detail.tournament_id = tournament_detail_champion.tournament_id
+            AND meta_tournament_detail.tournament_service_id
+                = tournament_detail_champion.tournament_service_id",
+        updated: "meta_tournament_detail.last_attempt",
+        order: "tournament_detail_champion.tournament_service_id,
+            tournament_detail_champion.tournament_id, tournament_detail_champion.fighter_id",
+    },
+    Table {
+        name: "tournament_detail_attack",
+        columns: &[
+            column(
+                "tournament_id",
+                Kind::Int,
+                "tournament_detail_attack.tournament_id",
+            ),
+            column(
+                "tournament_service_id",
+                Kind::Int,
+                "tournament_detail_attack.tournament_service_id",
+            ),
+            column(
+                "fighter_id",
+                Kind::Int,
+                "tournament_detail_attack.fighter_id",
+            ),
+            column("round", Kind::Int, "tournament_detail_attack.round"),
+            column(
+                "special_attack",
+                Kind::Bool,
+                "tournament_detail_attack.special_attack",
+            ),
+            column(
+                "special_defend",
+                Kind::Bool,
+                "tournament_detail_attack.speical_defend",
+            ),
+            column("damage", Kind::Int, "tournament_detail_attack.damage"),
+            column("order", Kind::Int, r#"tournament_detail_attack."order""#),
+        ],
+        from: "tournament_detail_attack JOIN meta_tournament_detail
+            ON meta_tournament_detail.tournament_id = tournament_detail_attack.tournament_id
+            AND meta_tournament_detail.tournament_service_id
+                = tournament_detail_attack.tournament_service_id",
+        updated: "meta_tournament_detail.last_attempt",
+        order: r#"tournament_detail_attack.tournament_service_id,
+            tournament_detail_attack.tournament_id, tournament_detail_attack.round,
+            tournament_detail_attack."order""#,
+    },
+];
+
+impl Column {
+    /// The column's value as text, written the same way on every backend.
+    fn text(&self, backend: DbBackend) -> String {
+        let expr = self.expr;
+        match (self.kind, backend) {
+            (Kind::Bool, _) => {
+                format!("CASE WHEN {expr} THEN 'true' WHEN NOT {expr} THEN 'false' END")
+            }
+            // hex(NULL) is an empty string rather than NULL
+            (Kind::Address, DbBackend::Sqlite) => {
+                format!("'0x' || lower(nullif(hex({expr}), ''))")
+            }
+            (Kind::Address, _) => format!("'0x' || encode({expr}, 'hex')"),
+            _ => format!("CAST({expr} AS TEXT)"),
+        }
+    }
+}
+
+impl Table {
+    /// One page of rows, every value as text.
+    fn select(
+        &self,
+        backend: DbBackend,
+        since: Option<NaiveDateTime>,
+        page: u64,
+    ) -> (String, Vec<Value>) {
+        let columns = self
+            .columns
+            .iter()
+            .map(|c| format!(r#"{} AS "{}""#, c.text(backend), c.name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut sql = format!("SELECT {columns} FROM {}", self.from);
+        let mut values: Vec<Value> = vec![];
+        if let Some(since) = since {
+            values.push(since.into());
+            sql += &format!(" WHERE {} >= $1", self.updated);
+        }
+        sql += &format!(
+            " ORDER BY {} LIMIT {PAGE_SIZE} OFFSET {}",
+            self.order,
+            page * PAGE_SIZE
+        );
+
+        (sql, values)
+    }
+}
+
+/// A table file being written.
+enum Writer {
+    Csv(csv::Writer<File>),
+    #[cfg(feature = "parquet")]
+    Parquet(parquet::Writer),
+}
+
+impl Writer {
+    fn create(path: &Path, format: ExportFormat, table: &Table) -> Result<Self> {
+        match format {
+            ExportFormat::Csv => {
+                let mut writer = csv::Writer::from_path(path)?;
+                writer.write_record(table.columns.iter().map(|c| c.name))?;
+                Ok(Writer::Csv(writer))
+            }
+            #[cfg(feature = "parquet")]
+            ExportFormat::Parquet => Ok(Writer::Parquet(parquet::Writer::create(
+                path,
+                table.columns,
+            )?)),
+            #[cfg(not(feature = "parquet"))]
+            ExportFormat::Parquet => anyhow::bail!("Parquet export requires the parquet feature"),
+        }
+    }
+
+    fn write(&mut self, rows: &[Vec<Option<String>>]) -> Result<()> {
+        match self {
+            Writer::Csv(writer) => {
+                for row in rows {
+                    writer.write_record(row.iter().map(|v| v.as_deref().unwrap_or("")))?;
+                }
+            }
+            #[cfg(feature = "parquet")]
+            Writer::Parquet(writer) => writer.write(rows)?,
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<()> {
+        match self {
+            Writer::Csv(mut writer) => writer.flush()?,
+            #[cfg(feature = "parquet")]
+            Writer::Parquet(writer) => writer.finish()?,
+        }
+
+        Ok(())
+    }
+}
+
+/// What was exported, written next to the table files.
+#[derive(Debug, Serialize)]
+pub struct Manifest {
+    /// When the export started. Pass as `--since` to the next export to only get what changed.
+    pub exported_at: NaiveDateTime,
+    pub since: Option<NaiveDateTime>,
+    pub format: String,
+    /// Rows exported per table.
+    pub tables: BTreeMap<&'static str, u64>,
+}
+
+/// Write every table to `dir` as `{table}.{csv,parquet}`, only with rows updated since `since` if
+/// given, and a `manifest.json` describing the export.
+#[instrument(skip(conn))]
+pub async fn export(
+    conn: &DatabaseConnection,
+    dir: &Path,
+    format: ExportFormat,
+    since: Option<NaiveDateTime>,
+) -> Result<Manifest> {
+    fs::create_dir_all(dir).with_context(|| format!("could not create {dir:?}"))?;
+    let mut manifest = Manifest {
+        exported_at: Utc::now().naive_utc(),
+        since,
+        format: format.extension().to_owned(),
+        tables: BTreeMap::new(),
+    };
+
+    let backend = conn.get_database_backend();
+    let txn = match backend {
+        // SQLite transactions always read from a snapshot, and cannot be configured
+        DbBackend::Sqlite => conn.begin().await?,
+        _ => {
+            conn.begin_with_config(
+                Some(IsolationLevel::RepeatableRead),
+                Some(AccessMode::ReadOnly),
+            )
+            .await?
+        }
+    };
+
+    for table in &TABLES {
+        let path = dir.join(format!("{}.{}", table.name, format.extension()));
+        let mut writer = Writer::create(&path, format, table)?;
+        let mut exported = 0;
+
+        for page in 0.. {
+            let (sql, values) = table.select(backend, since, page);
+            let rows = txn
+                .query_all(Statement::from_sql_and_values(backend, &sql, values))
+                .await?
+                .iter()
+                .map(|row| {
+                    table
+                        .columns
+                        .iter()
+                        .map(|c| row.try_get::<Option<String>>("", c.name))
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            writer.write(&rows)?;
+            exported += rows.len() as u64;
+            if (rows.len() as u64) < PAGE_SIZE {
+                break;
+            }
+        }
+
+        writer.finish()?;
+        info!(table = table.name, rows = exported, "exported table");
+        manifest.tables.insert(table.name, exported);
+    }
+
+    txn.commit().await?;
+
+    fs::write(
+        dir.join("manifest.json"),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use entity::entities::{
+        fighter, fighter_parent, fighter_trait, tournament, tournament_detail_attack,
+        tournament_detail_champion, tournament_fighter,
+    };
+    use sea_orm::{EntityTrait, IdenStatic, Iterable, PrimaryKeyToColumn};
+
+    #[test]
+    fn incremental_exports_filter_on_updated() {
+        let fighter = &TABLES[0];
+        let (sql, values) = fighter.select(DbBackend::Postgres, None, 2);
+        assert!(sql.starts_with(r#"SELECT CAST(fighter.id AS TEXT) AS "id", "#));
+        assert!(sql.ends_with(" FROM fighter ORDER BY fighter.id LIMIT 10000 OFFSET 20000"));
+        assert!(values.is_empty());
+
+        let since = NaiveDateTime::default();
+        let (sql, values) = fighter.select(DbBackend::Postgres, Some(since), 0);
+        assert!(sql.contains(" WHERE fighter.meta_last_updated >= $1 ORDER BY"));
+        assert_eq!(values, vec![since.into()]);
+    }
+
+    /// Primary key columns of `E`.
+    fn primary_key<E: EntityTrait>() -> Vec<String> {
+        E::PrimaryKey::iter()
+            .map(|key| key.into_column().as_str().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn pages_are_ordered_by_the_whole_primary_key() {
+        let keys = [
+            primary_key::<fighter::Entity>(),
+            primary_key::<fighter_trait::Entity>(),
+            primary_key::<fighter_parent::Entity>(),
+            primary_key::<tournament::Entity>(),
+            primary_key::<tournament_fighter::Entity>(),
+            primary_key::<tournament_detail_champion::Entity>(),
+            primary_key::<tournament_detail_attack::Entity>(),
+        ];
+
+        for (table, key) in TABLES.iter().zip(keys) {
+            for column in key {
+                assert!(
+                    table.order.contains(&format!("{}.{column}", table.name))
+                        || table
+                            .order
+                            .contains(&format!(r#"{}."{column}""#, table.name)),
+                    "{} is not ordered by {column}",
+                    table.name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn addresses_are_hex_on_every_backend() {
+        let account = TABLES[4].columns[3];
+        assert_eq!(
+            account.text(DbBackend::Postgres),
+            "'0x' || encode(tournament_fighter.account, 'hex')"
+        );
+        assert_eq!(
+            account.text(DbBackend::Sqlite),
+            "'0x' || lower(nullif(hex(tournament_fighter.account), ''))"
+        );
+    }
+}
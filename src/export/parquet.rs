@@ -0,0 +1,178 @@
+use anyhow::{Context, Result};
+use arrow_array::{
+    ArrayRef, BooleanArray, Float64Array, Int64Array, RecordBatch, StringArray,
+    TimestampMicrosecondArray,
+};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use chrono::NaiveDateTime;
+use parquet::{arrow::ArrowWriter, basic::Compression, file::properties::WriterProperties};
+use std::{fs::File, path::Path, sync::Arc};
+
+use super::{Column, Kind};
+
+impl Kind {
+    fn data_type(&self) -> DataType {
+        match self {
+            Kind::Int => DataType::Int64,
+            Kind::Float => DataType::Float64,
+            Kind::Bool => DataType::Boolean,
+            Kind::Text | Kind::Address => DataType::Utf8,
+            Kind::Timestamp => DataType::Timestamp(TimeUnit::Microsecond, None),
+        }
+    }
+}
+
+/// A Parquet file written a batch of rows at a time.
+pub struct Writer {
+    schema: Arc<Schema>,
+    kinds: Vec<Kind>,
+    writer: ArrowWriter<File>,
+}
+
+impl Writer {
+    pub fn create(path: &Path, columns: &[Column]) -> Result<Self> {
+        let schema = Arc::new(Schema::new(
+            columns
+                .iter()
+                .map(|c| Field::new(c.name, c.kind.data_type(), true))
+                .collect::<Vec<_>>(),
+        ));
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let writer = ArrowWriter::try_new(File::create(path)?, schema.clone(), Some(properties))?;
+
+        Ok(Self {
+            schema,
+            kinds: columns.iter().map(|c| c.kind).collect(),
+            writer,
+        })
+    }
+
+    pub fn write(&mut self, rows: &[Vec<Option<String>>]) -> Result<()> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let arrays = self
+            .kinds
+            .iter()
+            .enumerate()
+            .map(|(i, kind)| array(*kind, rows.iter().map(|row| row[i].as_deref())))
+            .collect::<Result<Vec<_>>>()?;
+        self.writer
+            .write(&RecordBatch::try_new(self.schema.clone(), arrays)?)?;
+
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<()> {
+        self.writer.close()?;
+        Ok(())
+    }
+}
+
+/// A column of text values as read from the database, parsed into its kind.
+fn array<'a>(kind: Kind, values: impl Iterator<Item = Option<&'a str>>) -> Result<ArrayRef> {
+    Ok(match kind {
+        Kind::Int => Arc::new(
+            values
+                .map(|v| v.map(str::parse::<i64>).transpose())
+                .collect::<Result<Int64Array, _>>()?,
+        ),
+        Kind::Float => Arc::new(
+            values
+                .map(|v| v.map(str::parse::<f64>).transpose())
+                .collect::<Result<Float64Array, _>>()?,
+        ),
+        Kind::Bool => Arc::new(
+            values
+                .map(|v| v.map(|v| v == "true"))
+                .collect::<BooleanArray>(),
+        ),
+        Kind::Text | Kind::Address => Arc::new(values.collect::<StringArray>()),
+        Kind::Timestamp => Arc::new(
+            values
+                .map(|v| {
+                    v.map(|v| {
+                        NaiveDateTime::parse_from_str(v, "%Y-%m-%d %H:%M:%S%.f")
+                            .map(|t| t.and_utc().timestamp_micros())
+                            .with_context(|| format!("invalid timestamp: {v}"))
+                    })
+                    .transpose()
+                })
+                .collect::<Result<TimestampMicrosecondArray>>()?,
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Array;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    #[test]
+    fn rows_are_written_typed() {
+        let columns = [
+            Column {
+                name: "id",
+                kind: Kind::Int,
+                expr: "id",
+            },
+            Column {
+                name: "buy_in",
+                kind: Kind::Float,
+                expr: "buy_in",
+            },
+            Column {
+                name: "start_time",
+                kind: Kind::Timestamp,
+                expr: "start_time",
+            },
+        ];
+        let path = std::env::temp_dir().join(format!("export-{}.parquet", std::process::id()));
+
+        let mut writer = Writer::create(&path, &columns).unwrap();
+        writer
+            .write(&[
+                vec![
+                    Some("12".to_owned()),
+                    Some("1.5".to_owned()),
+                    Some("2022-06-30 07:30:00".to_owned()),
+                ],
+                vec![Some("13".to_owned()), None, None],
+            ])
+            .unwrap();
+        writer.finish().unwrap();
+
+        let batch = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let ids = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        let buy_ins = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        let times = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<TimestampMicrosecondArray>()
+            .unwrap();
+        assert_eq!(ids.values(), &[12, 13]);
+        assert_eq!(buy_ins.value(0), 1.5);
+        assert!(buy_ins.is_null(1));
+        assert_eq!(times.value(0), 1_656_574_200_000_000);
+    }
+}
@@ -0,0 +1,419 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use entity::entities::{fighter, fighter_parent};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, FromQueryResult,
+    QueryFilter, Statement,
+};
+use serde::Serialize;
+use std::{collections::BTreeSet, fmt::Write};
+
+/// Generations followed at most, so that a cycle in bad data cannot recurse forever.
+const MAX_DEPTH: i32 = 64;
+
+/// Every parent → child edge, from both `fighter_parent` and `fighter.mum`.
+const EDGES: &str = r#"
+    edges (child, parent) AS (
+        SELECT fighter_id, parent_id FROM fighter_parent
+        UNION
+        SELECT id, mum FROM fighter WHERE mum IS NOT NULL
+    )"#;
+
+/// A fighter related to another, `generation` steps away.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromQueryResult, Serialize)]
+pub struct Relative {
+    pub id: i64,
+    pub generation: i32,
+}
+
+/// A fighter sharing at least one parent with another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromQueryResult, Serialize)]
+pub struct Sibling {
+    pub id: i64,
+    /// 2 for full siblings, 1 for half siblings.
+    pub shared_parents: i64,
+}
+
+/// An ancestor of two fighters, along with how many generations back it is for each.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, FromQueryResult, Serialize)]
+pub struct CommonAncestor {
+    pub id: i64,
+    pub generation_a: i32,
+    pub generation_b: i32,
+}
+
+/// Follow `edges` from `id` up (`parent` from `child`) or down, closest generation first.
+async fn walk(
+    conn: &DatabaseConnection,
+    id: i64,
+    max_depth: Option<i32>,
+    from: &str,
+    to: &str,
+) -> Result<Vec<Relative>, DbErr> {
+    let sql = format!(
+        r#"WITH RECURSIVE {EDGES},
+        walk (id, generation) AS (
+            SELECT {to}, 1 FROM edges WHERE {from} = $1
+            UNION
+            SELECT edges.{to}, walk.generation + 1
+            FROM walk JOIN edges ON edges.{from} = walk.id
+            WHERE walk.generation < $2
+        )
+        SELECT id, CAST(MIN(generation) AS INTEGER) AS generation
+        FROM walk GROUP BY id ORDER BY generation, id"#
+    );
+    let depth = max_depth.unwrap_or(MAX_DEPTH).min(MAX_DEPTH);
+
+    Relative::find_by_statement(Statement::from_sql_and_values(
+        conn.get_database_backend(),
+        &sql,
+        [id.into(), depth.into()],
+    ))
+    .all(conn)
+    .await
+}
+
+/// Parents, grandparents and so on, up to `max_depth` generations back.
+pub async fn ancestors(
+    conn: &DatabaseConnection,
+    id: i64,
+    max_depth: Option<i32>,
+) -> Result<Vec<Relative>, DbErr> {
+    walk(conn, id, max_depth, "child", "parent").await
+}
+
+/// Children, grandchildren and so on, up to `max_depth` generations down.
+pub async fn descendants(
+    conn: &DatabaseConnection,
+    id: i64,
+    max_depth: Option<i32>,
+) -> Result<Vec<Relative>, DbErr> {
+    walk(conn, id, max_depth, "parent", "child").await
+}
+
+/// Fighters sharing a parent with `id`, full siblings first.
+pub async fn siblings(conn: &DatabaseConnection, id: i64) -> Result<Vec<Sibling>, DbErr> {
+    let sql = format!(
+        r#"WITH {EDGES}
+        SELECT other.child AS id, COUNT(DISTINCT other.parent) AS shared_parents
+        FROM edges own JOIN edges other ON other.parent = own.parent
+        WHERE own.child = $1 AND other.child <> $1
+        GROUP BY other.child
+        ORDER BY shared_parents DESC, id"#
+    );
+
+    Sibling::find_by_statement(Statement::from_sql_and_values(
+        conn.get_database_backend(),
+        &sql,
+        [id.into()],
+    ))
+    .all(conn)
+    .await
+}
+
+/// Length of the longest line of known ancestors, 0 for a fighter without parents.
+pub async fn generation(conn: &DatabaseConnection, id: i64) -> Result<i32, DbErr> {
+    let sql = format!(
+        r#"WITH RECURSIVE {EDGES},
+        walk (id, generation) AS (
+            SELECT parent, 1 FROM edges WHERE child = $1
+            UNION
+            SELECT edges.parent, walk.generation + 1
+            FROM walk JOIN edges ON edges.child = walk.id
+            WHERE walk.generation < $2
+        )
+        SELECT CAST(COALESCE(MAX(generation), 0) AS INTEGER) AS generation FROM walk"#
+    );
+
+    #[derive(FromQueryResult)]
+    struct Generation {
+        generation: i32,
+    }
+
+    let generation = Generation::find_by_statement(Statement::from_sql_and_values(
+        conn.get_database_backend(),
+        &sql,
+        [id.into(), MAX_DEPTH.into()],
+    ))
+    .one(conn)
+    .await?;
+
+    Ok(generation.map_or(0, |g| g.generation))
+}
+
+/// Ancestors shared by `a` and `b`, closest first. Either fighter counts as its own ancestor at
+/// generation 0, so the common ancestor of a parent and its child is the parent.
+pub async fn common_ancestors(
+    conn: &DatabaseConnection,
+    a: i64,
+    b: i64,
+) -> Result<Vec<CommonAncestor>, DbErr> {
+    let sql = format!(
+        r#"WITH RECURSIVE {EDGES},
+        walk (origin, id, generation) AS (
+            SELECT $1, $1, 0
+            UNION
+            SELECT $2, $2, 0
+            UNION
+            SELECT walk.origin, edges.parent, walk.generation + 1
+            FROM walk JOIN edges ON edges.child = walk.id
+            WHERE walk.generation < $3
+        ),
+        closest AS (
+            SELECT origin, id, MIN(generation) AS generation FROM walk GROUP BY origin, id
+        )
+        SELECT x.id, CAST(x.generation AS INTEGER) AS generation_a,
+            CAST(y.generation AS INTEGER) AS generation_b
+        FROM closest x JOIN closest y ON x.id = y.id
+        WHERE x.origin = $1 AND y.origin = $2
+        ORDER BY x.generation + y.generation, x.id"#
+    );
+
+    CommonAncestor::find_by_statement(Statement::from_sql_and_values(
+        conn.get_database_backend(),
+        &sql,
+        [a.into(), b.into(), MAX_DEPTH.into()],
+    ))
+    .all(conn)
+    .await
+}
+
+/// The closest ancestor shared by `a` and `b`, if any.
+pub async fn common_ancestor(
+    conn: &DatabaseConnection,
+    a: i64,
+    b: i64,
+) -> Result<Option<CommonAncestor>, DbErr> {
+    Ok(common_ancestors(conn, a, b).await?.into_iter().next())
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum TreeFormat {
+    Dot,
+    Json,
+}
+
+/// Ancestors and descendants of a fighter, along with the edges between them.
+#[derive(Debug, Serialize)]
+pub struct FamilyTree {
+    pub id: i64,
+    /// See [`generation`].
+    pub generation: i32,
+    pub ancestors: Vec<Relative>,
+    pub descendants: Vec<Relative>,
+    /// `(parent, child)` pairs.
+    pub edges: BTreeSet<(i64, i64)>,
+    pub siblings: Vec<Sibling>,
+    /// Ancestors shared with another fighter, when asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub common_ancestors: Option<Vec<CommonAncestor>>,
+}
+
+impl FamilyTree {
+    pub async fn load(
+        conn: &DatabaseConnection,
+        id: i64,
+        max_depth: Option<i32>,
+        with: Option<i64>,
+    ) -> Result<Self> {
+        let ancestors = ancestors(conn, id, max_depth).await?;
+        let descendants = descendants(conn, id, max_depth).await?;
+
+        let ids = ancestors
+            .iter()
+            .chain(&descendants)
+            .map(|r| r.id)
+            .chain([id])
+            .collect::<BTreeSet<_>>();
+
+        let mut edges = fighter_parent::Entity::find()
+            .filter(fighter_parent::Column::FighterId.is_in(ids.iter().copied()))
+            .filter(fighter_parent::Column::ParentId.is_in(ids.iter().copied()))
+            .all(conn)
+            .await?
+            .into_iter()
+            .map(|m| (m.parent_id, m.fighter_id))
+            .collect::<BTreeSet<_>>();
+        edges.extend(
+            fighter::Entity::find()
+                .filter(fighter::Column::Id.is_in(ids.iter().copied()))
+                .all(conn)
+                .await?
+                .into_iter()
+                .filter_map(|m| m.mum.filter(|mum| ids.contains(mum)).map(|mum| (mum, m.id))),
+        );
+
+        let common_ancestors = match with {
+            Some(other) => Some(common_ancestors(conn, id, other).await?),
+            None => None,
+        };
+
+        Ok(Self {
+            id,
+            generation: generation(conn, id).await?,
+            ancestors,
+            descendants,
+            edges,
+            siblings: siblings(conn, id).await?,
+            common_ancestors,
+        })
+    }
+
+    /// Graphviz rendering, with the fighter itself filled in and common ancestors boxed.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph lineage {\n    rankdir=TB;\n");
+        let _ = writeln!(dot, "    \"{}\" [style=filled];", self.id);
+        for ancestor in self.common_ancestors.iter().flatten() {
+            let _ = writeln!(dot, "    \"{}\" [shape=box];", ancestor.id);
+        }
+        for (parent, child) in &self.edges {
+            let _ = writeln!(dot, "    \"{parent}\" -> \"{child}\";");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Print the family tree of `id` to stdout, including the ancestors it shares with `with`.
+pub async fn export(
+    conn: &DatabaseConnection,
+    id: i64,
+    max_depth: Option<i32>,
+    with: Option<i64>,
+    format: TreeFormat,
+) -> Result<()> {
+    let tree = FamilyTree::load(conn, id, max_depth, with).await?;
+
+    match format {
+        TreeFormat::Dot => print!("{}", tree.to_dot()),
+        TreeFormat::Json => println!("{}", serde_json::to_string_pretty(&tree)?),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dot_highlights_fighter_and_common_ancestors() {
+        let tree = FamilyTree {
+            id: 8399,
+            generation: 1,
+            ancestors: vec![],
+            descendants: vec![],
+            edges: BTreeSet::from([(240, 8399), (3071, 8399)]),
+            siblings: vec![],
+            common_ancestors: Some(vec![CommonAncestor {
+                id: 240,
+                generation_a: 1,
+                generation_b: 1,
+            }]),
+        };
+
+        assert_eq!(
+            tree.to_dot(),
+            "digraph lineage {\n    rankdir=TB;\n    \"8399\" [style=filled];\n    \"240\" [shape=box];\n    \"240\" -> \"8399\";\n    \"3071\" -> \"8399\";\n}\n"
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn pedigree_queries_follow_both_kinds_of_edges() {
+        use crate::testing;
+        use sea_orm::{IntoActiveModel, Set};
+
+        let conn = testing::database().await;
+        testing::without_foreign_keys(&conn).await;
+
+        // 3 and 4 are full siblings, 6 and 10 half siblings through 4, 5 only knows its mum and 8
+        // and 9 are each other's parent
+        fighter::Entity::insert_many([1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11].map(|id| {
+            fighter::Model {
+                mum: (id == 5).then_some(3),
+                ..testing::fighter(id)
+            }
+            .into_active_model()
+        }))
+        .exec(&conn)
+        .await
+        .unwrap();
+        fighter_parent::Entity::insert_many(
+            [
+                (3, 1),
+                (3, 2),
+                (4, 1),
+                (4, 2),
+                (6, 4),
+                (6, 7),
+                (10, 4),
+                (10, 11),
+                (8, 9),
+                (9, 8),
+            ]
+            .map(|(child, parent)| fighter_parent::ActiveModel {
+                fighter_id: Set(child),
+                parent_id: Set(parent),
+            }),
+        )
+        .exec(&conn)
+        .await
+        .unwrap();
+
+        let relatives = |relatives: Vec<Relative>| {
+            relatives
+                .into_iter()
+                .map(|r| (r.id, r.generation))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            relatives(ancestors(&conn, 5, None).await.unwrap()),
+            [(3, 1), (1, 2), (2, 2)]
+        );
+        assert_eq!(
+            relatives(ancestors(&conn, 5, Some(1)).await.unwrap()),
+            [(3, 1)]
+        );
+        assert_eq!(
+            relatives(descendants(&conn, 1, None).await.unwrap()),
+            [(3, 1), (4, 1), (5, 2), (6, 2), (10, 2)]
+        );
+        assert_eq!(
+            relatives(ancestors(&conn, 8, None).await.unwrap()),
+            [(9, 1), (8, 2)]
+        );
+
+        let shared = |siblings: Vec<Sibling>| {
+            siblings
+                .into_iter()
+                .map(|s| (s.id, s.shared_parents))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(shared(siblings(&conn, 3).await.unwrap()), [(4, 2)]);
+        assert_eq!(shared(siblings(&conn, 6).await.unwrap()), [(10, 1)]);
+        assert_eq!(shared(siblings(&conn, 5).await.unwrap()), []);
+
+        assert_eq!(generation(&conn, 1).await.unwrap(), 0);
+        assert_eq!(generation(&conn, 5).await.unwrap(), 2);
+        // A cycle is only followed as deep as allowed
+        assert_eq!(generation(&conn, 8).await.unwrap(), MAX_DEPTH);
+
+        let common = |ancestors: Vec<CommonAncestor>| {
+            ancestors
+                .into_iter()
+                .map(|a| (a.id, a.generation_a, a.generation_b))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            common(common_ancestors(&conn, 5, 6).await.unwrap()),
+            [(1, 2, 2), (2, 2, 2)]
+        );
+        // Each fighter is its own ancestor at generation 0
+        assert_eq!(
+            common(common_ancestors(&conn, 3, 5).await.unwrap()),
+            [(3, 0, 1), (1, 1, 2), (2, 1, 2)]
+        );
+        assert_eq!(common_ancestor(&conn, 5, 7).await.unwrap(), None);
+    }
+}
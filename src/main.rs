@@ -1,48 +1,440 @@
-use anyhow::Context;
 use anyhow::Result;
+use anyhow::{ensure, Context};
+use archive::{Archive, ArchiveStore};
+use chrono::{NaiveDate, NaiveDateTime};
+use clap::{Parser, Subcommand};
+use client::{Client, HostLimit, ALCHEMY_HOST, FEDERATION_HOST};
+use ethers_core::types::Address;
+use export::ExportFormat;
+use lineage::TreeFormat;
 use migration::MigratorTrait;
+use pnl::{ReportFormat, Window};
+use price::PriceFile;
 use sea_orm::ConnectOptions;
-use sea_orm::Database;
+use sea_orm::{Database, DatabaseConnection};
+use simulator::{validation, Entrant};
+use std::collections::HashMap;
 use std::env;
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::time::Duration;
 use task::fighter::ChampionTask;
 use task::tournament::TournamentTask;
+use tokio::time::MissedTickBehavior;
 
+pub mod archive;
+pub mod breeding;
+pub mod client;
+pub mod combat;
+pub mod currency;
+pub mod dead_letter;
+pub mod error;
+pub mod export;
+pub mod lineage;
+pub mod matchup;
+pub mod metrics;
+pub mod pnl;
+pub mod price;
+pub mod rarity;
+pub mod rating;
+pub mod server;
+pub mod simulator;
 pub mod task;
+pub mod telemetry;
+#[cfg(all(test, feature = "sqlite"))]
+mod testing;
 
 const CONCURRENT_REQUESTS: usize = 128;
 
 /// 2 hours
 const SCRAPE_INTERVAL: u64 = 2 * 60 * 60 * 1000;
 
+/// 15 minutes
+const REFRESH_INTERVAL: u64 = 15 * 60 * 1000;
+
 const TOURNAMENT_PAGE_SIZE: u64 = 128;
 
+const FEDERATION_LIMIT: HostLimit = HostLimit {
+    requests_per_second: 50.0,
+    burst: 50.0,
+    max_concurrency: CONCURRENT_REQUESTS,
+};
+
+const ALCHEMY_LIMIT: HostLimit = HostLimit {
+    requests_per_second: 5.0,
+    burst: 5.0,
+    max_concurrency: 4,
+};
+
+/// Scrape champion/tournament information for The Red Village.
+#[derive(Debug, Parser)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum Command {
+    /// Continuously scrape champions and tournaments (default).
+    Scrape,
+    /// Re-parse stored dead letters and ingest the ones that now succeed.
+    Reparse,
+    /// Rebuild the fighter, tournament and tournament detail tables from the response archive.
+    Reingest,
+    /// Print the family tree of a fighter.
+    Lineage {
+        id: i64,
+        #[arg(long, value_enum, default_value_t = TreeFormat::Json)]
+        format: TreeFormat,
+        /// Generations to follow up and down.
+        #[arg(long)]
+        depth: Option<i32>,
+        /// Another fighter to find common ancestors with.
+        #[arg(long)]
+        with: Option<i64>,
+    },
+    /// Estimate the traits and wisdom of a child summoned from two fighters.
+    Breeding {
+        a: i64,
+        b: i64,
+        /// Rebuild the frequency tables first rather than using the ones from the last scan.
+        #[arg(long)]
+        refresh: bool,
+    },
+    /// Recompute ratings from all stored battles and print how well they predicted them.
+    Ratings,
+    /// Print the battles and placements of one fighter against another.
+    Matchup {
+        a: i64,
+        b: i64,
+        /// Count newly completed tournaments first rather than waiting for the next scan.
+        #[arg(long)]
+        refresh: bool,
+    },
+    /// Print a fighter's damage and combat statistics next to its wisdom ranges.
+    Combat {
+        id: i64,
+        /// Recompute the fighter's statistics from its attacks first.
+        #[arg(long)]
+        refresh: bool,
+    },
+    /// Simulate battles between a lineup of fighters, given as `ID` or `ID:STANCE`.
+    Simulate {
+        #[arg(required_unless_present = "validate")]
+        lineup: Vec<Entrant>,
+        /// Simulated battles per pair of fighters.
+        #[arg(long, default_value_t = 1000)]
+        runs: u32,
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Compare simulated chances with recorded battles instead.
+        #[arg(long)]
+        validate: bool,
+        /// Share of the latest tournaments to validate on rather than fit on.
+        #[arg(long, default_value_t = 0.2)]
+        holdout: f64,
+    },
+    /// Report each wallet's spend, winnings and net profit by tournament type and time window.
+    Pnl {
+        /// Only report this wallet.
+        #[arg(long)]
+        account: Option<Address>,
+        #[arg(long, value_enum, default_value_t = Window::Month)]
+        window: Window,
+        /// Only tournaments starting on or after this date.
+        #[arg(long)]
+        since: Option<NaiveDate>,
+        /// Only tournaments starting before this date.
+        #[arg(long)]
+        until: Option<NaiveDate>,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Json)]
+        format: ReportFormat,
+    },
+    /// Convert tournament buy-ins and prize pools to USD with prices from a CSV or JSON file.
+    Prices {
+        file: PathBuf,
+        /// Convert every tournament again, not only those that changed since.
+        #[arg(long)]
+        all: bool,
+    },
+    /// Export the fighter, tournament and battle tables to CSV or Parquet files in a directory.
+    Export {
+        dir: PathBuf,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// Only rows updated since this time, such as the `exported_at` of a previous export.
+        #[arg(long, value_parser = price::parse_time)]
+        since: Option<NaiveDateTime>,
+    },
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args = Args::parse();
     dotenv::dotenv().ok();
-    tracing_subscriber::fmt::init();
+    let _telemetry = telemetry::init()?;
 
     let opt = ConnectOptions::new(env::var("DATABASE_URL").context("DATABASE_URL not set")?)
         .sqlx_logging(false)
         .to_owned();
-    let alchemy_api_key = env::var("ALCHEMY_API_KEY").context("ALCHEMY_API_KEY not set")?;
     let database = Database::connect(opt).await?;
 
     // Run migrations
     migration::Migrator::up(&database, None).await?;
 
-    let client = reqwest::Client::new();
+    match args.command.unwrap_or(Command::Scrape) {
+        Command::Scrape => {
+            let archive = archive(&database);
+            let (champion_task, tournament_task) = tasks(&database, archive)?;
+            scrape(database, champion_task, tournament_task).await
+        }
+        Command::Reparse => {
+            let (champion_task, tournament_task) = tasks(&database, archive(&database))?;
+            dead_letter::reparse(&database, &champion_task, &tournament_task).await
+        }
+        Command::Reingest => {
+            let archive = archive(&database).context("ARCHIVE not set")?;
+            let (champion_task, tournament_task) = tasks(&database, Some(archive.clone()))?;
+            archive::reingest(&database, &archive, &champion_task, &tournament_task).await
+        }
+        Command::Lineage {
+            id,
+            format,
+            depth,
+            with,
+        } => lineage::export(&database, id, depth, with, format).await,
+        Command::Breeding { a, b, refresh } => {
+            if refresh {
+                breeding::refresh(&database).await?;
+            }
+            breeding::print_estimate(&database, a, b).await
+        }
+        Command::Ratings => rating::print_backtest(&database).await,
+        Command::Matchup { a, b, refresh } => {
+            if refresh {
+                matchup::refresh(&database).await?;
+            }
+            matchup::print_history(&database, a, b).await
+        }
+        Command::Combat { id, refresh } => {
+            if refresh {
+                combat::update(&database, &[id]).await?;
+            }
+            combat::print_summary(&database, id).await
+        }
+        Command::Simulate {
+            lineup,
+            runs,
+            seed,
+            validate,
+            holdout,
+        } => {
+            if validate {
+                validation::print_report(&database, holdout, runs, seed).await
+            } else {
+                simulator::print_lineup(&database, &lineup, runs, seed).await
+            }
+        }
+        Command::Pnl {
+            account,
+            window,
+            since,
+            until,
+            format,
+        } => pnl::print_report(&database, account, window, since, until, format).await,
+        Command::Prices { file, all } => price::refresh(&database, &PriceFile::load(&file)?, all)
+            .await
+            .map(|_| ()),
+        Command::Export { dir, format, since } => {
+            let manifest = export::export(&database, &dir, format, since).await?;
+            println!("{}", serde_json::to_string_pretty(&manifest)?);
+            Ok(())
+        }
+    }
+}
+
+/// The response archive, if `ARCHIVE` is set.
+fn archive(database: &DatabaseConnection) -> Option<Archive> {
+    env::var("ARCHIVE")
+        .ok()
+        .filter(|setting| !setting.is_empty())
+        .map(|setting| Archive::new(database.clone(), ArchiveStore::from_setting(&setting)))
+}
+
+/// Set up the scrape tasks with a client limited per host.
+fn tasks(
+    database: &DatabaseConnection,
+    archive: Option<Archive>,
+) -> Result<(ChampionTask, TournamentTask)> {
+    let alchemy_api_key = env::var("ALCHEMY_API_KEY").context("ALCHEMY_API_KEY not set")?;
+
+    let client = Client::new(
+        reqwest::Client::new(),
+        HashMap::from([
+            (
+                FEDERATION_HOST.to_owned(),
+                host_limit("FEDERATION", FEDERATION_LIMIT)?,
+            ),
+            (
+                ALCHEMY_HOST.to_owned(),
+                host_limit("ALCHEMY", ALCHEMY_LIMIT)?,
+            ),
+        ]),
+    );
+
+    let champion_task = ChampionTask::new(
+        client.clone(),
+        database.clone(),
+        alchemy_api_key,
+        archive.clone(),
+    );
+    let tournament_task =
+        TournamentTask::new(client, database.clone(), TOURNAMENT_PAGE_SIZE, archive);
+
+    Ok((champion_task, tournament_task))
+}
+
+/// Continuously scrape champions and tournaments, and recompute everything derived from them.
+async fn scrape(
+    database: DatabaseConnection,
+    champion_task: ChampionTask,
+    tournament_task: TournamentTask,
+) -> Result<()> {
+    // Optionally convert tournaments to USD, rereading the price file every scan
+    let prices = env::var("PRICES")
+        .ok()
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from);
 
+    // Optionally expose metrics, health and status over HTTP
+    if let Some(addr) = env::var("HTTP_ADDR").ok().filter(|addr| !addr.is_empty()) {
+        let addr = addr.parse::<SocketAddr>().context("invalid HTTP_ADDR")?;
+        let conn = database.clone();
+        tokio::spawn(async move {
+            if let Err(e) = server::serve(addr, conn).await {
+                tracing::error!(e = ?e, "http server stopped");
+            }
+        });
+    }
+
+    // A scan can outlast either interval, so wait a full interval after it rather than bursting
+    // through the ticks it missed
     let mut interval = tokio::time::interval(Duration::from_millis(SCRAPE_INTERVAL));
-    let champion_task = ChampionTask::new(client.clone(), database.clone(), alchemy_api_key);
-    let tournament_task = TournamentTask::new(client, database, TOURNAMENT_PAGE_SIZE);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut refresh_interval = tokio::time::interval(Duration::from_millis(REFRESH_INTERVAL));
+    refresh_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
     loop {
-        interval.tick().await;
-        // Rescan all champions
-        let _ = champion_task.scan().await;
+        tokio::select! {
+            _ = interval.tick() => {
+                // Rescan all champions
+                let _ = metrics::observe_scan("champion", champion_task.scan()).await;
+
+                // Recount inheritance and rarity with the fresh lineage and traits
+                let _ = metrics::observe_scan("breeding", breeding::refresh(&database)).await;
+                let _ = metrics::observe_scan("rarity", rarity::refresh(&database)).await;
+
+                // Fetch new tournaments
+                let _ = metrics::observe_scan("tournament", tournament_task.scan()).await;
+
+                // Replay battles, including any newly scraped details
+                let _ = metrics::observe_scan("rating", rating::refresh(&database)).await;
+                let _ = metrics::observe_scan("matchup", matchup::refresh(&database)).await;
+                let _ = metrics::observe_scan("combat", combat::backfill(&database)).await;
+
+                if let Some(path) = &prices {
+                    let _ = metrics::observe_scan("price", async {
+                        price::refresh(&database, &PriceFile::load(path)?, false).await
+                    })
+                    .await;
+                }
+            }
+            _ = refresh_interval.tick() => {
+                // Revisit tournaments that are still open
+                let _ = metrics::observe_scan("refresh", tournament_task.refresh()).await;
+            }
+        }
+    }
+}
+
+/// Read a host's limits from `{prefix}_REQUESTS_PER_SECOND`, `{prefix}_BURST` and
+/// `{prefix}_MAX_CONCURRENCY`, falling back to `default` for any that are not set.
+fn host_limit(prefix: &str, default: HostLimit) -> Result<HostLimit> {
+    fn var<T: std::str::FromStr>(name: String, default: T) -> Result<T>
+    where
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        match env::var(&name) {
+            Ok(v) if !v.is_empty() => v.parse().with_context(|| format!("invalid {name}")),
+            _ => Ok(default),
+        }
+    }
+
+    let limit = HostLimit {
+        requests_per_second: var(
+            format!("{prefix}_REQUESTS_PER_SECOND"),
+            default.requests_per_second,
+        )?,
+        burst: var(format!("{prefix}_BURST"), default.burst)?,
+        max_concurrency: var(format!("{prefix}_MAX_CONCURRENCY"), default.max_concurrency)?,
+    };
+    validate_host_limit(prefix, &limit)?;
+
+    Ok(limit)
+}
+
+/// Reject limits the token bucket cannot work with, as a zero rate would never refill and a
+/// burst below one would never hold enough tokens for a request.
+fn validate_host_limit(prefix: &str, limit: &HostLimit) -> Result<()> {
+    // NaN fails these comparisons, so it is rejected as well
+    ensure!(
+        limit.requests_per_second > 0.0,
+        "invalid {prefix}_REQUESTS_PER_SECOND: must be greater than 0"
+    );
+    ensure!(
+        limit.burst >= 1.0,
+        "invalid {prefix}_BURST: must be at least 1"
+    );
+    ensure!(
+        limit.max_concurrency > 0,
+        "invalid {prefix}_MAX_CONCURRENCY: must be greater than 0"
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_limits_must_let_requests_through() {
+        let valid = HostLimit {
+            requests_per_second: 0.5,
+            burst: 1.0,
+            max_concurrency: 1,
+        };
+        assert!(validate_host_limit("TEST", &valid).is_ok());
 
-        // Fetch new tournaments
-        let _ = tournament_task.scan().await;
+        for invalid in [
+            HostLimit {
+                requests_per_second: 0.0,
+                ..valid
+            },
+            HostLimit {
+                requests_per_second: f64::NAN,
+                ..valid
+            },
+            HostLimit {
+                burst: 0.5,
+                ..valid
+            },
+            HostLimit {
+                max_concurrency: 0,
+                ..valid
+            },
+        ] {
+            assert!(validate_host_limit("TEST", &invalid).is_err());
+        }
     }
 }
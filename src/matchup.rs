@@ -0,0 +1,462 @@
+use anyhow::Result;
+use chrono::{NaiveDateTime, Utc};
+use entity::entities::{
+    class_matchup, head_to_head, matchup_tournament, stance_matchup, tournament_detail_champion,
+};
+use itertools::Itertools;
+use sea_orm::{
+    sea_query::{Expr, OnConflict},
+    ActiveValue::Set,
+    ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DatabaseTransaction, DbErr,
+    EntityTrait, FromQueryResult, QueryFilter, Statement, TransactionTrait,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use tracing::{info, instrument};
+
+use crate::{
+    metrics,
+    rating::{self, Game},
+    task::excluded,
+};
+
+/// Tournaments counted per transaction.
+const TOURNAMENT_CHUNK: usize = 100;
+
+/// Completed tournaments with battles that are not in the matchup tables yet. Details of
+/// tournaments that have not completed may still change, so they are left for a later refresh.
+const UNCOUNTED: &str = r#"
+    SELECT id, service_id, start_time FROM tournament
+    WHERE status = 'completed'
+        AND EXISTS (
+            SELECT 1 FROM tournament_detail_attack
+            WHERE tournament_id = tournament.id AND tournament_service_id = tournament.service_id
+        )
+        AND NOT EXISTS (
+            SELECT 1 FROM matchup_tournament
+            WHERE tournament_id = tournament.id AND tournament_service_id = tournament.service_id
+        )
+    ORDER BY start_time, service_id, id"#;
+
+/// Class matchups, regrouped from `head_to_head` since a fighter's class may only become known
+/// after its battles were counted.
+const CLASS_MATCHUP: &str = r#"
+    INSERT INTO class_matchup (character_class, opponent_class, wins, losses)
+    SELECT fighter.character_class, opponent.character_class, SUM(wins), SUM(losses)
+    FROM head_to_head
+    JOIN fighter ON fighter.id = head_to_head.fighter_id
+    JOIN fighter opponent ON opponent.id = head_to_head.opponent_id
+    WHERE fighter.character_class IS NOT NULL AND opponent.character_class IS NOT NULL
+    GROUP BY 1, 2
+    HAVING SUM(wins) + SUM(losses) > 0"#;
+
+/// Tournaments where both `a` and `b` were champions.
+const SHARED: &str = r#"
+    SELECT id, service_id, start_time FROM tournament
+    WHERE EXISTS (
+            SELECT 1 FROM tournament_detail_champion
+            WHERE tournament_id = tournament.id AND tournament_service_id = tournament.service_id
+                AND fighter_id = $1
+        )
+        AND EXISTS (
+            SELECT 1 FROM tournament_detail_champion
+            WHERE tournament_id = tournament.id AND tournament_service_id = tournament.service_id
+                AND fighter_id = $2
+        )
+    ORDER BY start_time, service_id, id"#;
+
+#[derive(Debug, FromQueryResult)]
+struct MatchupTournament {
+    id: i64,
+    service_id: i32,
+    start_time: NaiveDateTime,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Record {
+    pub wins: i32,
+    pub losses: i32,
+    pub tournaments: i32,
+    pub finished_ahead: i32,
+    pub finished_behind: i32,
+    pub last_met: Option<NaiveDateTime>,
+}
+
+/// Matchups of a batch of tournaments, added onto the stored ones in one go.
+#[derive(Debug, Default)]
+pub struct Tally {
+    /// By `(fighter, opponent)`, with every pair counted both ways.
+    pub head_to_head: HashMap<(i64, i64), Record>,
+    /// Wins and losses by `(stance, opponent stance)`.
+    pub stances: HashMap<(i32, i32), (i64, i64)>,
+}
+
+impl Tally {
+    /// Count one tournament's `games`, with the `stances` its champions entered with.
+    ///
+    /// Every pair of fighters with battles in the tournament shares it, and the one with more
+    /// wins finished ahead.
+    pub fn add(&mut self, start_time: NaiveDateTime, games: &[Game], stances: &HashMap<i64, i32>) {
+        let placements = rating::placements(games);
+        for ((a, (_, _, placement_a)), (b, (_, _, placement_b))) in
+            placements.iter().tuple_combinations()
+        {
+            for (fighter, opponent, ahead, behind) in [
+                (a, b, placement_a < placement_b, placement_a > placement_b),
+                (b, a, placement_b < placement_a, placement_b > placement_a),
+            ] {
+                let record = self.head_to_head.entry((*fighter, *opponent)).or_default();
+                record.tournaments += 1;
+                record.finished_ahead += i32::from(ahead);
+                record.finished_behind += i32::from(behind);
+                record.last_met = record.last_met.max(Some(start_time));
+            }
+        }
+
+        for game in games {
+            self.head_to_head
+                .entry((game.winner, game.loser))
+                .or_default()
+                .wins += 1;
+            self.head_to_head
+                .entry((game.loser, game.winner))
+                .or_default()
+                .losses += 1;
+
+            if let (Some(winner), Some(loser)) =
+                (stances.get(&game.winner), stances.get(&game.loser))
+            {
+                self.stances.entry((*winner, *loser)).or_default().0 += 1;
+                self.stances.entry((*loser, *winner)).or_default().1 += 1;
+            }
+        }
+    }
+}
+
+async fn save(txn: &DatabaseTransaction, tally: Tally) -> Result<(), DbErr> {
+    let records = tally
+        .head_to_head
+        .into_iter()
+        .filter_map(|((fighter_id, opponent_id), record)| {
+            Some(head_to_head::ActiveModel {
+                fighter_id: Set(fighter_id),
+                opponent_id: Set(opponent_id),
+                wins: Set(record.wins),
+                losses: Set(record.losses),
+                tournaments: Set(record.tournaments),
+                finished_ahead: Set(record.finished_ahead),
+                finished_behind: Set(record.finished_behind),
+                last_met: Set(record.last_met?),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for records in &records.into_iter().chunks(100) {
+        use head_to_head::*;
+
+        let records = records.collect::<Vec<_>>();
+        let rows = records.len();
+        Entity::insert_many(records)
+            .on_conflict(
+                OnConflict::columns([Column::FighterId, Column::OpponentId])
+                    .values([
+                        (
+                            Column::Wins,
+                            Expr::col((Entity, Column::Wins)).add(excluded(Column::Wins)),
+                        ),
+                        (
+                            Column::Losses,
+                            Expr::col((Entity, Column::Losses)).add(excluded(Column::Losses)),
+                        ),
+                        (
+                            Column::Tournaments,
+                            Expr::col((Entity, Column::Tournaments))
+                                .add(excluded(Column::Tournaments)),
+                        ),
+                        (
+                            Column::FinishedAhead,
+                            Expr::col((Entity, Column::FinishedAhead))
+                                .add(excluded(Column::FinishedAhead)),
+                        ),
+                        (
+                            Column::FinishedBehind,
+                            Expr::col((Entity, Column::FinishedBehind))
+                                .add(excluded(Column::FinishedBehind)),
+                        ),
+                        (
+                            Column::LastMet,
+                            Expr::cust(
+                                "CASE WHEN head_to_head.last_met >= excluded.last_met \
+                                THEN head_to_head.last_met ELSE excluded.last_met END",
+                            ),
+                        ),
+                    ])
+                    .to_owned(),
+            )
+            .exec_without_returning(txn)
+            .await?;
+        metrics::upserted("head_to_head", rows);
+    }
+
+    let stances = tally
+        .stances
+        .into_iter()
+        .map(
+            |((stance, opponent_stance), (wins, losses))| stance_matchup::ActiveModel {
+                stance: Set(stance),
+                opponent_stance: Set(opponent_stance),
+                wins: Set(wins),
+                losses: Set(losses),
+            },
+        )
+        .collect::<Vec<_>>();
+
+    if !stances.is_empty() {
+        use stance_matchup::*;
+
+        let rows = stances.len();
+        Entity::insert_many(stances)
+            .on_conflict(
+                OnConflict::columns([Column::Stance, Column::OpponentStance])
+                    .values([
+                        (
+                            Column::Wins,
+                            Expr::col((Entity, Column::Wins)).add(excluded(Column::Wins)),
+                        ),
+                        (
+                            Column::Losses,
+                            Expr::col((Entity, Column::Losses)).add(excluded(Column::Losses)),
+                        ),
+                    ])
+                    .to_owned(),
+            )
+            .exec_without_returning(txn)
+            .await?;
+        metrics::upserted("stance_matchup", rows);
+    }
+
+    Ok(())
+}
+
+/// Count the battles of completed tournaments that were not counted before into `head_to_head`
+/// and `stance_matchup`, and regroup `class_matchup`.
+///
+/// Each tournament is recorded in `matchup_tournament` in the same transaction as its matchups,
+/// so every tournament is counted exactly once. Returns how many tournaments were counted.
+#[instrument(skip_all)]
+pub async fn refresh(conn: &DatabaseConnection) -> Result<usize> {
+    let backend = conn.get_database_backend();
+
+    let tournaments =
+        MatchupTournament::find_by_statement(Statement::from_string(backend, UNCOUNTED.to_owned()))
+            .all(conn)
+            .await?;
+
+    for chunk in &tournaments.iter().chunks(TOURNAMENT_CHUNK) {
+        let chunk = chunk.collect::<Vec<_>>();
+
+        let txn = conn.begin().await?;
+        let attacks = rating::attacks(&txn, chunk.iter().map(|t| (t.id, t.service_id))).await?;
+
+        let condition = chunk.iter().fold(Condition::any(), |condition, t| {
+            condition.add(
+                Condition::all()
+                    .add(tournament_detail_champion::Column::TournamentId.eq(t.id))
+                    .add(tournament_detail_champion::Column::TournamentServiceId.eq(t.service_id)),
+            )
+        });
+        let stances = tournament_detail_champion::Entity::find()
+            .filter(condition)
+            .all(&txn)
+            .await?
+            .into_iter()
+            .into_group_map_by(|m| (m.tournament_id, m.tournament_service_id as i32));
+
+        let mut tally = Tally::default();
+        let mut counted = vec![];
+        for tournament in chunk {
+            let key = (tournament.id, tournament.service_id);
+            let games = attacks
+                .get(&key)
+                .map(|a| rating::games(a))
+                .unwrap_or_default();
+            let stances = stances
+                .get(&key)
+                .into_iter()
+                .flatten()
+                .map(|m| (m.fighter_id, m.stance))
+                .collect::<HashMap<_, _>>();
+
+            tally.add(tournament.start_time, &games, &stances);
+            counted.push(matchup_tournament::ActiveModel {
+                tournament_id: Set(tournament.id),
+                tournament_service_id: Set(tournament.service_id),
+                games: Set(games.len() as i32),
+                counted_at: Set(Utc::now().naive_utc()),
+            });
+        }
+
+        save(&txn, tally).await?;
+        matchup_tournament::Entity::insert_many(counted)
+            .exec_without_returning(&txn)
+            .await?;
+        txn.commit().await?;
+    }
+
+    conn.transaction::<_, (), DbErr>(|txn| {
+        Box::pin(async move {
+            class_matchup::Entity::delete_many().exec(txn).await?;
+            txn.execute(Statement::from_string(backend, CLASS_MATCHUP.to_owned()))
+                .await?;
+
+            Ok(())
+        })
+    })
+    .await?;
+
+    info!(tournaments = tournaments.len(), "refreshed matchups");
+
+    Ok(tournaments.len())
+}
+
+/// A tournament two fighters were both champions in.
+#[derive(Debug, Serialize)]
+pub struct Meeting {
+    pub tournament_id: i64,
+    pub service_id: i32,
+    pub start_time: NaiveDateTime,
+    /// Battles won against the opponent.
+    pub wins: i32,
+    pub losses: i32,
+    /// Placements in the tournament, if the fighter had any battles.
+    pub placement: Option<i32>,
+    pub opponent_placement: Option<i32>,
+}
+
+/// How `fighter_id` fared against `opponent_id`.
+#[derive(Debug, Serialize)]
+pub struct History {
+    pub fighter_id: i64,
+    pub opponent_id: i64,
+    pub wins: i32,
+    pub losses: i32,
+    pub finished_ahead: i32,
+    pub finished_behind: i32,
+    pub meetings: Vec<Meeting>,
+}
+
+/// Every tournament `a` and `b` were both champions in, with their battles against each other.
+/// The totals are the counted ones from `head_to_head`, so they leave out tournaments that have
+/// not completed yet.
+pub async fn history(conn: &DatabaseConnection, a: i64, b: i64) -> Result<History> {
+    let tournaments = MatchupTournament::find_by_statement(Statement::from_sql_and_values(
+        conn.get_database_backend(),
+        SHARED,
+        [a.into(), b.into()],
+    ))
+    .all(conn)
+    .await?;
+    let attacks = rating::attacks(conn, tournaments.iter().map(|t| (t.id, t.service_id))).await?;
+
+    let meetings = tournaments
+        .into_iter()
+        .map(|tournament| {
+            let games = attacks
+                .get(&(tournament.id, tournament.service_id))
+                .map(|a| rating::games(a))
+                .unwrap_or_default();
+            let placements = rating::placements(&games);
+            let count = |winner, loser| {
+                games
+                    .iter()
+                    .filter(|g| g.winner == winner && g.loser == loser)
+                    .count() as i32
+            };
+
+            Meeting {
+                tournament_id: tournament.id,
+                service_id: tournament.service_id,
+                start_time: tournament.start_time,
+                wins: count(a, b),
+                losses: count(b, a),
+                placement: placements.get(&a).map(|(_, _, placement)| *placement),
+                opponent_placement: placements.get(&b).map(|(_, _, placement)| *placement),
+            }
+        })
+        .collect();
+
+    let record = head_to_head::Entity::find_by_id((a, b))
+        .one(conn)
+        .await?
+        .unwrap_or(head_to_head::Model {
+            fighter_id: a,
+            opponent_id: b,
+            wins: 0,
+            losses: 0,
+            tournaments: 0,
+            finished_ahead: 0,
+            finished_behind: 0,
+            last_met: NaiveDateTime::default(),
+        });
+
+    Ok(History {
+        fighter_id: a,
+        opponent_id: b,
+        wins: record.wins,
+        losses: record.losses,
+        finished_ahead: record.finished_ahead,
+        finished_behind: record.finished_behind,
+        meetings,
+    })
+}
+
+/// Print the history of `a` against `b` to stdout as JSON.
+pub async fn print_history(conn: &DatabaseConnection, a: i64, b: i64) -> Result<()> {
+    let history = history(conn, a, b).await?;
+    println!("{}", serde_json::to_string_pretty(&history)?);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs_are_counted_both_ways() {
+        let start_time = NaiveDateTime::default();
+        let games = [
+            Game {
+                winner: 1,
+                loser: 2,
+            },
+            Game {
+                winner: 3,
+                loser: 4,
+            },
+            Game {
+                winner: 1,
+                loser: 3,
+            },
+        ];
+        let stances = HashMap::from([(1, 0), (2, 1), (3, 1)]);
+
+        let mut tally = Tally::default();
+        tally.add(start_time, &games, &stances);
+
+        let record = tally.head_to_head[&(1, 3)];
+        assert_eq!((record.wins, record.losses), (1, 0));
+        assert_eq!((record.finished_ahead, record.finished_behind), (1, 0));
+        assert_eq!(tally.head_to_head[&(3, 1)].losses, 1);
+
+        // 2 and 4 never fought, but finished level
+        let record = tally.head_to_head[&(2, 4)];
+        assert_eq!((record.wins, record.tournaments), (0, 1));
+        assert_eq!((record.finished_ahead, record.finished_behind), (0, 0));
+
+        // The battle against 4 has no stance for 4
+        assert_eq!(tally.stances[&(0, 1)], (2, 0));
+        assert_eq!(tally.stances[&(1, 0)], (0, 2));
+        assert_eq!(tally.stances.len(), 2);
+    }
+}
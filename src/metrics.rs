@@ -0,0 +1,182 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use prometheus::{
+    register_histogram_vec, register_int_counter_vec, register_int_gauge, register_int_gauge_vec,
+    HistogramVec, IntCounterVec, IntGauge, IntGaugeVec,
+};
+use serde::Serialize;
+use std::{collections::BTreeMap, future::Future, sync::RwLock};
+
+pub static REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "trv_requests_total",
+        "HTTP requests sent, by endpoint and response status",
+        &["endpoint", "status"]
+    )
+    .unwrap()
+});
+
+pub static RETRIES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "trv_request_retries_total",
+        "HTTP requests that failed transiently and were tried again",
+        &["endpoint"]
+    )
+    .unwrap()
+});
+
+pub static PARSE_FAILURES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "trv_parse_failures_total",
+        "Payloads that could not be deserialized and were stored as dead letters",
+        &["kind"]
+    )
+    .unwrap()
+});
+
+pub static ROWS_UPSERTED: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "trv_rows_upserted_total",
+        "Rows inserted or updated, by table",
+        &["table"]
+    )
+    .unwrap()
+});
+
+pub static SCAN_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "trv_scan_duration_seconds",
+        "How long each scan took",
+        &["task"],
+        vec![1.0, 10.0, 60.0, 300.0, 900.0, 1800.0, 3600.0, 7200.0]
+    )
+    .unwrap()
+});
+
+pub static LAST_SUCCESSFUL_SCAN: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "trv_last_successful_scan_timestamp_seconds",
+        "Unix time at which each scan last completed without error",
+        &["task"]
+    )
+    .unwrap()
+});
+
+pub static FAILED_PAGES: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "trv_failed_pages",
+        "Tournament pages waiting to be tried again"
+    )
+    .unwrap()
+});
+
+/// The most recent run of each task, by task name.
+pub static LAST_RUNS: Lazy<RwLock<BTreeMap<String, LastRun>>> = Lazy::new(Default::default);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LastRun {
+    pub started: DateTime<Utc>,
+    /// When the run ended, or `None` while it is still going.
+    pub finished: Option<DateTime<Utc>>,
+    pub succeeded: Option<bool>,
+}
+
+/// Count rows written to `table`.
+pub fn upserted(table: &str, rows: usize) {
+    ROWS_UPSERTED
+        .with_label_values(&[table])
+        .inc_by(rows as u64);
+}
+
+/// Run a scan, recording how long it took and when it last ran and succeeded.
+pub async fn observe_scan<T>(task: &str, scan: impl Future<Output = Result<T>>) -> Result<T> {
+    let started = Utc::now();
+    LAST_RUNS.write().unwrap().insert(
+        task.to_owned(),
+        LastRun {
+            started,
+            finished: None,
+            succeeded: None,
+        },
+    );
+
+    let timer = SCAN_DURATION.with_label_values(&[task]).start_timer();
+    let result = scan.await;
+    timer.observe_duration();
+
+    let finished = Utc::now();
+    if result.is_ok() {
+        LAST_SUCCESSFUL_SCAN
+            .with_label_values(&[task])
+            .set(finished.timestamp());
+    }
+
+    LAST_RUNS.write().unwrap().insert(
+        task.to_owned(),
+        LastRun {
+            started,
+            finished: Some(finished),
+            succeeded: Some(result.is_ok()),
+        },
+    );
+
+    result
+}
+
+/// Everything registered so far in the text exposition format.
+pub fn gather() -> Result<String> {
+    Ok(prometheus::TextEncoder::new().encode_to_string(&prometheus::gather())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[tokio::test]
+    async fn scans_record_their_last_run() {
+        observe_scan("metrics_test_ok", async { Ok(()) })
+            .await
+            .unwrap();
+        observe_scan::<()>("metrics_test_err", async { Err(anyhow!("failed")) })
+            .await
+            .unwrap_err();
+
+        let runs = LAST_RUNS.read().unwrap();
+        let ok = &runs["metrics_test_ok"];
+        assert_eq!(ok.succeeded, Some(true));
+        assert!(ok.finished.unwrap() >= ok.started);
+        assert_eq!(runs["metrics_test_err"].succeeded, Some(false));
+
+        assert!(
+            LAST_SUCCESSFUL_SCAN
+                .with_label_values(&["metrics_test_ok"])
+                .get()
+                > 0
+        );
+        assert_eq!(
+            LAST_SUCCESSFUL_SCAN
+                .with_label_values(&["metrics_test_err"])
+                .get(),
+            0
+        );
+        assert_eq!(
+            SCAN_DURATION
+                .with_label_values(&["metrics_test_err"])
+                .get_sample_count(),
+            1
+        );
+    }
+
+    #[test]
+    fn gather_renders_registered_families() {
+        upserted("metrics_test", 3);
+        FAILED_PAGES.set(2);
+
+        let text = gather().unwrap();
+        assert!(text.contains("# TYPE trv_rows_upserted_total counter"));
+        assert!(text.contains(r#"trv_rows_upserted_total{table="metrics_test"} 3"#));
+        assert!(text.contains("trv_failed_pages 2"));
+    }
+}
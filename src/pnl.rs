@@ -0,0 +1,413 @@
+//! Profit and loss of the wallets entering warriors into tournaments.
+//!
+//! Every warrior entered costs its wallet the tournament's buy-in. Payouts are not scraped, so
+//! winnings are estimated: the buy-ins less the fee, plus the top-up, are split between the
+//! champions placed first in the tournament's battles. Tournaments without battles only count
+//! towards spend, and 1v1 entries are left out since they do not record a wallet.
+
+use anyhow::Result;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
+use clap::ValueEnum;
+use ethers_core::types::{Address, U256};
+use itertools::Itertools;
+use sea_orm::{ConnectionTrait, DatabaseConnection, FromQueryResult, Statement, Value};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use tracing::instrument;
+
+use crate::{
+    currency::{self, Tokens},
+    rating,
+};
+
+/// Tournaments loaded per query for their battles.
+const TOURNAMENT_CHUNK: usize = 100;
+
+/// Names of the tournament types, by service ID.
+pub const SERVICES: [&str; 7] = [
+    "1v1",
+    "blooding",
+    "bloodbath",
+    "bloodelo",
+    "double_up",
+    "double_up_reverse",
+    "traditional",
+];
+
+/// Time windows the report is grouped by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Window {
+    Day,
+    Week,
+    Month,
+    All,
+}
+
+impl Window {
+    /// First day of the window `time` falls in.
+    pub fn start(&self, time: NaiveDateTime) -> Option<NaiveDate> {
+        let date = time.date();
+        match self {
+            Window::Day => Some(date),
+            Window::Week => {
+                Some(date - Duration::days(date.weekday().num_days_from_monday().into()))
+            }
+            Window::Month => date.with_day(1),
+            Window::All => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    Json,
+    Csv,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct Entry {
+    tournament_id: i64,
+    service_id: i32,
+    start_time: NaiveDateTime,
+    currency: Vec<u8>,
+    fee_percentage: i32,
+    buy_in: Vec<u8>,
+    top_up: Vec<u8>,
+    entrants: i64,
+    fighter_id: i64,
+    account: Vec<u8>,
+}
+
+/// The estimated payout of each champion placed first among `entrants`.
+pub fn payouts(
+    entrants: usize,
+    buy_in: U256,
+    top_up: U256,
+    fee_percentage: i32,
+    winners: &[i64],
+) -> HashMap<i64, U256> {
+    if winners.is_empty() {
+        return HashMap::new();
+    }
+
+    let share = currency::prize_pool(entrants, buy_in, top_up, fee_percentage) / winners.len();
+
+    winners.iter().map(|id| (*id, share)).collect()
+}
+
+/// Wallet, service ID, window start and currency.
+type Key = (Vec<u8>, i32, Option<NaiveDate>, Vec<u8>);
+
+#[derive(Clone, Debug, Default)]
+struct Totals {
+    tournaments: u64,
+    entries: u64,
+    wins: u64,
+    spend: U256,
+    winnings: U256,
+}
+
+/// One wallet's profit and loss in a currency, for a tournament type and time window.
+#[derive(Debug, Serialize)]
+pub struct Row {
+    pub account: String,
+    pub tournament_type: String,
+    /// First day of the window, empty for all time.
+    pub window: Option<NaiveDate>,
+    pub currency: String,
+    pub tournaments: u64,
+    pub entries: u64,
+    pub wins: u64,
+    pub spend: String,
+    pub winnings: String,
+    pub net: String,
+}
+
+impl Row {
+    const HEADER: [&'static str; 10] = [
+        "account",
+        "tournament_type",
+        "window",
+        "currency",
+        "tournaments",
+        "entries",
+        "wins",
+        "spend",
+        "winnings",
+        "net",
+    ];
+
+    fn fields(&self) -> [String; 10] {
+        [
+            self.account.clone(),
+            self.tournament_type.clone(),
+            self.window.map(|w| w.to_string()).unwrap_or_default(),
+            self.currency.clone(),
+            self.tournaments.to_string(),
+            self.entries.to_string(),
+            self.wins.to_string(),
+            self.spend.clone(),
+            self.winnings.clone(),
+            self.net.clone(),
+        ]
+    }
+}
+
+/// A CSV field, quoted if it needs to be.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Rows as CSV, with a header.
+pub fn to_csv(rows: &[Row]) -> String {
+    std::iter::once(Row::HEADER.map(str::to_owned))
+        .chain(rows.iter().map(Row::fields))
+        .map(|fields| fields.iter().map(|f| csv_field(f)).join(",") + "\n")
+        .collect()
+}
+
+/// Profit and loss per wallet, tournament type, currency and `window`, of tournaments that
+/// started between `since` and `until`, optionally for one `account`.
+#[instrument(skip_all)]
+pub async fn report(
+    conn: &DatabaseConnection,
+    account: Option<Address>,
+    window: Window,
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+) -> Result<Vec<Row>> {
+    let mut conditions = vec![];
+    let mut values: Vec<Value> = vec![];
+    if let Some(since) = since {
+        values.push(since.and_hms_opt(0, 0, 0).into());
+        conditions.push(format!("tournament.start_time >= ${}", values.len()));
+    }
+    if let Some(until) = until {
+        values.push(until.and_hms_opt(0, 0, 0).into());
+        conditions.push(format!("tournament.start_time < ${}", values.len()));
+    }
+    if let Some(account) = account {
+        values.push(account.as_bytes().to_vec().into());
+        conditions.push(format!("tournament_fighter.account = ${}", values.len()));
+    }
+    let where_clause = conditions
+        .iter()
+        .map(|c| format!(" AND {c}"))
+        .collect::<String>();
+
+    let entries = Entry::find_by_statement(Statement::from_sql_and_values(
+        conn.get_database_backend(),
+        &format!(
+            r#"SELECT tournament.id AS tournament_id, tournament.service_id, tournament.start_time,
+                tournament.currency, tournament.fee_percentage, tournament.buy_in, tournament.top_up,
+                (SELECT COUNT(*) FROM tournament_fighter AS entrant
+                    WHERE entrant.tournament_id = tournament.id
+                        AND entrant.tournament_service_id = tournament.service_id) AS entrants,
+                tournament_fighter.fighter_id, tournament_fighter.account
+            FROM tournament
+            JOIN tournament_fighter ON tournament_fighter.tournament_id = tournament.id
+                AND tournament_fighter.tournament_service_id = tournament.service_id
+            WHERE tournament.status <> 'cancelled'
+                AND tournament_fighter.account IS NOT NULL{where_clause}
+            ORDER BY tournament.start_time, tournament.service_id, tournament.id"#
+        ),
+        values,
+    ))
+    .all(conn)
+    .await?;
+
+    let tournaments = entries
+        .into_iter()
+        .into_group_map_by(|e| (e.tournament_id, e.service_id));
+
+    let tokens = Tokens::load(conn).await?;
+    let mut totals: BTreeMap<Key, Totals> = BTreeMap::new();
+    let keys = tournaments.keys().copied().sorted().collect::<Vec<_>>();
+    for chunk in keys.chunks(TOURNAMENT_CHUNK) {
+        let attacks = rating::attacks(conn, chunk.iter().copied()).await?;
+
+        for key in chunk {
+            let entries = &tournaments[key];
+            let first = &entries[0];
+            let buy_in = currency::amount(&first.buy_in);
+
+            let placements = attacks
+                .get(key)
+                .map(|a| rating::placements(&rating::games(a)))
+                .unwrap_or_default();
+            let winners = placements
+                .iter()
+                .filter(|(_, (_, _, placement))| *placement == 1)
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>();
+            let payouts = payouts(
+                first.entrants as usize,
+                buy_in,
+                currency::amount(&first.top_up),
+                first.fee_percentage,
+                &winners,
+            );
+
+            for (wallet, entries) in entries.iter().into_group_map_by(|e| e.account.clone()) {
+                let key = (
+                    wallet,
+                    first.service_id,
+                    window.start(first.start_time),
+                    first.currency.clone(),
+                );
+                let totals = totals.entry(key).or_default();
+                totals.tournaments += 1;
+                for entry in entries {
+                    totals.entries += 1;
+                    totals.spend += buy_in;
+                    if let Some(payout) = payouts.get(&entry.fighter_id) {
+                        totals.wins += 1;
+                        totals.winnings += *payout;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(totals
+        .into_iter()
+        .map(|((wallet, service_id, window, currency), totals)| Row {
+            account: format!("{:?}", Address::from_slice(&wallet)),
+            tournament_type: SERVICES
+                .get(service_id as usize)
+                .map_or_else(|| service_id.to_string(), |s| s.to_string()),
+            window,
+            currency: tokens.symbol(&currency),
+            tournaments: totals.tournaments,
+            entries: totals.entries,
+            wins: totals.wins,
+            spend: tokens.format(&currency, totals.spend),
+            winnings: tokens.format(&currency, totals.winnings),
+            net: tokens.format_signed(&currency, totals.winnings, totals.spend),
+        })
+        .collect())
+}
+
+/// Print the report to stdout as JSON or CSV.
+pub async fn print_report(
+    conn: &DatabaseConnection,
+    account: Option<Address>,
+    window: Window,
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+    format: ReportFormat,
+) -> Result<()> {
+    let rows = report(conn, account, window, since, until).await?;
+    match format {
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+        ReportFormat::Csv => print!("{}", to_csv(&rows)),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pool_is_split_between_winners() {
+        let split = payouts(4, U256::from(100), U256::from(40), 10, &[1, 2]);
+
+        // 4 * 100 less 10%, plus 40
+        assert_eq!(split[&1], U256::from(200));
+        assert_eq!(split[&2], U256::from(200));
+        assert!(payouts(4, U256::from(100), U256::zero(), 10, &[]).is_empty());
+    }
+
+    #[test]
+    fn windows_start_on_their_first_day() {
+        let time = NaiveDate::from_ymd_opt(2022, 6, 30)
+            .unwrap()
+            .and_hms_opt(7, 30, 0)
+            .unwrap();
+
+        assert_eq!(
+            Window::Week.start(time),
+            NaiveDate::from_ymd_opt(2022, 6, 27)
+        );
+        assert_eq!(
+            Window::Month.start(time),
+            NaiveDate::from_ymd_opt(2022, 6, 1)
+        );
+        assert_eq!(Window::All.start(time), None);
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_when_needed() {
+        assert_eq!(csv_field("1.5"), "1.5");
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn wallets_count_each_tournament_once() {
+        use crate::testing;
+        use entity::entities::{
+            sea_orm_active_enums::TournamentStatus, tournament, tournament_fighter,
+        };
+        use sea_orm::{EntityTrait, IntoActiveModel};
+
+        let conn = testing::database().await;
+        testing::without_foreign_keys(&conn).await;
+
+        tournament::Entity::insert(
+            tournament::Model {
+                currency: vec![0xab],
+                fee_percentage: 10,
+                buy_in: vec![100],
+                ..testing::tournament(1, 0, TournamentStatus::Completed)
+            }
+            .into_active_model(),
+        )
+        .exec(&conn)
+        .await
+        .unwrap();
+
+        // The same wallet entered fighters on either side of another wallet's
+        let (a, b) = (Address::repeat_byte(0xaa), Address::repeat_byte(0xbb));
+        tournament_fighter::Entity::insert_many([(1, a), (2, b), (3, a)].map(
+            |(fighter_id, account)| {
+                tournament_fighter::Model {
+                    tournament_id: 1,
+                    tournament_service_id: 0,
+                    fighter_id,
+                    account: Some(account.as_bytes().to_vec()),
+                }
+                .into_active_model()
+            },
+        ))
+        .exec(&conn)
+        .await
+        .unwrap();
+
+        let rows = report(&conn, None, Window::All, None, None).await.unwrap();
+        let counts = rows
+            .iter()
+            .map(|r| (r.account.as_str(), r.tournaments, r.entries))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            counts,
+            [
+                (format!("{a:?}").as_str(), 1, 2),
+                (format!("{b:?}").as_str(), 1, 1)
+            ]
+        );
+        assert_eq!(rows[0].spend, "200");
+
+        let rows = report(&conn, Some(b), Window::All, None, None)
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!((rows[0].tournaments, rows[0].entries), (1, 1));
+    }
+}
@@ -0,0 +1,294 @@
+//! USD values of tournament buy-ins and prize pools, from historical token prices.
+//!
+//! Tournaments are configured in token units. Each one is converted at the latest price quoted at
+//! or before its start time, and stored in `tournament_usd` next to the raw amounts in
+//! `tournament`. Tournaments paid in tokens missing from the `token` table, or without a price
+//! before they started, are left out.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use entity::entities::{token, tournament_usd};
+use ethers_core::types::Address;
+use sea_orm::{
+    sea_query::OnConflict, ConnectionTrait, DatabaseConnection, EntityTrait, FromQueryResult, Set,
+    Statement,
+};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+};
+use tracing::{info, instrument};
+
+use crate::currency::{self, Tokens};
+
+/// Rows upserted per statement.
+const CHUNK: usize = 1000;
+
+/// Tournaments that can be converted, with how many fighters entered them.
+const UNCONVERTED: &str = r#"
+    SELECT tournament.id, tournament.service_id, tournament.start_time, tournament.currency,
+        tournament.fee_percentage, tournament.buy_in, tournament.top_up,
+        (SELECT COUNT(*) FROM tournament_fighter
+            WHERE tournament_fighter.tournament_id = tournament.id
+                AND tournament_fighter.tournament_service_id = tournament.service_id) AS entrants
+    FROM tournament
+    LEFT JOIN tournament_usd ON tournament_usd.tournament_id = tournament.id
+        AND tournament_usd.tournament_service_id = tournament.service_id
+    WHERE tournament.status <> 'cancelled'"#;
+
+/// Somewhere historical token prices come from.
+pub trait PriceProvider {
+    /// The latest USD price of `token` quoted at or before `time`, and when it was quoted.
+    fn price(&self, token: &token::Model, time: NaiveDateTime) -> Option<(NaiveDateTime, f64)>;
+}
+
+/// One quoted price, as read from a price file.
+#[derive(Debug, Deserialize)]
+struct Quote {
+    /// The token's symbol or address.
+    token: String,
+    time: String,
+    usd: f64,
+}
+
+/// Prices read from a CSV or JSON file.
+///
+/// CSV files have a `token,time,usd` header, and JSON files hold an array of
+/// `{"token": ..., "time": ..., "usd": ...}` objects. Tokens are given by symbol or address, and
+/// times as dates, date times or RFC 3339 timestamps in UTC, or as Unix timestamps.
+#[derive(Debug, Default)]
+pub struct PriceFile(HashMap<String, BTreeMap<NaiveDateTime, f64>>);
+
+impl PriceFile {
+    /// Read a price file, as JSON if its extension is `.json` and as CSV otherwise.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents =
+            fs::read_to_string(path).with_context(|| format!("could not read {path:?}"))?;
+        let quotes = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => serde_json::from_str(&contents)?,
+            _ => parse_csv(&contents)?,
+        };
+
+        quotes.into_iter().collect()
+    }
+}
+
+impl FromIterator<Quote> for Result<PriceFile> {
+    fn from_iter<I: IntoIterator<Item = Quote>>(iter: I) -> Self {
+        let mut prices = PriceFile::default();
+        for quote in iter {
+            prices
+                .0
+                .entry(quote.token.trim().to_lowercase())
+                .or_default()
+                .insert(parse_time(&quote.time)?, quote.usd);
+        }
+
+        Ok(prices)
+    }
+}
+
+impl PriceProvider for PriceFile {
+    fn price(&self, token: &token::Model, time: NaiveDateTime) -> Option<(NaiveDateTime, f64)> {
+        let address = format!("{:?}", Address::from_slice(&token.address));
+        [address, token.symbol.to_lowercase()]
+            .iter()
+            .filter_map(|key| self.0.get(key)?.range(..=time).next_back())
+            .max_by_key(|(quoted, _)| **quoted)
+            .map(|(quoted, usd)| (*quoted, *usd))
+    }
+}
+
+fn parse_csv(contents: &str) -> Result<Vec<Quote>> {
+    let mut lines = contents.lines().filter(|l| !l.trim().is_empty());
+    let header = lines
+        .next()
+        .ok_or_else(|| anyhow!("price file is empty"))?
+        .split(',')
+        .map(|h| h.trim().to_lowercase())
+        .collect::<Vec<_>>();
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|h| h == name)
+            .ok_or_else(|| anyhow!("price file has no {name} column"))
+    };
+    let (token, time, usd) = (column("token")?, column("time")?, column("usd")?);
+
+    lines
+        .map(|line| {
+            let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+            let field = |i: usize| {
+                fields
+                    .get(i)
+                    .copied()
+                    .ok_or_else(|| anyhow!("price file line is too short: {line}"))
+            };
+
+            Ok(Quote {
+                token: field(token)?.to_owned(),
+                time: field(time)?.to_owned(),
+                usd: field(usd)?
+                    .parse()
+                    .with_context(|| format!("invalid price: {line}"))?,
+            })
+        })
+        .collect()
+}
+
+/// A time given as a date, a UTC date time or RFC 3339 timestamp, or a Unix timestamp.
+pub fn parse_time(time: &str) -> Result<NaiveDateTime> {
+    let time = time.trim();
+    if let Ok(t) = DateTime::parse_from_rfc3339(time) {
+        return Ok(t.naive_utc());
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"] {
+        if let Ok(t) = NaiveDateTime::parse_from_str(time, format) {
+            return Ok(t);
+        }
+    }
+    if let Ok(d) = NaiveDate::parse_from_str(time, "%Y-%m-%d") {
+        return Ok(d.and_hms_opt(0, 0, 0).unwrap());
+    }
+    if let Some(t) = time
+        .parse()
+        .ok()
+        .and_then(|s| DateTime::from_timestamp(s, 0))
+    {
+        return Ok(t.naive_utc());
+    }
+
+    bail!("invalid price time: {time}")
+}
+
+#[derive(Debug, FromQueryResult)]
+struct Unconverted {
+    id: i64,
+    service_id: i32,
+    start_time: NaiveDateTime,
+    currency: Vec<u8>,
+    fee_percentage: i32,
+    buy_in: Vec<u8>,
+    top_up: Vec<u8>,
+    entrants: i64,
+}
+
+/// Convert tournaments to USD with `prices`, either every one or only those not converted since
+/// they last changed. Returns how many tournaments were converted.
+#[instrument(skip_all)]
+pub async fn refresh(
+    conn: &DatabaseConnection,
+    prices: &impl PriceProvider,
+    all: bool,
+) -> Result<usize> {
+    let sql = if all {
+        UNCONVERTED.to_owned()
+    } else {
+        format!(
+            "{UNCONVERTED} AND (tournament_usd.tournament_id IS NULL
+                OR tournament_usd.meta_last_updated < tournament.meta_last_updated)"
+        )
+    };
+    let tournaments =
+        Unconverted::find_by_statement(Statement::from_string(conn.get_database_backend(), sql))
+            .all(conn)
+            .await?;
+    let tokens = Tokens::load(conn).await?;
+    let now = Utc::now().naive_utc();
+
+    let rows = tournaments
+        .into_iter()
+        .filter_map(|t| {
+            let token = tokens.get(&t.currency)?;
+            let (priced_at, token_usd) = prices.price(token, t.start_time)?;
+            let buy_in = currency::amount(&t.buy_in);
+            let prize_pool = currency::prize_pool(
+                t.entrants as usize,
+                buy_in,
+                currency::amount(&t.top_up),
+                t.fee_percentage,
+            );
+
+            Some(tournament_usd::ActiveModel {
+                tournament_id: Set(t.id),
+                tournament_service_id: Set(t.service_id),
+                token_usd: Set(token_usd),
+                priced_at: Set(priced_at),
+                buy_in_usd: Set(tokens.units(&t.currency, buy_in)? * token_usd),
+                prize_pool_usd: Set(tokens.units(&t.currency, prize_pool)? * token_usd),
+                meta_last_updated: Set(now),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let converted = rows.len();
+    let mut rows = rows.into_iter().peekable();
+    while rows.peek().is_some() {
+        tournament_usd::Entity::insert_many(rows.by_ref().take(CHUNK))
+            .on_conflict(
+                OnConflict::columns([
+                    tournament_usd::Column::TournamentId,
+                    tournament_usd::Column::TournamentServiceId,
+                ])
+                .update_columns([
+                    tournament_usd::Column::TokenUsd,
+                    tournament_usd::Column::PricedAt,
+                    tournament_usd::Column::BuyInUsd,
+                    tournament_usd::Column::PrizePoolUsd,
+                    tournament_usd::Column::MetaLastUpdated,
+                ])
+                .to_owned(),
+            )
+            .exec(conn)
+            .await?;
+    }
+
+    if converted > 0 {
+        info!(tournaments = converted, "converted tournaments to USD");
+    }
+
+    Ok(converted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prices_are_the_latest_quoted_before() {
+        let prices = parse_csv(
+            "token,time,usd\n\
+             WETH,2022-06-01,1800.5\n\
+             0x7ceb23fd6bc0add59e62ac25578270cff1b9f619,2022-06-02T12:00:00Z,1750\n\
+             WETH,1654387200,1900\n",
+        )
+        .unwrap()
+        .into_iter()
+        .collect::<Result<PriceFile>>()
+        .unwrap();
+        let weth = token::Model {
+            address:
+                b"\x7c\xeb\x23\xfd\x6b\xc0\xad\xd5\x9e\x62\xac\x25\x57\x82\x70\xcf\xf1\xb9\xf6\x19"
+                    .to_vec(),
+            symbol: "WETH".to_owned(),
+            decimals: 18,
+        };
+        let at = |date: &str| parse_time(date).unwrap();
+
+        assert_eq!(prices.price(&weth, at("2022-05-31")), None);
+        assert_eq!(
+            prices.price(&weth, at("2022-06-02")),
+            Some((at("2022-06-01"), 1800.5))
+        );
+        assert_eq!(
+            prices.price(&weth, at("2022-06-03")),
+            Some((at("2022-06-02 12:00:00"), 1750.0))
+        );
+        assert_eq!(
+            prices.price(&weth, at("2022-06-30")),
+            Some((at("2022-06-05"), 1900.0))
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use anyhow::Result;
+use entity::entities::{fighter_rarity, trait_frequency};
+use itertools::Itertools;
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, FromQueryResult, Statement,
+    TransactionTrait,
+};
+use tracing::{info, instrument};
+
+/// How often each trait value occurs, out of every fighter with any trait.
+const TRAIT_FREQUENCY: &str = r#"
+    INSERT INTO trait_frequency (trait_type, value, count, frequency)
+    SELECT trait_type, value, COUNT(*),
+        CAST(COUNT(*) AS DOUBLE PRECISION)
+            / (SELECT COUNT(DISTINCT fighter_id) FROM fighter_trait)
+    FROM fighter_trait
+    GROUP BY trait_type, value"#;
+
+/// The frequency of every trait of every fighter, and how many values its trait type has.
+const FIGHTER_TRAITS: &str = r#"
+    WITH values_per_type (trait_type, n) AS (
+        SELECT trait_type, COUNT(*) FROM trait_frequency GROUP BY trait_type
+    )
+    SELECT fighter_trait.fighter_id, trait_frequency.frequency, values_per_type.n
+    FROM fighter_trait
+    JOIN trait_frequency ON trait_frequency.trait_type = fighter_trait.trait_type
+        AND trait_frequency.value = fighter_trait.value
+    JOIN values_per_type ON values_per_type.trait_type = fighter_trait.trait_type
+    ORDER BY fighter_trait.fighter_id, trait_frequency.frequency"#;
+
+/// Rows inserted per statement.
+const CHUNK: usize = 1000;
+
+#[derive(Debug, FromQueryResult)]
+struct FighterTrait {
+    fighter_id: i64,
+    frequency: f64,
+    n: i64,
+}
+
+/// Score every fighter from the frequencies of its traits:
+///
+/// - `statistical`: the product of the frequencies, the chance of a random fighter having all of
+///   these traits. Lower is rarer.
+/// - `information_content`: the sum of `-log2(frequency)`, in bits. Higher is rarer, and fighters
+///   are ranked by it, tied fighters sharing a rank.
+/// - `trait_count_normalized`: the sum of `1 / frequency`, each divided by how many values its
+///   trait type has, so that trait types with many values do not dominate. Higher is rarer.
+///
+/// Scores are computed here rather than in SQL, as SQLite has no logarithms.
+fn score(traits: Vec<FighterTrait>) -> Vec<fighter_rarity::Model> {
+    let mut scores = traits
+        .into_iter()
+        .group_by(|t| t.fighter_id)
+        .into_iter()
+        .map(|(fighter_id, traits)| {
+            traits.fold(
+                fighter_rarity::Model {
+                    fighter_id,
+                    trait_count: 0,
+                    statistical: 1.0,
+                    information_content: 0.0,
+                    trait_count_normalized: 0.0,
+                    rank: 0,
+                },
+                |mut score, t| {
+                    score.trait_count += 1;
+                    score.statistical *= t.frequency;
+                    score.information_content -= t.frequency.log2();
+                    score.trait_count_normalized += 1.0 / (t.frequency * t.n as f64);
+                    score
+                },
+            )
+        })
+        .collect::<Vec<_>>();
+
+    scores.sort_by(|a, b| b.information_content.total_cmp(&a.information_content));
+    let mut previous = None;
+    for (i, score) in scores.iter_mut().enumerate() {
+        score.rank = match previous {
+            Some((information_content, rank))
+                if information_content == score.information_content =>
+            {
+                rank
+            }
+            _ => i as i32 + 1,
+        };
+        previous = Some((score.information_content, score.rank));
+    }
+
+    scores
+}
+
+/// Recompute trait frequencies and fighter rarity scores from the current traits.
+#[instrument(skip_all)]
+pub async fn refresh(conn: &DatabaseConnection) -> Result<()> {
+    let backend = conn.get_database_backend();
+
+    conn.transaction::<_, (), DbErr>(|txn| {
+        Box::pin(async move {
+            fighter_rarity::Entity::delete_many().exec(txn).await?;
+            trait_frequency::Entity::delete_many().exec(txn).await?;
+
+            txn.execute(Statement::from_string(backend, TRAIT_FREQUENCY.to_owned()))
+                .await?;
+
+            let traits = FighterTrait::find_by_statement(Statement::from_string(
+                backend,
+                FIGHTER_TRAITS.to_owned(),
+            ))
+            .all(txn)
+            .await?;
+            for chunk in score(traits).chunks(CHUNK) {
+                fighter_rarity::Entity::insert_many(
+                    chunk.iter().cloned().map(fighter_rarity::ActiveModel::from),
+                )
+                .exec(txn)
+                .await?;
+            }
+
+            Ok(())
+        })
+    })
+    .await?;
+
+    info!("refreshed fighter rarity");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fighters_with_equal_scores_share_a_rank() {
+        let traits = [
+            (1, 0.5, 2),
+            (1, 0.25, 4),
+            (2, 0.5, 2),
+            (3, 0.5, 2),
+            (3, 0.25, 4),
+        ]
+        .into_iter()
+        .map(|(fighter_id, frequency, n)| FighterTrait {
+            fighter_id,
+            frequency,
+            n,
+        })
+        .collect();
+        let scores = score(traits);
+
+        assert_eq!(
+            scores
+                .iter()
+                .map(|s| (s.fighter_id, s.rank))
+                .collect::<Vec<_>>(),
+            [(1, 1), (3, 1), (2, 3)]
+        );
+        assert_eq!(scores[0].trait_count, 2);
+        assert_eq!(scores[0].statistical, 0.125);
+        assert_eq!(scores[0].information_content, 3.0);
+        assert_eq!(scores[0].trait_count_normalized, 2.0);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn refreshes_on_sqlite() {
+        use sea_orm::QueryOrder;
+
+        let conn = crate::testing::database().await;
+        conn.execute_unprepared(
+            "INSERT INTO fighter (id, wisdom_point, strength_from, strength_to, attack_from,
+                attack_to, defence_from, defence_to, omega_from, omega_to, meta_last_updated)
+            VALUES (1, 0, 1, 1, 1, 1, 1, 1, 1, 1, '2022-06-30 07:30:00'),
+                (2, 0, 1, 1, 1, 1, 1, 1, 1, 1, '2022-06-30 07:30:00');
+            INSERT INTO fighter_trait (fighter_id, trait_type, value)
+            VALUES (1, 'Breed', 'Orc'), (1, 'Hair', 'Long'), (2, 'Breed', 'Orc');",
+        )
+        .await
+        .unwrap();
+
+        refresh(&conn).await.unwrap();
+
+        let rarity = fighter_rarity::Entity::find()
+            .order_by_asc(fighter_rarity::Column::Rank)
+            .all(&conn)
+            .await
+            .unwrap();
+        assert_eq!(
+            rarity
+                .iter()
+                .map(|r| (r.fighter_id, r.trait_count, r.rank))
+                .collect::<Vec<_>>(),
+            [(1, 2, 1), (2, 1, 2)]
+        );
+        assert_eq!(rarity[0].statistical, 0.5);
+    }
+}
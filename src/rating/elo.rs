@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use super::{Game, Rating, RatingSystem};
+
+/// Classic Elo, updated game by game in battle order.
+#[derive(Clone, Copy, Debug)]
+pub struct Elo {
+    pub initial: f64,
+    pub k: f64,
+}
+
+impl Default for Elo {
+    fn default() -> Self {
+        Self {
+            initial: 1500.0,
+            k: 32.0,
+        }
+    }
+}
+
+impl Elo {
+    /// Expected score of a player rated `a` against one rated `b`.
+    pub fn expected(a: f64, b: f64) -> f64 {
+        1.0 / (1.0 + 10f64.powf((b - a) / 400.0))
+    }
+}
+
+impl RatingSystem for Elo {
+    fn name(&self) -> &'static str {
+        "elo"
+    }
+
+    fn initial(&self) -> Rating {
+        Rating {
+            rating: self.initial,
+            deviation: 0.0,
+            volatility: 0.0,
+        }
+    }
+
+    fn rate(&self, before: &HashMap<i64, Rating>, games: &[Game]) -> HashMap<i64, Rating> {
+        let mut ratings = before.clone();
+
+        for game in games {
+            let winner = ratings[&game.winner].rating;
+            let loser = ratings[&game.loser].rating;
+            let change = self.k * (1.0 - Self::expected(winner, loser));
+
+            if let Some(r) = ratings.get_mut(&game.winner) {
+                r.rating += change;
+            }
+            if let Some(r) = ratings.get_mut(&game.loser) {
+                r.rating -= change;
+            }
+        }
+
+        ratings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn winner_takes_what_loser_gives() {
+        let elo = Elo::default();
+        let before = HashMap::from([(1, elo.initial()), (2, elo.initial())]);
+
+        let after = elo.rate(
+            &before,
+            &[Game {
+                winner: 1,
+                loser: 2,
+            }],
+        );
+
+        assert_eq!(after[&1].rating, 1516.0);
+        assert_eq!(after[&2].rating, 1484.0);
+    }
+}
@@ -0,0 +1,189 @@
+use std::{collections::HashMap, f64::consts::PI};
+
+use super::{Game, Rating, RatingSystem};
+
+/// Conversion between the Glicko and Glicko-2 scales.
+const SCALE: f64 = 173.7178;
+
+/// Convergence tolerance of the volatility iteration.
+const EPSILON: f64 = 0.000001;
+
+/// Glicko-2, with each tournament as a rating period for the fighters in it.
+///
+/// Rating periods without games are accounted for lazily: a fighter's deviation grows by one
+/// period's worth of volatility for every `period_days` since they last fought.
+#[derive(Clone, Copy, Debug)]
+pub struct Glicko2 {
+    pub initial: Rating,
+    /// System constant constraining volatility changes.
+    pub tau: f64,
+    pub period_days: f64,
+}
+
+impl Default for Glicko2 {
+    fn default() -> Self {
+        Self {
+            initial: Rating {
+                rating: 1500.0,
+                deviation: 350.0,
+                volatility: 0.06,
+            },
+            tau: 0.5,
+            period_days: 1.0,
+        }
+    }
+}
+
+fn g(phi: f64) -> f64 {
+    1.0 / (1.0 + 3.0 * phi * phi / (PI * PI)).sqrt()
+}
+
+fn e(mu: f64, mu_j: f64, phi_j: f64) -> f64 {
+    1.0 / (1.0 + (-g(phi_j) * (mu - mu_j)).exp())
+}
+
+impl Glicko2 {
+    /// New volatility, found with the Illinois algorithm from step 5 of Glickman's paper.
+    fn volatility(&self, phi: f64, sigma: f64, v: f64, delta: f64) -> f64 {
+        let a = (sigma * sigma).ln();
+        let f = |x: f64| {
+            let ex = x.exp();
+            ex * (delta * delta - phi * phi - v - ex) / (2.0 * (phi * phi + v + ex).powi(2))
+                - (x - a) / (self.tau * self.tau)
+        };
+
+        let mut big_a = a;
+        let mut big_b = if delta * delta > phi * phi + v {
+            (delta * delta - phi * phi - v).ln()
+        } else {
+            let mut k = 1.0;
+            while f(a - k * self.tau) < 0.0 {
+                k += 1.0;
+            }
+            a - k * self.tau
+        };
+
+        let mut f_a = f(big_a);
+        let mut f_b = f(big_b);
+        while (big_b - big_a).abs() > EPSILON {
+            let big_c = big_a + (big_a - big_b) * f_a / (f_b - f_a);
+            let f_c = f(big_c);
+            if f_c * f_b <= 0.0 {
+                big_a = big_b;
+                f_a = f_b;
+            } else {
+                f_a /= 2.0;
+            }
+            big_b = big_c;
+            f_b = f_c;
+        }
+
+        (big_a / 2.0).exp()
+    }
+
+    /// Rating after one period, given `(opponent, score)` results against pre-period ratings.
+    fn update(&self, player: Rating, results: &[(Rating, f64)]) -> Rating {
+        let mu = (player.rating - 1500.0) / SCALE;
+        let phi = player.deviation / SCALE;
+        let sigma = player.volatility;
+
+        if results.is_empty() {
+            return self.idle(player, self.period_days);
+        }
+
+        let (mut v_inv, mut sum) = (0.0, 0.0);
+        for (opponent, score) in results {
+            let mu_j = (opponent.rating - 1500.0) / SCALE;
+            let phi_j = opponent.deviation / SCALE;
+            let expected = e(mu, mu_j, phi_j);
+
+            v_inv += g(phi_j).powi(2) * expected * (1.0 - expected);
+            sum += g(phi_j) * (score - expected);
+        }
+        let v = 1.0 / v_inv;
+
+        let sigma = self.volatility(phi, sigma, v, v * sum);
+        let phi_star = (phi * phi + sigma * sigma).sqrt();
+        let phi = 1.0 / (1.0 / (phi_star * phi_star) + 1.0 / v).sqrt();
+        let mu = mu + phi * phi * sum;
+
+        Rating {
+            rating: mu * SCALE + 1500.0,
+            deviation: phi * SCALE,
+            volatility: sigma,
+        }
+    }
+}
+
+impl RatingSystem for Glicko2 {
+    fn name(&self) -> &'static str {
+        "glicko2"
+    }
+
+    fn initial(&self) -> Rating {
+        self.initial
+    }
+
+    fn idle(&self, rating: Rating, days: f64) -> Rating {
+        let periods = (days / self.period_days).floor().max(0.0);
+        let phi = rating.deviation / SCALE;
+        let phi = (phi * phi + periods * rating.volatility * rating.volatility).sqrt();
+
+        Rating {
+            deviation: (phi * SCALE).min(self.initial.deviation),
+            ..rating
+        }
+    }
+
+    fn rate(&self, before: &HashMap<i64, Rating>, games: &[Game]) -> HashMap<i64, Rating> {
+        let mut results: HashMap<i64, Vec<(Rating, f64)>> = HashMap::new();
+        for game in games {
+            results
+                .entry(game.winner)
+                .or_default()
+                .push((before[&game.loser], 1.0));
+            results
+                .entry(game.loser)
+                .or_default()
+                .push((before[&game.winner], 0.0));
+        }
+
+        before
+            .iter()
+            .map(|(id, rating)| {
+                let results = results.get(id).map(Vec::as_slice).unwrap_or_default();
+                (*id, self.update(*rating, results))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rating(rating: f64, deviation: f64) -> Rating {
+        Rating {
+            rating,
+            deviation,
+            volatility: 0.06,
+        }
+    }
+
+    /// The worked example from Glickman's "Example of the Glicko-2 system".
+    #[test]
+    fn matches_paper_example() {
+        let after = Glicko2::default().update(
+            rating(1500.0, 200.0),
+            &[
+                (rating(1400.0, 30.0), 1.0),
+                (rating(1550.0, 100.0), 0.0),
+                (rating(1700.0, 300.0), 0.0),
+            ],
+        );
+
+        assert!((after.rating - 1464.06).abs() < 0.01, "{after:?}");
+        assert!((after.deviation - 151.52).abs() < 0.01, "{after:?}");
+        assert!((after.volatility - 0.05999).abs() < 0.00001, "{after:?}");
+    }
+}
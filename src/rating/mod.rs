@@ -0,0 +1,418 @@
+//! Ratings computed from battle outcomes, independently of the API's `elo`.
+//!
+//! Tournaments with stored battles are replayed in `start_time` order. Every one-on-one battle is
+//! a game won by the champion landing the last attack, and a champion's placement in a tournament
+//! follows from how many battles it won. Each system's rating change per fighter and tournament is
+//! kept in `rating_delta`, and the latest ratings in `fighter_rating`.
+
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use entity::entities::{fighter_rating, rating_delta, tournament_detail_attack};
+use itertools::Itertools;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr,
+    EntityTrait, FromQueryResult, QueryFilter, QueryOrder, Statement, TransactionTrait,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use tracing::{info, instrument};
+
+use crate::metrics;
+
+pub mod elo;
+pub mod glicko2;
+
+pub use elo::Elo;
+pub use glicko2::Glicko2;
+
+/// Tournaments replayed per query for their battles.
+const TOURNAMENT_CHUNK: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rating {
+    pub rating: f64,
+    /// Rating deviation, 0 for systems without one.
+    pub deviation: f64,
+    pub volatility: f64,
+}
+
+/// A one-on-one battle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Game {
+    pub winner: i64,
+    pub loser: i64,
+}
+
+pub trait RatingSystem {
+    /// Stored in the `system` column.
+    fn name(&self) -> &'static str;
+
+    /// Rating of a fighter before its first game.
+    fn initial(&self) -> Rating;
+
+    /// Account for `days` without games before a fighter's next tournament.
+    fn idle(&self, rating: Rating, _days: f64) -> Rating {
+        rating
+    }
+
+    /// Ratings after a tournament's `games`, for every fighter in `before`.
+    fn rate(&self, before: &HashMap<i64, Rating>, games: &[Game]) -> HashMap<i64, Rating>;
+}
+
+/// How well a system's ratings before each tournament predicted its battles.
+#[derive(Debug, Default, Serialize)]
+pub struct Backtest {
+    pub system: &'static str,
+    pub fighters: usize,
+    pub games: u64,
+    /// Games won by the fighter rated higher beforehand. Games between equal ratings are left out.
+    pub predicted: u64,
+    pub accuracy: f64,
+    /// Mean absolute difference from the API's `elo`, over the fighters that have one.
+    pub official_difference: Option<f64>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct RatedTournament {
+    id: i64,
+    service_id: i32,
+    start_time: NaiveDateTime,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct OfficialDifference {
+    difference: Option<f64>,
+}
+
+struct Latest {
+    rating: Rating,
+    games: i32,
+    last_played: NaiveDateTime,
+}
+
+/// Attacks of each of `tournaments`, by `(id, service_id)`, ordered by round and attack order.
+pub async fn attacks<C: ConnectionTrait>(
+    conn: &C,
+    tournaments: impl IntoIterator<Item = (i64, i32)>,
+) -> Result<HashMap<(i64, i32), Vec<tournament_detail_attack::Model>>, DbErr> {
+    let condition =
+        tournaments
+            .into_iter()
+            .fold(Condition::any(), |condition, (id, service_id)| {
+                condition.add(
+                    Condition::all()
+                        .add(tournament_detail_attack::Column::TournamentId.eq(id))
+                        .add(tournament_detail_attack::Column::TournamentServiceId.eq(service_id)),
+                )
+            });
+
+    Ok(tournament_detail_attack::Entity::find()
+        .filter(condition)
+        .order_by_asc(tournament_detail_attack::Column::Round)
+        .order_by_asc(tournament_detail_attack::Column::Order)
+        .all(conn)
+        .await?
+        .into_iter()
+        .into_group_map_by(|m| (m.tournament_id, m.tournament_service_id as i32)))
+}
+
+/// Attacks of one tournament split into battles. Attacks are ordered by round and attack order,
+/// and a round's battles are stored one after another with a gap in `order` between them. Details
+/// stored before the gap was introduced have none, so a battle also ends when a third champion
+/// attacks.
+pub fn battles(
+    attacks: &[tournament_detail_attack::Model],
+) -> Vec<Vec<&tournament_detail_attack::Model>> {
+    let mut battles: Vec<Vec<&tournament_detail_attack::Model>> = Vec::new();
+
+    for attack in attacks {
+        match battles.last_mut() {
+            Some(battle)
+                if battle[0].round == attack.round
+                    && battle
+                        .last()
+                        .is_some_and(|last| last.order + 1 == attack.order)
+                    && (battle.iter().any(|a| a.fighter_id == attack.fighter_id)
+                        || battle.iter().map(|a| a.fighter_id).unique().count() < 2) =>
+            {
+                battle.push(attack)
+            }
+            _ => battles.push(vec![attack]),
+        }
+    }
+
+    battles
+}
+
+/// Games of one tournament's battles, each won by the champion landing the last attack.
+pub fn games(attacks: &[tournament_detail_attack::Model]) -> Vec<Game> {
+    battles(attacks)
+        .into_iter()
+        .filter_map(|battle| {
+            let winner = battle.last()?.fighter_id;
+            let loser = battle
+                .iter()
+                .map(|a| a.fighter_id)
+                .find(|id| *id != winner)?;
+            Some(Game { winner, loser })
+        })
+        .collect()
+}
+
+/// Placement of every fighter in `games`, 1 for the most wins, with ties sharing a placement.
+pub fn placements(games: &[Game]) -> HashMap<i64, (i32, i32, i32)> {
+    let mut record: BTreeMap<i64, (i32, i32)> = BTreeMap::new();
+    for game in games {
+        let winner = record.entry(game.winner).or_default();
+        winner.0 += 1;
+        winner.1 += 1;
+        record.entry(game.loser).or_default().0 += 1;
+    }
+
+    record
+        .iter()
+        .map(|(id, (played, wins))| {
+            let placement = 1 + record.values().filter(|(_, w)| w > wins).count() as i32;
+            (*id, (*played, *wins, placement))
+        })
+        .collect()
+}
+
+/// Replay every tournament with battles and rebuild `rating_delta` and `fighter_rating`.
+#[instrument(skip_all)]
+pub async fn refresh(conn: &DatabaseConnection) -> Result<Vec<Backtest>> {
+    let systems: [&dyn RatingSystem; 2] = [&Elo::default(), &Glicko2::default()];
+
+    let tournaments = RatedTournament::find_by_statement(Statement::from_string(
+        conn.get_database_backend(),
+        r#"SELECT id, service_id, start_time FROM tournament
+        WHERE EXISTS (
+            SELECT 1 FROM tournament_detail_attack
+            WHERE tournament_id = tournament.id AND tournament_service_id = tournament.service_id
+        )
+        ORDER BY start_time, service_id, id"#
+            .to_owned(),
+    ))
+    .all(conn)
+    .await?;
+
+    let mut latest: Vec<HashMap<i64, Latest>> = systems.iter().map(|_| HashMap::new()).collect();
+    let mut backtests: Vec<Backtest> = systems
+        .iter()
+        .map(|system| Backtest {
+            system: system.name(),
+            ..Default::default()
+        })
+        .collect();
+
+    // Readers keep seeing the previous ratings until the replay is complete
+    let txn = conn.begin().await?;
+    rating_delta::Entity::delete_many().exec(&txn).await?;
+    fighter_rating::Entity::delete_many().exec(&txn).await?;
+
+    for chunk in &tournaments.iter().chunks(TOURNAMENT_CHUNK) {
+        let chunk = chunk.collect::<Vec<_>>();
+        let attacks = attacks(&txn, chunk.iter().map(|t| (t.id, t.service_id))).await?;
+
+        let mut deltas = vec![];
+        for tournament in chunk {
+            let Some(attacks) = attacks.get(&(tournament.id, tournament.service_id)) else {
+                continue;
+            };
+            let games = games(attacks);
+            let placements = placements(&games);
+
+            for ((system, latest), backtest) in systems.iter().zip(&mut latest).zip(&mut backtests)
+            {
+                let before = placements
+                    .keys()
+                    .map(|id| {
+                        let rating = match latest.get(id) {
+                            Some(l) => {
+                                let idle = tournament.start_time - l.last_played;
+                                system.idle(l.rating, idle.num_seconds() as f64 / 86400.0)
+                            }
+                            None => system.initial(),
+                        };
+                        (*id, rating)
+                    })
+                    .collect::<HashMap<_, _>>();
+
+                for game in &games {
+                    let (winner, loser) = (before[&game.winner], before[&game.loser]);
+                    if winner.rating != loser.rating {
+                        backtest.games += 1;
+                        backtest.predicted += u64::from(winner.rating > loser.rating);
+                    }
+                }
+
+                let after = system.rate(&before, &games);
+                for (id, (played, wins, placement)) in &placements {
+                    deltas.push(rating_delta::ActiveModel {
+                        system: Set(system.name().to_owned()),
+                        fighter_id: Set(*id),
+                        tournament_id: Set(tournament.id),
+                        tournament_service_id: Set(tournament.service_id),
+                        start_time: Set(tournament.start_time),
+                        games: Set(*played),
+                        wins: Set(*wins),
+                        placement: Set(*placement),
+                        rating_before: Set(before[id].rating),
+                        rating_after: Set(after[id].rating),
+                        deviation_after: Set(after[id].deviation),
+                        volatility_after: Set(after[id].volatility),
+                    });
+
+                    let entry = latest.entry(*id).or_insert(Latest {
+                        rating: after[id],
+                        games: 0,
+                        last_played: tournament.start_time,
+                    });
+                    entry.rating = after[id];
+                    entry.games += played;
+                    entry.last_played = tournament.start_time;
+                }
+            }
+        }
+
+        for deltas in &deltas.into_iter().chunks(100) {
+            let deltas = deltas.collect::<Vec<_>>();
+            let rows = deltas.len();
+            rating_delta::Entity::insert_many(deltas)
+                .exec_without_returning(&txn)
+                .await?;
+            metrics::upserted("rating_delta", rows);
+        }
+    }
+
+    for ((system, latest), backtest) in systems.iter().zip(&latest).zip(&mut backtests) {
+        backtest.fighters = latest.len();
+        if backtest.games > 0 {
+            backtest.accuracy = backtest.predicted as f64 / backtest.games as f64;
+        }
+
+        let ratings = latest.iter().map(|(id, l)| fighter_rating::ActiveModel {
+            system: Set(system.name().to_owned()),
+            fighter_id: Set(*id),
+            rating: Set(l.rating.rating),
+            deviation: Set(l.rating.deviation),
+            volatility: Set(l.rating.volatility),
+            games: Set(l.games),
+            last_played: Set(l.last_played),
+        });
+        for ratings in &ratings.chunks(100) {
+            let ratings = ratings.collect::<Vec<_>>();
+            let rows = ratings.len();
+            fighter_rating::Entity::insert_many(ratings)
+                .exec_without_returning(&txn)
+                .await?;
+            metrics::upserted("fighter_rating", rows);
+        }
+
+        backtest.official_difference =
+            OfficialDifference::find_by_statement(Statement::from_sql_and_values(
+                txn.get_database_backend(),
+                r#"SELECT AVG(ABS(fighter_rating.rating - fighter.elo)) AS difference
+                FROM fighter_rating
+                JOIN fighter ON fighter.id = fighter_rating.fighter_id
+                WHERE fighter_rating.system = $1 AND fighter.elo IS NOT NULL"#,
+                [system.name().into()],
+            ))
+            .one(&txn)
+            .await?
+            .and_then(|row| row.difference);
+    }
+
+    txn.commit().await?;
+
+    info!(tournaments = tournaments.len(), "refreshed ratings");
+
+    Ok(backtests)
+}
+
+/// Replay all tournaments and print how well each system predicted them.
+pub async fn print_backtest(conn: &DatabaseConnection) -> Result<()> {
+    let backtests = refresh(conn).await?;
+    println!("{}", serde_json::to_string_pretty(&backtests)?);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attack(round: i32, order: i32, fighter_id: i64) -> tournament_detail_attack::Model {
+        tournament_detail_attack::Model {
+            tournament_id: 1,
+            tournament_service_id: 0,
+            fighter_id,
+            round,
+            special_attack: false,
+            speical_defend: false,
+            damage: 100,
+            order,
+        }
+    }
+
+    #[test]
+    fn last_attacker_wins_each_battle() {
+        let attacks = [
+            attack(1, 0, 1),
+            attack(1, 1, 2),
+            attack(1, 2, 1),
+            attack(1, 3, 3),
+            attack(1, 4, 4),
+            attack(2, 0, 4),
+            attack(2, 1, 1),
+        ];
+
+        let games = games(&attacks);
+        assert_eq!(
+            games,
+            [
+                Game {
+                    winner: 1,
+                    loser: 2
+                },
+                Game {
+                    winner: 4,
+                    loser: 3
+                },
+                Game {
+                    winner: 1,
+                    loser: 4
+                },
+            ]
+        );
+
+        let placements = placements(&games);
+        assert_eq!(placements[&1], (2, 2, 1));
+        assert_eq!(placements[&4], (2, 1, 2));
+        assert_eq!(placements[&2], (1, 0, 3));
+    }
+
+    #[test]
+    fn single_attack_battles_stand_alone() {
+        // 1 kills its opponent outright, then 3 and 4 fight it out
+        let attacks = [
+            attack(1, 0, 1),
+            attack(1, 2, 3),
+            attack(1, 3, 4),
+            attack(1, 4, 3),
+        ];
+
+        let battles = battles(&attacks);
+        assert_eq!(battles.len(), 2);
+        assert_eq!(battles[0].len(), 1);
+
+        // The loser of a single attack battle never attacked, so it is not a game
+        assert_eq!(
+            games(&attacks),
+            [Game {
+                winner: 3,
+                loser: 4
+            }]
+        );
+    }
+}
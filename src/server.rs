@@ -0,0 +1,196 @@
+use anyhow::Result;
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use entity::entities::{fighter, meta_failed_tournament_request, meta_last_page};
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryOrder, Statement,
+};
+use serde::Serialize;
+use std::{collections::BTreeMap, net::SocketAddr};
+use tracing::{info, warn};
+
+use crate::{
+    metrics::{self, LastRun},
+    task::fighter::{failure_report, FailureReport},
+};
+
+/// Serve operational endpoints on `addr` until the process exits.
+///
+/// - `/metrics`: Prometheus metrics.
+/// - `/healthz`: 200 if the database can be reached, 503 otherwise.
+/// - `/status`: JSON summary of scraping progress.
+pub async fn serve(addr: SocketAddr, conn: DatabaseConnection) -> Result<()> {
+    let app = router(conn);
+
+    info!(addr = ?addr, "serving http");
+    axum::Server::bind(&addr)
+        .serve(app.into_make_service())
+        .await?;
+
+    Ok(())
+}
+
+fn router(conn: DatabaseConnection) -> Router {
+    Router::new()
+        .route("/metrics", get(get_metrics))
+        .route("/healthz", get(get_healthz))
+        .route("/status", get(get_status))
+        .with_state(conn)
+}
+
+async fn get_metrics() -> Result<String, StatusCode> {
+    metrics::gather().map_err(|e| {
+        warn!(e = ?e, "could not encode metrics");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+async fn get_healthz(State(conn): State<DatabaseConnection>) -> StatusCode {
+    match conn
+        .execute(Statement::from_string(
+            conn.get_database_backend(),
+            "SELECT 1".to_owned(),
+        ))
+        .await
+    {
+        Ok(_) => StatusCode::OK,
+        Err(e) => {
+            warn!(e = ?e, "database unreachable");
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Status {
+    /// Latest run of each task, keyed by `champion`, `tournament` and `refresh`.
+    tasks: BTreeMap<String, LastRun>,
+    last_page: Option<LastPage>,
+    failed_pages: u64,
+    failed_champions: FailureReport,
+    highest_fighter_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+struct LastPage {
+    page_size: i64,
+    page_index: i64,
+}
+
+async fn get_status(State(conn): State<DatabaseConnection>) -> Result<Json<Status>, StatusCode> {
+    status(&conn).await.map(Json).map_err(|e| {
+        warn!(e = ?e, "could not get status");
+        StatusCode::SERVICE_UNAVAILABLE
+    })
+}
+
+async fn status(conn: &DatabaseConnection) -> Result<Status> {
+    let last_page = meta_last_page::Entity::find()
+        .one(conn)
+        .await?
+        .map(|m| LastPage {
+            page_size: m.page_size,
+            page_index: m.page_index,
+        });
+
+    let failed_pages = meta_failed_tournament_request::Entity::find()
+        .count(conn)
+        .await?;
+
+    let failed_champions = failure_report(conn).await?;
+
+    let highest_fighter_id = fighter::Entity::find()
+        .order_by_desc(fighter::Column::Id)
+        .one(conn)
+        .await?
+        .map(|m| m.id);
+
+    Ok(Status {
+        tasks: metrics::LAST_RUNS.read().unwrap().clone(),
+        last_page,
+        failed_pages,
+        failed_champions,
+        highest_fighter_id,
+    })
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use crate::{metrics::observe_scan, testing};
+    use axum::{body::Body, http::Request};
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    async fn get(conn: DatabaseConnection, uri: &str) -> (StatusCode, String) {
+        let resp = router(conn)
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = resp.status();
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn metrics_render_registered_families() {
+        metrics::upserted("server_test", 1);
+
+        let (status, body) = get(testing::database().await, "/metrics").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.contains("# TYPE trv_rows_upserted_total counter"));
+        assert!(body.contains(r#"trv_rows_upserted_total{table="server_test"} 1"#));
+    }
+
+    #[tokio::test]
+    async fn healthz_checks_the_database() {
+        let (status, _) = get(testing::database().await, "/healthz").await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn status_serialises_last_runs() {
+        observe_scan("server_test", async { Ok(()) }).await.unwrap();
+
+        let (status, body) = get(testing::database().await, "/status").await;
+        assert_eq!(status, StatusCode::OK);
+
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["tasks"]["server_test"]["succeeded"], true);
+        assert!(body["tasks"]["server_test"]["finished"].is_string());
+        assert_eq!(body["last_page"], Value::Null);
+        assert_eq!(body["failed_pages"], 0);
+        assert_eq!(body["failed_champions"]["total"], 0);
+        assert_eq!(body["highest_fighter_id"], Value::Null);
+    }
+
+    #[tokio::test]
+    async fn status_reports_failed_champions() {
+        use entity::entities::meta_failed_fighter_request;
+        use sea_orm::{ActiveModelTrait, Set};
+
+        let conn = testing::database().await;
+        for (id, kind, attempts) in [(1, "not_found", 8), (2, "server", 1)] {
+            meta_failed_fighter_request::ActiveModel {
+                fighter_id: Set(id),
+                error_kind: Set(kind.to_owned()),
+                attempts: Set(attempts),
+                last_error: Set(String::new()),
+                last_attempt: Set(Default::default()),
+            }
+            .insert(&conn)
+            .await
+            .unwrap();
+        }
+
+        let (status, body) = get(conn, "/status").await;
+        assert_eq!(status, StatusCode::OK);
+
+        let body: Value = serde_json::from_str(&body).unwrap();
+        let failed = &body["failed_champions"];
+        assert_eq!(failed["total"], 2);
+        assert_eq!(failed["by_kind"]["not_found"], 1);
+        assert_eq!(failed["max_attempts"], 8);
+        assert_eq!(failed["given_up"], 1);
+    }
+}
@@ -0,0 +1,748 @@
+//! Monte-Carlo battle simulator, fitted from the recorded attacks of past tournaments.
+//!
+//! Each battle is simulated attack by attack, alternating between two champions from a random
+//! first attacker until one has taken as much damage as its health:
+//!
+//! - an attack is a special attack, and misses, at rates fitted by the attacker's stance (and the
+//!   defender's stance, for misses)
+//! - a hit is met with a special defend at a rate fitted by the defender's stance
+//! - a hit's damage is drawn from a normal distribution around a linear fit on the attacker's
+//!   attack and strength, the defender's defence, and both special flags
+//! - health is drawn from a linear fit on strength of the damage losers took in their battles
+//!
+//! Wisdom ranges enter the fits as their midpoints.
+
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDateTime;
+use entity::entities::{fighter, tournament_detail_champion};
+use itertools::Itertools;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sea_orm::{
+    ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, EntityTrait, FromQueryResult,
+    QueryFilter, Statement,
+};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    f64::consts::PI,
+    str::FromStr,
+};
+use tracing::{info, instrument};
+
+use crate::rating;
+
+pub mod validation;
+
+/// Tournaments loaded per query.
+const TOURNAMENT_CHUNK: usize = 100;
+
+/// Stance-specific rates fall back to the overall rate below this many samples.
+const MIN_SAMPLES: u64 = 30;
+
+/// Attacks after which a battle goes to the champion with more health left.
+const MAX_ATTACKS: usize = 200;
+
+/// Ridge penalty on the non-intercept coefficients, so that fits stay solvable when a stat does
+/// not vary, e.g. with few fighters.
+const RIDGE: f64 = 0.001;
+
+/// Wisdom of a champion, as the midpoints of its ranges.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Stats {
+    pub strength: f64,
+    pub attack: f64,
+    pub defence: f64,
+    pub omega: f64,
+}
+
+impl From<&fighter::Model> for Stats {
+    fn from(m: &fighter::Model) -> Self {
+        let mid = |from: i32, to: i32| f64::from(from + to) / 2.0;
+
+        Self {
+            strength: mid(m.strength_from, m.strength_to),
+            attack: mid(m.attack_from, m.attack_to),
+            defence: mid(m.defence_from, m.defence_to),
+            omega: mid(m.omega_from, m.omega_to),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Combatant {
+    pub fighter_id: i64,
+    pub stance: i32,
+    pub stats: Stats,
+}
+
+/// A fighter in a lineup, `ID` or `ID:STANCE`. Without a stance, the one it last entered a
+/// tournament with is used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Entrant {
+    pub fighter_id: i64,
+    pub stance: Option<i32>,
+}
+
+impl FromStr for Entrant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (id, stance) = match s.split_once(':') {
+            Some((id, stance)) => (id, Some(stance)),
+            None => (s, None),
+        };
+
+        Ok(Self {
+            fighter_id: id
+                .parse()
+                .map_err(|_| format!("invalid fighter ID {id:?}"))?,
+            stance: stance
+                .map(|stance| stance.parse())
+                .transpose()
+                .map_err(|_| format!("invalid stance in {s:?}"))?,
+        })
+    }
+}
+
+/// Events out of a number of tries.
+#[derive(Clone, Copy, Debug, Default)]
+struct Rate {
+    events: u64,
+    total: u64,
+}
+
+impl Rate {
+    fn add(&mut self, event: bool) {
+        self.events += u64::from(event);
+        self.total += 1;
+    }
+
+    fn get(&self) -> Option<f64> {
+        (self.total >= MIN_SAMPLES).then(|| self.events as f64 / self.total as f64)
+    }
+}
+
+/// Rates by key, falling back to the rate over all keys.
+#[derive(Clone, Debug, Default)]
+struct Rates<K> {
+    by: HashMap<K, Rate>,
+    all: Rate,
+}
+
+impl<K: std::hash::Hash + Eq> Rates<K> {
+    fn add(&mut self, key: K, event: bool) {
+        self.by.entry(key).or_default().add(event);
+        self.all.add(event);
+    }
+
+    fn get(&self, key: &K) -> f64 {
+        self.by
+            .get(key)
+            .and_then(Rate::get)
+            .or_else(|| {
+                (self.all.total > 0).then(|| self.all.events as f64 / self.all.total as f64)
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Ridge regression accumulated from its normal equations.
+#[derive(Clone, Copy, Debug)]
+struct LeastSquares<const N: usize> {
+    xtx: [[f64; N]; N],
+    xty: [f64; N],
+    yty: f64,
+    n: u64,
+}
+
+impl<const N: usize> Default for LeastSquares<N> {
+    fn default() -> Self {
+        Self {
+            xtx: [[0.0; N]; N],
+            xty: [0.0; N],
+            yty: 0.0,
+            n: 0,
+        }
+    }
+}
+
+impl<const N: usize> LeastSquares<N> {
+    fn add(&mut self, x: [f64; N], y: f64) {
+        for i in 0..N {
+            for j in 0..N {
+                self.xtx[i][j] += x[i] * x[j];
+            }
+            self.xty[i] += x[i] * y;
+        }
+        self.yty += y * y;
+        self.n += 1;
+    }
+
+    /// Coefficients and residual standard deviation, by Gaussian elimination.
+    fn solve(&self) -> Option<([f64; N], f64)> {
+        if self.n <= N as u64 {
+            return None;
+        }
+
+        let mut a = self.xtx;
+        let mut b = self.xty;
+        for (i, row) in a.iter_mut().enumerate().skip(1) {
+            row[i] += RIDGE * self.n as f64;
+        }
+
+        for col in 0..N {
+            let pivot = (col..N).max_by(|x, y| a[*x][col].abs().total_cmp(&a[*y][col].abs()))?;
+            if a[pivot][col].abs() < f64::EPSILON {
+                return None;
+            }
+            a.swap(col, pivot);
+            b.swap(col, pivot);
+
+            let (top, bottom) = a.split_at_mut(col + 1);
+            let pivot = &top[col];
+            for (offset, row) in bottom.iter_mut().enumerate() {
+                let factor = row[col] / pivot[col];
+                for (x, p) in row.iter_mut().zip(pivot).skip(col) {
+                    *x -= factor * p;
+                }
+                b[col + 1 + offset] -= factor * b[col];
+            }
+        }
+
+        let mut beta = [0.0; N];
+        for row in (0..N).rev() {
+            let rest = (row + 1..N).map(|k| a[row][k] * beta[k]).sum::<f64>();
+            beta[row] = (b[row] - rest) / a[row][row];
+        }
+
+        // Residual sum of squares from the accumulated sums
+        let fitted = (0..N)
+            .map(|i| {
+                (0..N)
+                    .map(|j| beta[i] * self.xtx[i][j] * beta[j])
+                    .sum::<f64>()
+            })
+            .sum::<f64>();
+        let rss = self.yty - 2.0 * dot(&beta, &self.xty) + fitted;
+        let sd = (rss.max(0.0) / (self.n - N as u64) as f64).sqrt();
+
+        Some((beta, sd))
+    }
+}
+
+fn dot<const N: usize>(a: &[f64; N], b: &[f64; N]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// A standard normal sample, by the Box-Muller transform.
+fn normal(rng: &mut impl Rng) -> f64 {
+    let u: f64 = rng.gen::<f64>().max(f64::MIN_POSITIVE);
+    let v: f64 = rng.gen();
+    (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()
+}
+
+fn damage_features(
+    attacker: &Combatant,
+    defender: &Combatant,
+    special_attack: bool,
+    special_defend: bool,
+) -> [f64; 6] {
+    [
+        1.0,
+        attacker.stats.attack,
+        attacker.stats.strength,
+        defender.stats.defence,
+        f64::from(u8::from(special_attack)),
+        f64::from(u8::from(special_defend)),
+    ]
+}
+
+fn health_features(combatant: &Combatant) -> [f64; 2] {
+    [1.0, combatant.stats.strength]
+}
+
+/// One attack of a recorded battle.
+#[derive(Clone, Copy, Debug)]
+pub struct RecordedAttack {
+    /// Whether the first champion of the battle attacked.
+    pub by_first: bool,
+    pub damage: i32,
+    pub special_attack: bool,
+    pub special_defend: bool,
+}
+
+/// A recorded battle between two champions whose stance and wisdom are known.
+#[derive(Clone, Debug)]
+pub struct RecordedBattle {
+    pub first: Combatant,
+    pub second: Combatant,
+    pub attacks: Vec<RecordedAttack>,
+    /// Whether the first champion landed the last attack.
+    pub first_won: bool,
+}
+
+#[derive(Clone, Copy, Debug, FromQueryResult)]
+pub struct ScheduledTournament {
+    pub id: i64,
+    pub service_id: i32,
+    pub start_time: NaiveDateTime,
+}
+
+/// Tournaments with recorded battles, oldest first.
+pub async fn tournaments(conn: &DatabaseConnection) -> Result<Vec<ScheduledTournament>> {
+    Ok(
+        ScheduledTournament::find_by_statement(Statement::from_string(
+            conn.get_database_backend(),
+            r#"SELECT id, service_id, start_time FROM tournament
+            WHERE EXISTS (
+                SELECT 1 FROM tournament_detail_attack
+                WHERE tournament_id = tournament.id
+                    AND tournament_service_id = tournament.service_id
+            )
+            ORDER BY start_time, service_id, id"#
+                .to_owned(),
+        ))
+        .all(conn)
+        .await?,
+    )
+}
+
+/// The one-on-one battles of `tournaments` between champions with known stances and wisdom.
+pub async fn recorded(
+    conn: &DatabaseConnection,
+    tournaments: &[ScheduledTournament],
+) -> Result<Vec<RecordedBattle>> {
+    let mut stats: HashMap<i64, Stats> = HashMap::new();
+    let mut battles = vec![];
+
+    for chunk in tournaments.chunks(TOURNAMENT_CHUNK) {
+        let attacks = rating::attacks(conn, chunk.iter().map(|t| (t.id, t.service_id))).await?;
+
+        let condition = chunk.iter().fold(Condition::any(), |condition, t| {
+            condition.add(
+                Condition::all()
+                    .add(tournament_detail_champion::Column::TournamentId.eq(t.id))
+                    .add(tournament_detail_champion::Column::TournamentServiceId.eq(t.service_id)),
+            )
+        });
+        let stances = tournament_detail_champion::Entity::find()
+            .filter(condition)
+            .all(conn)
+            .await?
+            .into_iter()
+            .map(|m| {
+                (
+                    (
+                        m.tournament_id,
+                        m.tournament_service_id as i32,
+                        m.fighter_id,
+                    ),
+                    m.stance,
+                )
+            })
+            .collect::<HashMap<_, _>>();
+
+        let missing = attacks
+            .values()
+            .flatten()
+            .map(|a| a.fighter_id)
+            .filter(|id| !stats.contains_key(id))
+            .unique()
+            .collect::<Vec<_>>();
+        for ids in missing.chunks(TOURNAMENT_CHUNK) {
+            let fighters = fighter::Entity::find()
+                .filter(fighter::Column::Id.is_in(ids.iter().copied()))
+                .all(conn)
+                .await?;
+            stats.extend(fighters.iter().map(|f| (f.id, Stats::from(f))));
+        }
+
+        for ((id, service_id), attacks) in &attacks {
+            let combatant = |fighter_id: i64| {
+                Some(Combatant {
+                    fighter_id,
+                    stance: *stances.get(&(*id, *service_id, fighter_id))?,
+                    stats: *stats.get(&fighter_id)?,
+                })
+            };
+
+            for battle in rating::battles(attacks) {
+                let Some((first, second)) =
+                    battle.iter().map(|a| a.fighter_id).unique().collect_tuple()
+                else {
+                    continue;
+                };
+                let (Some(first), Some(second)) = (combatant(first), combatant(second)) else {
+                    continue;
+                };
+
+                battles.push(RecordedBattle {
+                    first,
+                    second,
+                    attacks: battle
+                        .iter()
+                        .map(|a| RecordedAttack {
+                            by_first: a.fighter_id == first.fighter_id,
+                            damage: a.damage,
+                            special_attack: a.special_attack,
+                            special_defend: a.speical_defend,
+                        })
+                        .collect(),
+                    first_won: battle.last().map(|a| a.fighter_id) == Some(first.fighter_id),
+                });
+            }
+        }
+    }
+
+    Ok(battles)
+}
+
+/// The fitted distributions battles are simulated from.
+#[derive(Clone, Debug)]
+pub struct Model {
+    miss: Rates<(i32, i32)>,
+    special_attack: Rates<i32>,
+    special_defend: Rates<i32>,
+    damage: [f64; 6],
+    damage_sd: f64,
+    health: [f64; 2],
+    health_sd: f64,
+}
+
+/// Coefficients of a fitted model, for reports.
+#[derive(Clone, Debug, Serialize)]
+pub struct Coefficients {
+    pub miss_rate: f64,
+    pub special_attack_rate: f64,
+    pub special_defend_rate: f64,
+    /// Intercept, attack, strength, defender's defence, special attack, special defend.
+    pub damage: [f64; 6],
+    pub damage_sd: f64,
+    /// Intercept, strength.
+    pub health: [f64; 2],
+    pub health_sd: f64,
+}
+
+impl Model {
+    /// Fit the model on recorded battles.
+    pub fn fit(battles: &[RecordedBattle]) -> Result<Self> {
+        let mut miss = Rates::default();
+        let mut special_attack = Rates::default();
+        let mut special_defend = Rates::default();
+        let mut damage = LeastSquares::default();
+        let mut health = LeastSquares::default();
+
+        for battle in battles {
+            let mut taken = [0.0, 0.0];
+
+            for attack in &battle.attacks {
+                let (attacker, defender) = if attack.by_first {
+                    (&battle.first, &battle.second)
+                } else {
+                    (&battle.second, &battle.first)
+                };
+
+                special_attack.add(attacker.stance, attack.special_attack);
+                miss.add((attacker.stance, defender.stance), attack.damage == 0);
+                if attack.damage > 0 {
+                    special_defend.add(defender.stance, attack.special_defend);
+                    damage.add(
+                        damage_features(
+                            attacker,
+                            defender,
+                            attack.special_attack,
+                            attack.special_defend,
+                        ),
+                        f64::from(attack.damage),
+                    );
+                }
+
+                taken[usize::from(attack.by_first)] += f64::from(attack.damage);
+            }
+
+            // The loser took at least its health in damage, the last hit possibly more
+            let (loser, taken) = if battle.first_won {
+                (&battle.second, taken[1])
+            } else {
+                (&battle.first, taken[0])
+            };
+            if taken > 0.0 {
+                health.add(health_features(loser), taken);
+            }
+        }
+
+        let (damage, damage_sd) = damage.solve().context("not enough hits to fit damage")?;
+        let (health, health_sd) = health.solve().context("not enough battles to fit health")?;
+
+        Ok(Self {
+            miss,
+            special_attack,
+            special_defend,
+            damage,
+            damage_sd,
+            health,
+            health_sd,
+        })
+    }
+
+    pub fn coefficients(&self) -> Coefficients {
+        let overall = |rate: &Rate| rate.events as f64 / rate.total.max(1) as f64;
+
+        Coefficients {
+            miss_rate: overall(&self.miss.all),
+            special_attack_rate: overall(&self.special_attack.all),
+            special_defend_rate: overall(&self.special_defend.all),
+            damage: self.damage,
+            damage_sd: self.damage_sd,
+            health: self.health,
+            health_sd: self.health_sd,
+        }
+    }
+
+    fn attack(&self, rng: &mut impl Rng, attacker: &Combatant, defender: &Combatant) -> f64 {
+        let special_attack = rng.gen_bool(self.special_attack.get(&attacker.stance));
+        if rng.gen_bool(self.miss.get(&(attacker.stance, defender.stance))) {
+            return 0.0;
+        }
+        let special_defend = rng.gen_bool(self.special_defend.get(&defender.stance));
+
+        let mean = dot(
+            &self.damage,
+            &damage_features(attacker, defender, special_attack, special_defend),
+        );
+        (mean + self.damage_sd * normal(rng)).max(1.0)
+    }
+
+    fn health(&self, rng: &mut impl Rng, combatant: &Combatant) -> f64 {
+        (dot(&self.health, &health_features(combatant)) + self.health_sd * normal(rng)).max(1.0)
+    }
+
+    /// Simulate one battle, returning whether `a` won.
+    pub fn battle(&self, rng: &mut impl Rng, a: &Combatant, b: &Combatant) -> bool {
+        let health = [self.health(rng, a), self.health(rng, b)];
+        let mut left = health;
+        let mut a_attacks = rng.gen_bool(0.5);
+
+        for _ in 0..MAX_ATTACKS {
+            if a_attacks {
+                left[1] -= self.attack(rng, a, b);
+                if left[1] <= 0.0 {
+                    return true;
+                }
+            } else {
+                left[0] -= self.attack(rng, b, a);
+                if left[0] <= 0.0 {
+                    return false;
+                }
+            }
+            a_attacks = !a_attacks;
+        }
+
+        left[0] / health[0] >= left[1] / health[1]
+    }
+
+    /// Share of `runs` simulated battles won by `a`.
+    pub fn win_probability(
+        &self,
+        rng: &mut impl Rng,
+        a: &Combatant,
+        b: &Combatant,
+        runs: u32,
+    ) -> f64 {
+        let wins = (0..runs).filter(|_| self.battle(rng, a, b)).count();
+        wins as f64 / f64::from(runs.max(1))
+    }
+}
+
+/// A fighter's chances in a simulated lineup.
+#[derive(Debug, Serialize)]
+pub struct LineupFighter {
+    #[serde(flatten)]
+    pub combatant: Combatant,
+    /// Average chance of winning a battle against the rest of the lineup.
+    pub win_share: f64,
+    /// Chance of winning a battle against each opponent.
+    pub against: BTreeMap<i64, f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Lineup {
+    pub runs: u32,
+    pub battles: usize,
+    pub model: Coefficients,
+    /// Best chances first.
+    pub fighters: Vec<LineupFighter>,
+}
+
+async fn combatant(conn: &DatabaseConnection, entrant: Entrant) -> Result<Combatant> {
+    let fighter = fighter::Entity::find_by_id(entrant.fighter_id)
+        .one(conn)
+        .await?
+        .with_context(|| format!("fighter {} not found", entrant.fighter_id))?;
+
+    let stance = match entrant.stance {
+        Some(stance) => stance,
+        None => {
+            #[derive(FromQueryResult)]
+            struct LastStance {
+                stance: i32,
+            }
+
+            LastStance::find_by_statement(Statement::from_sql_and_values(
+                conn.get_database_backend(),
+                r#"SELECT stance FROM tournament_detail_champion
+                JOIN tournament ON tournament.id = tournament_detail_champion.tournament_id
+                    AND tournament.service_id = tournament_detail_champion.tournament_service_id
+                WHERE fighter_id = $1
+                ORDER BY tournament.start_time DESC
+                LIMIT 1"#,
+                [entrant.fighter_id.into()],
+            ))
+            .one(conn)
+            .await?
+            .with_context(|| {
+                format!(
+                    "no stance known for fighter {0}, give one as {0}:STANCE",
+                    entrant.fighter_id
+                )
+            })?
+            .stance
+        }
+    };
+
+    Ok(Combatant {
+        fighter_id: fighter.id,
+        stance,
+        stats: Stats::from(&fighter),
+    })
+}
+
+/// Fit the model on every recorded battle and simulate each pair of `entrants` `runs` times.
+#[instrument(skip_all)]
+pub async fn simulate(
+    conn: &DatabaseConnection,
+    entrants: &[Entrant],
+    runs: u32,
+    seed: Option<u64>,
+) -> Result<Lineup> {
+    if entrants.len() < 2 {
+        bail!("a lineup needs at least two fighters");
+    }
+
+    let mut combatants = vec![];
+    for entrant in entrants {
+        combatants.push(combatant(conn, *entrant).await?);
+    }
+
+    let battles = recorded(conn, &tournaments(conn).await?).await?;
+    let model = Model::fit(&battles)?;
+    info!(battles = battles.len(), "fitted simulator");
+
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    let mut fighters = combatants
+        .iter()
+        .map(|a| {
+            let against = combatants
+                .iter()
+                .filter(|b| b.fighter_id != a.fighter_id)
+                .map(|b| (b.fighter_id, model.win_probability(&mut rng, a, b, runs)))
+                .collect::<BTreeMap<_, _>>();
+
+            LineupFighter {
+                combatant: *a,
+                win_share: against.values().sum::<f64>() / against.len().max(1) as f64,
+                against,
+            }
+        })
+        .collect::<Vec<_>>();
+    fighters.sort_by(|a, b| b.win_share.total_cmp(&a.win_share));
+
+    Ok(Lineup {
+        runs,
+        battles: battles.len(),
+        model: model.coefficients(),
+        fighters,
+    })
+}
+
+/// Print the simulated chances of `entrants` to stdout as JSON.
+pub async fn print_lineup(
+    conn: &DatabaseConnection,
+    entrants: &[Entrant],
+    runs: u32,
+    seed: Option<u64>,
+) -> Result<()> {
+    let lineup = simulate(conn, entrants, runs, seed).await?;
+    println!("{}", serde_json::to_string_pretty(&lineup)?);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entrants_parse_with_optional_stance() {
+        assert_eq!(
+            "8399".parse(),
+            Ok(Entrant {
+                fighter_id: 8399,
+                stance: None
+            })
+        );
+        assert_eq!(
+            "8399:3".parse(),
+            Ok(Entrant {
+                fighter_id: 8399,
+                stance: Some(3)
+            })
+        );
+        assert!("8399:".parse::<Entrant>().is_err());
+    }
+
+    #[test]
+    fn least_squares_recovers_a_line() {
+        let mut fit = LeastSquares::<2>::default();
+        for x in 0..100 {
+            let x = f64::from(x);
+            fit.add([1.0, x], 10.0 + 2.0 * x);
+        }
+
+        let ([intercept, slope], sd) = fit.solve().unwrap();
+        assert!((intercept - 10.0).abs() < 0.1, "{intercept}");
+        assert!((slope - 2.0).abs() < 0.01, "{slope}");
+        assert!(sd < 0.5, "{sd}");
+    }
+
+    #[test]
+    fn stronger_attackers_win_more() {
+        let model = Model {
+            miss: Rates::default(),
+            special_attack: Rates::default(),
+            special_defend: Rates::default(),
+            damage: [0.0, 1.0, 0.0, 0.0, 0.0, 0.0],
+            damage_sd: 5.0,
+            health: [500.0, 0.0],
+            health_sd: 10.0,
+        };
+        let combatant = |fighter_id, attack| Combatant {
+            fighter_id,
+            stance: 0,
+            stats: Stats {
+                strength: 0.0,
+                attack,
+                defence: 0.0,
+                omega: 0.0,
+            },
+        };
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let p = model.win_probability(&mut rng, &combatant(1, 60.0), &combatant(2, 40.0), 1000);
+        assert!(p > 0.9, "{p}");
+    }
+}
@@ -0,0 +1,131 @@
+use anyhow::{bail, Result};
+use rand::{rngs::StdRng, SeedableRng};
+use sea_orm::DatabaseConnection;
+use serde::Serialize;
+use tracing::instrument;
+
+use super::{recorded, tournaments, Coefficients, Model};
+
+/// Buckets predictions are grouped into for calibration.
+const BUCKETS: usize = 10;
+
+/// Predictions within a range of probabilities, against how often they came true.
+#[derive(Debug, Default, Serialize)]
+pub struct Bucket {
+    pub from: f64,
+    pub to: f64,
+    pub battles: u64,
+    /// Mean predicted chance of the first champion winning.
+    pub predicted: f64,
+    /// Share of the battles the first champion won.
+    pub actual: f64,
+}
+
+/// How well simulated chances matched recorded battles the model was not fitted on.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub fitted_tournaments: usize,
+    pub fitted_battles: usize,
+    pub validated_tournaments: usize,
+    pub validated_battles: usize,
+    pub runs: u32,
+    pub model: Coefficients,
+    /// Battles won by the champion given the better chance.
+    pub accuracy: f64,
+    /// Mean squared error of the predicted chances, 0.25 for always predicting a coin flip.
+    pub brier_score: f64,
+    pub log_loss: f64,
+    pub calibration: Vec<Bucket>,
+}
+
+/// Fit the model on all but the latest `holdout` share of tournaments, and predict the battles
+/// of those. A `holdout` of 0 fits and validates on every tournament.
+#[instrument(skip_all)]
+pub async fn report(
+    conn: &DatabaseConnection,
+    holdout: f64,
+    runs: u32,
+    seed: Option<u64>,
+) -> Result<Report> {
+    if !(0.0..1.0).contains(&holdout) {
+        bail!("holdout must be at least 0 and below 1");
+    }
+
+    let tournaments = tournaments(conn).await?;
+    let split = tournaments.len() - (tournaments.len() as f64 * holdout).ceil() as usize;
+    let (fitted, validated) = if split == tournaments.len() {
+        (&tournaments[..], &tournaments[..])
+    } else {
+        tournaments.split_at(split)
+    };
+
+    let fitted_battles = recorded(conn, fitted).await?;
+    let model = Model::fit(&fitted_battles)?;
+    let battles = recorded(conn, validated).await?;
+
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    let mut correct = 0;
+    let mut brier = 0.0;
+    let mut log_loss = 0.0;
+    let mut buckets = (0..BUCKETS)
+        .map(|i| Bucket {
+            from: i as f64 / BUCKETS as f64,
+            to: (i + 1) as f64 / BUCKETS as f64,
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+
+    for battle in &battles {
+        let p = model.win_probability(&mut rng, &battle.first, &battle.second, runs);
+        let actual = f64::from(u8::from(battle.first_won));
+
+        correct += u64::from((p > 0.5) == battle.first_won && p != 0.5);
+        brier += (p - actual).powi(2);
+        let clamped = p.clamp(1e-6, 1.0 - 1e-6);
+        log_loss -= actual * clamped.ln() + (1.0 - actual) * (1.0 - clamped).ln();
+
+        let bucket = &mut buckets[((p * BUCKETS as f64) as usize).min(BUCKETS - 1)];
+        bucket.battles += 1;
+        bucket.predicted += p;
+        bucket.actual += actual;
+    }
+
+    for bucket in &mut buckets {
+        if bucket.battles > 0 {
+            bucket.predicted /= bucket.battles as f64;
+            bucket.actual /= bucket.battles as f64;
+        }
+    }
+
+    let n = battles.len().max(1) as f64;
+
+    Ok(Report {
+        fitted_tournaments: fitted.len(),
+        fitted_battles: fitted_battles.len(),
+        validated_tournaments: validated.len(),
+        validated_battles: battles.len(),
+        runs,
+        model: model.coefficients(),
+        accuracy: correct as f64 / n,
+        brier_score: brier / n,
+        log_loss: log_loss / n,
+        calibration: buckets.into_iter().filter(|b| b.battles > 0).collect(),
+    })
+}
+
+/// Print the validation report to stdout as JSON.
+pub async fn print_report(
+    conn: &DatabaseConnection,
+    holdout: f64,
+    runs: u32,
+    seed: Option<u64>,
+) -> Result<()> {
+    let report = report(conn, holdout, runs, seed).await?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    Ok(())
+}
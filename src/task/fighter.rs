@@ -1,51 +1,115 @@
 use anyhow::Result;
-use api::fighter::FighterResponse;
-use backoff::{Error, ExponentialBackoff};
+use api::fighter::{display_type_name, FighterResponse};
 use chrono::{DateTime, Utc};
-use entity::entities::{fighter, fighter_parent, fighter_trait, prelude::*};
+use entity::entities::{
+    fighter, fighter_parent, fighter_trait, meta_failed_fighter_request, prelude::*,
+};
 use erc_nft_metadata::AttributeEntry;
 use ethers_core::types::Address;
 use futures::{stream, StreamExt};
 use itertools::Itertools;
-use sea_orm::sea_query::OnConflict;
+use sea_orm::sea_query::{Expr, OnConflict};
 use sea_orm::{prelude::*, QueryOrder};
 use sea_orm::{ActiveValue::*, TransactionTrait};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
-use tracing::{debug, info, instrument, warn};
+use tracing::{debug, info, info_span, instrument, warn, Instrument};
 
-use crate::CONCURRENT_REQUESTS;
+use super::excluded;
+use crate::{
+    archive::Archive,
+    client::{Client, ALCHEMY_HOST, FEDERATION_HOST},
+    dead_letter::{self, PayloadKind},
+    error::ScrapeError,
+    metrics, CONCURRENT_REQUESTS,
+};
 
 const SUMMONED_CHAMPIONS_CONTRACT: &str = "0x57f698d99d964aef66d974739b98ec694724b1b8";
 
+/// Times a champion may not be found before it is no longer asked for, e.g. a burned token.
+const MAX_NOT_FOUND_ATTEMPTS: i32 = 8;
+
 #[derive(Debug)]
 pub struct ChampionTask {
-    client: reqwest::Client,
+    client: Client,
     conn: DatabaseConnection,
     alchemy_api_key: String,
+    archive: Option<Archive>,
 }
 
 impl ChampionTask {
-    pub fn new(client: reqwest::Client, conn: DatabaseConnection, alchemy_api_key: String) -> Self {
+    pub fn new(
+        client: Client,
+        conn: DatabaseConnection,
+        alchemy_api_key: String,
+        archive: Option<Archive>,
+    ) -> Self {
         Self {
             client,
             conn,
             alchemy_api_key,
+            archive,
         }
     }
 
     #[instrument(skip_all)]
     pub async fn scan(&self) -> Result<()> {
         info!("beginning champion scan");
+
+        // Retry champions that failed during previous scans first
+        let (failed, given_up) = self.get_failed_ids().await?;
+        debug!(count = ?failed.len(), given_up = ?given_up.len(), "retrying failed champions");
+        let (mut fetched, mut failures) = self
+            .scrape_champions(failed.iter().copied().collect())
+            .await;
+
         let count = self.get_count().await?;
         debug!(count = ?count, "highest token id");
 
+        let skipped = failed.union(&given_up).copied().collect();
+        let (rest, rest_failures) = self.scrape_champions(unretried(count, &skipped)).await;
+        fetched.extend(rest);
+        failures.extend(rest_failures);
+
+        let recovered = recovered(failed, &failures.iter().map(|(id, _)| *id).collect());
+
+        self.insert_champions(fetched).await?;
+
+        for (id, e) in failures {
+            if let Err(e) = self.insert_failed_champion(id, &e).await {
+                warn!(id = ?id, e = ?e, "could not register failed champion. this champion will not be retried until the next scan!");
+            }
+        }
+
+        if let Err(e) = self.delete_failed_champions(recovered).await {
+            warn!(e = ?e, "could not clear recovered champions");
+        }
+
+        match failure_report(&self.conn).await {
+            Ok(report) => {
+                info!(total = report.total, by_kind = ?report.by_kind, max_attempts = report.max_attempts, given_up = report.given_up, "failed champions")
+            }
+            Err(e) => warn!(e = ?e, "could not build failed champion report"),
+        }
+
+        info!("champion scan complete");
+        Ok(())
+    }
+
+    /// Insert fetched champions, their traits and their parents into the database.
+    pub(crate) async fn insert_champions(
+        &self,
+        fetched: Vec<(FighterResponse, DateTime<Utc>)>,
+    ) -> Result<()> {
         let mut champions = vec![];
         let mut traits = vec![];
         let mut parents = vec![];
 
-        for (fighter, dt) in self.scrape_champions(count).await.into_iter() {
+        for (fighter, dt) in fetched.into_iter() {
+            let typed = fighter.attributes.traits();
+
             if let Some(lineage_node) = fighter.lineage_node {
                 parents.push(fighter_parent::ActiveModel {
                     fighter_id: Set(fighter.attributes.id as i64),
@@ -71,6 +135,14 @@ impl ChampionTask {
                 omega_to: Set(fighter.statistic.wisdom.omega.to as i32),
                 meta_last_updated: Set(dt.naive_utc()),
                 mum: Set(fighter.lineage_node.map(|l| l.original_mum as i64)),
+                elo: Set(fighter.statistic.elo.map(|elo| elo as i32)),
+                bloodline: Set(typed.bloodline.map(|t| t.as_str().to_owned())),
+                genotype: Set(typed.genotype.map(|t| t.0 as i32)),
+                character_class: Set(typed.character_class.map(|t| t.as_str().to_owned())),
+                breed: Set(typed.breed.map(|t| t.as_str().to_owned())),
+                armor_color: Set(typed.armor_color.map(|t| t.as_str().to_owned())),
+                hair_style: Set(typed.hair_style.map(|t| t.as_str().to_owned())),
+                warpaint: Set(typed.warpaint.map(|t| t.as_str().to_owned())),
             });
 
             traits.extend(
@@ -85,16 +157,21 @@ impl ChampionTask {
                                 fighter_id: Set(fighter.attributes.id as i64),
                                 trait_type: Set(trait_type),
                                 value: Set(value),
+                                numeric_value: Set(None),
+                                display_type: Set(None),
                             }
                         }
                         AttributeEntry::Number {
                             trait_type,
                             value,
-                            display_type: _,
+                            display_type,
                         } => fighter_trait::ActiveModel {
                             fighter_id: Set(fighter.attributes.id as i64),
                             trait_type: Set(trait_type),
                             value: Set(value.to_string()),
+                            numeric_value: Set(Some(value as i64)),
+                            display_type: Set(display_type
+                                .map(|d| display_type_name(d).to_owned())),
                         },
                     }),
             )
@@ -108,6 +185,8 @@ impl ChampionTask {
             .collect::<Vec<_>>();
 
         for chunk in champions {
+            let rows = chunk.len();
+
             // No need to "clear out" rows here
             let _ = Fighter::insert_many(chunk)
                 .on_conflict(
@@ -122,6 +201,14 @@ impl ChampionTask {
                             fighter::Column::StrengthFrom,
                             fighter::Column::StrengthTo,
                             fighter::Column::WisdomPoint,
+                            fighter::Column::Elo,
+                            fighter::Column::Bloodline,
+                            fighter::Column::Genotype,
+                            fighter::Column::CharacterClass,
+                            fighter::Column::Breed,
+                            fighter::Column::ArmorColor,
+                            fighter::Column::HairStyle,
+                            fighter::Column::Warpaint,
                         ])
                         .to_owned(),
                 )
@@ -131,6 +218,8 @@ impl ChampionTask {
                     warn!(e = ?e);
                     e
                 })?;
+
+            metrics::upserted("fighter", rows);
         }
 
         let traits = traits
@@ -141,6 +230,7 @@ impl ChampionTask {
             .collect::<Vec<_>>();
 
         for chunk in traits {
+            let rows = chunk.len();
             let ids = chunk
                 .iter()
                 .map(|x| x.fighter_id.clone().unwrap())
@@ -166,6 +256,8 @@ impl ChampionTask {
                                     fighter_trait::Column::FighterId,
                                     fighter_trait::Column::TraitType,
                                     fighter_trait::Column::Value,
+                                    fighter_trait::Column::NumericValue,
+                                    fighter_trait::Column::DisplayType,
                                 ])
                                 .to_owned(),
                             )
@@ -180,6 +272,8 @@ impl ChampionTask {
                     warn!(e = ?e);
                     e
                 })?;
+
+            metrics::upserted("fighter_trait", rows);
         }
 
         let parents = parents
@@ -190,6 +284,7 @@ impl ChampionTask {
             .collect::<Vec<_>>();
 
         for chunk in parents {
+            let rows = chunk.len();
             let ids = chunk
                 .iter()
                 .map(|x| x.fighter_id.clone().unwrap())
@@ -228,9 +323,72 @@ impl ChampionTask {
                     warn!(e = ?e);
                     e
                 })?;
+
+            metrics::upserted("fighter_parent", rows);
         }
 
-        info!("champion scan complete");
+        Ok(())
+    }
+
+    /// Get the IDs of all champions that failed to be fetched previously, split into those to
+    /// retry and those that were given up on.
+    async fn get_failed_ids(&self) -> Result<(HashSet<u64>, HashSet<u64>), DbErr> {
+        let (given_up, failed): (Vec<_>, Vec<_>) = MetaFailedFighterRequest::find()
+            .all(&self.conn)
+            .await?
+            .into_iter()
+            .partition(given_up);
+
+        let ids = |failed: Vec<meta_failed_fighter_request::Model>| {
+            failed.into_iter().map(|m| m.fighter_id as u64).collect()
+        };
+        Ok((ids(failed), ids(given_up)))
+    }
+
+    /// Register a failed champion, bumping the attempt count if it has failed before.
+    async fn insert_failed_champion(&self, id: u64, e: &ScrapeError) -> Result<(), DbErr> {
+        use meta_failed_fighter_request::*;
+
+        Entity::insert(ActiveModel {
+            fighter_id: Set(id as i64),
+            error_kind: Set(e.kind().to_owned()),
+            attempts: Set(1),
+            last_error: Set(e.to_string()),
+            last_attempt: Set(Utc::now().naive_utc()),
+        })
+        .on_conflict(
+            OnConflict::column(Column::FighterId)
+                .values([
+                    (Column::ErrorKind, excluded(Column::ErrorKind)),
+                    (Column::LastError, excluded(Column::LastError)),
+                    (Column::LastAttempt, excluded(Column::LastAttempt)),
+                    (
+                        Column::Attempts,
+                        Expr::col((Entity, Column::Attempts)).add(1),
+                    ),
+                ])
+                .to_owned(),
+        )
+        .exec(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove champions that have been fetched successfully from the failed list.
+    async fn delete_failed_champions(&self, ids: Vec<u64>) -> Result<(), DbErr> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        MetaFailedFighterRequest::delete_many()
+            .filter(
+                meta_failed_fighter_request::Column::FighterId
+                    .is_in(ids.into_iter().map(|id| id as i64)),
+            )
+            .exec(&self.conn)
+            .await?;
+
         Ok(())
     }
 
@@ -275,45 +433,127 @@ impl ChampionTask {
         // Ok(1000)
     }
 
-    async fn scrape_champions(&self, up_to: u64) -> Vec<(FighterResponse, DateTime<Utc>)> {
-        stream::iter(0..=up_to)
-            .map(|i| async move {
-                let resp = self.get_champion(i).await?;
-                debug!(n = ?i, "completed");
-                Ok::<(FighterResponse, DateTime<Utc>), reqwest::Error>((resp, Utc::now()))
+    /// Fetch the given champions, returning the ones that failed separately.
+    ///
+    /// Champions whose metadata could not be deserialized are stored as dead letters instead.
+    async fn scrape_champions(&self, ids: Vec<u64>) -> (FetchedChampions, FailedChampions) {
+        let (fetched, failed): (Vec<_>, _) = stream::iter(ids)
+            .map(|i| {
+                async move {
+                    let raw = self.get_champion(i).await.map_err(|e| (i, e))?;
+                    debug!(n = ?i, "completed");
+
+                    if let Some(archive) = &self.archive {
+                        archive
+                            .store(PayloadKind::Fighter, i.to_string(), champion_url(i), &raw)
+                            .await;
+                    }
+                    Ok::<_, (u64, ScrapeError)>(
+                        self.parse_champion(i, raw)
+                            .await
+                            .map(|resp| (resp, Utc::now())),
+                    )
+                }
+                .instrument(info_span!("champion", fighter_id = i))
             })
             .buffer_unordered(CONCURRENT_REQUESTS)
             .collect::<Vec<Result<_, _>>>()
             .await
             .into_iter()
-            .filter_map(|x| x.ok())
-            .collect()
+            .partition_result();
+
+        (fetched.into_iter().flatten().collect(), failed)
     }
 
-    async fn get_champion(&self, id: u64) -> Result<FighterResponse, reqwest::Error> {
-        backoff::future::retry(ExponentialBackoff::default(), || async {
-            debug!(id = ?id, "sending request");
-            self.client
-                .get(format!(
-                    "https://federation22.theredvillage.com/api/v2/champions/id/{id}",
-                ))
-                .send()
-                .await
-                .and_then(|resp| resp.error_for_status())
-                .map(|resp| resp.json::<FighterResponse>())
-                .map_err(|e| {
-                    warn!(e = ?e);
-                    e
-                })?
-                .await
-                // No point in trying again if metadata is invalid
-                .map_err(|e| {
-                    warn!(id = ?id, "invalid metadata (perhaps token doesn't exist)");
-                    Error::Permanent(e)
-                })
-        })
+    /// Deserialize a raw champion, storing it as a dead letter if that fails.
+    async fn parse_champion(&self, id: u64, raw: Value) -> Option<FighterResponse> {
+        let e = match Deserialize::deserialize(&raw) {
+            Ok(v) => return Some(v),
+            Err(e) => e,
+        };
+
+        warn!(id = ?id, e = ?e, "invalid metadata (perhaps token doesn't exist), storing as dead letter");
+
+        if let Err(e) = dead_letter::insert(
+            &self.conn,
+            PayloadKind::Fighter,
+            id.to_string(),
+            champion_url(id),
+            raw,
+            &e,
+        )
         .await
+        {
+            warn!(id = ?id, e = ?e, "could not store dead letter. this champion will not be tried again!");
+        }
+
+        None
     }
+
+    async fn get_champion(&self, id: u64) -> Result<Value, ScrapeError> {
+        self.client
+            .get_json("champion", &champion_url(id), &[])
+            .await
+            .map_err(|e| {
+                warn!(id = ?id, e = ?e, "could not get champion (perhaps token doesn't exist)");
+                e
+            })
+    }
+}
+
+fn champion_url(id: u64) -> String {
+    format!("https://{FEDERATION_HOST}/api/v2/champions/id/{id}")
+}
+
+type FetchedChampions = Vec<(FighterResponse, DateTime<Utc>)>;
+type FailedChampions = Vec<(u64, ScrapeError)>;
+
+/// Summary of champions that could not be fetched.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct FailureReport {
+    pub total: usize,
+    pub by_kind: HashMap<String, usize>,
+    pub max_attempts: i32,
+    /// Champions that are no longer asked for.
+    pub given_up: usize,
+}
+
+impl FailureReport {
+    fn new(failed: &[meta_failed_fighter_request::Model]) -> Self {
+        Self {
+            total: failed.len(),
+            by_kind: failed.iter().map(|m| m.error_kind.clone()).counts(),
+            max_attempts: failed.iter().map(|m| m.attempts).max().unwrap_or_default(),
+            given_up: failed.iter().filter(|m| given_up(m)).count(),
+        }
+    }
+}
+
+/// Summarise the champions that are currently failing.
+pub async fn failure_report(conn: &DatabaseConnection) -> Result<FailureReport, DbErr> {
+    Ok(FailureReport::new(
+        &MetaFailedFighterRequest::find().all(conn).await?,
+    ))
+}
+
+/// Whether a failed champion was not found often enough to stop asking for it. Other failures
+/// may clear up, so they are retried every scan.
+fn given_up(failed: &meta_failed_fighter_request::Model) -> bool {
+    failed.error_kind == "not_found" && failed.attempts >= MAX_NOT_FOUND_ATTEMPTS
+}
+
+/// Token IDs up to `count` that were not already retried as failed this scan or given up on.
+fn unretried(count: u64, skipped: &HashSet<u64>) -> Vec<u64> {
+    (0..=count).filter(|i| !skipped.contains(i)).collect()
+}
+
+/// Retried champions that did not fail again, and so no longer need tracking.
+fn recovered(failed: HashSet<u64>, failed_again: &HashSet<u64>) -> Vec<u64> {
+    failed
+        .into_iter()
+        .filter(|id| !failed_again.contains(id))
+        .sorted()
+        .collect()
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -325,27 +565,79 @@ struct GetNftsForCollection {
 }
 
 async fn get_nfts_for_collection(
-    client: &reqwest::Client,
+    client: &Client,
     key: &str,
     contract_address: Address,
     start_token: u64,
-) -> Result<GetNftsForCollection, reqwest::Error> {
-    backoff::future::retry(ExponentialBackoff::default(), || async {
-        client
-            .get(format!(
-                "https://polygon-mainnet.g.alchemy.com/nft/v2/{key}/getNFTsForCollection",
-            ))
-            .query(&[
+) -> Result<GetNftsForCollection, ScrapeError> {
+    client
+        .get_json(
+            "alchemy_nfts",
+            &format!("https://{ALCHEMY_HOST}/nft/v2/{key}/getNFTsForCollection"),
+            &[
                 ("contractAddress", format!("{contract_address:?}",)),
                 ("withMetadata", "false".to_owned()),
                 ("startToken", start_token.to_string()),
-            ])
-            .send()
-            .await
-            .and_then(|resp| resp.error_for_status())
-            .map(|resp| resp.json::<GetNftsForCollection>())?
-            .await
-            .map_err(Error::Permanent)
-    })
-    .await
+            ],
+        )
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    #[test]
+    fn failed_champions_are_retried_once() {
+        let failed = HashSet::from([2, 5]);
+        assert_eq!(unretried(6, &failed), [0, 1, 3, 4, 6]);
+        assert_eq!(recovered(failed, &HashSet::from([5, 6])), [2]);
+    }
+
+    #[test]
+    fn champions_that_keep_not_being_found_are_given_up_on() {
+        let failed = |error_kind: &str, attempts| meta_failed_fighter_request::Model {
+            fighter_id: 1,
+            error_kind: error_kind.to_owned(),
+            attempts,
+            last_error: String::new(),
+            last_attempt: NaiveDateTime::default(),
+        };
+
+        assert!(!given_up(&failed("not_found", MAX_NOT_FOUND_ATTEMPTS - 1)));
+        assert!(given_up(&failed("not_found", MAX_NOT_FOUND_ATTEMPTS)));
+        assert!(!given_up(&failed("server", MAX_NOT_FOUND_ATTEMPTS)));
+        assert_eq!(
+            FailureReport::new(&[failed("not_found", MAX_NOT_FOUND_ATTEMPTS)]).given_up,
+            1
+        );
+    }
+
+    #[test]
+    fn failures_are_counted_by_kind() {
+        let failed = [("not_found", 1), ("schema", 3), ("not_found", 2)]
+            .into_iter()
+            .enumerate()
+            .map(
+                |(id, (kind, attempts))| meta_failed_fighter_request::Model {
+                    fighter_id: id as i64,
+                    error_kind: kind.to_owned(),
+                    attempts,
+                    last_error: String::new(),
+                    last_attempt: NaiveDateTime::default(),
+                },
+            )
+            .collect::<Vec<_>>();
+        let report = FailureReport::new(&failed);
+
+        assert_eq!(report.total, 3);
+        assert_eq!(
+            report.by_kind,
+            HashMap::from([("not_found".to_owned(), 2), ("schema".to_owned(), 1)])
+        );
+        assert_eq!(report.max_attempts, 3);
+
+        assert_eq!(FailureReport::new(&[]).max_attempts, 0);
+    }
 }
@@ -1,2 +1,9 @@
+use sea_orm::sea_query::{Alias, Expr, IntoIden, SimpleExpr};
+
 pub mod fighter;
 pub mod tournament;
+
+/// The value that would have been inserted into `column` had the insert not conflicted.
+pub(crate) fn excluded(column: impl IntoIden + 'static) -> SimpleExpr {
+    Expr::col((Alias::new("excluded"), column)).into()
+}
@@ -1,39 +1,69 @@
 use anyhow::Result;
 use api::{
     tournament::{RawTournamentResponse, Status, Tournament, TournamentResponse},
-    tournament_detail::TournamentDetailResponse,
+    tournament_detail::{Battle, TournamentDetailResponse},
 };
-use backoff::{Error, ExponentialBackoff};
-use chrono::Utc;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
 use entity::entities::{
-    meta_failed_tournament_request, meta_last_page, tournament, tournament_detail_attack,
-    tournament_detail_champion, tournament_fighter,
+    meta_failed_tournament_request, meta_last_page, meta_tournament_detail, meta_tournament_page,
+    sea_orm_active_enums::{TournamentDetailState, TournamentStatus},
+    tournament, tournament_detail_attack, tournament_detail_champion, tournament_fighter,
 };
 use ethers_core::abi::AbiEncode;
-use futures::{future, stream, StreamExt};
+use futures::{stream, StreamExt};
 use itertools::Itertools;
 use sea_orm::{
-    sea_query::OnConflict, ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait,
-    DatabaseBackend, DatabaseConnection, DbErr, EntityTrait, ModelTrait, QueryFilter, Set,
-    Statement, TransactionTrait,
+    sea_query::{Expr, OnConflict},
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, JoinType,
+    ModelTrait, PaginatorTrait, QueryFilter, QuerySelect, RelationTrait, Set, TransactionTrait,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    sync::atomic::{AtomicBool, Ordering},
 };
 use tracing::{debug, info, instrument, warn};
 
-use crate::CONCURRENT_REQUESTS;
+use super::excluded;
+use crate::{
+    archive::Archive,
+    client::{Client, FEDERATION_HOST},
+    combat,
+    dead_letter::{self, PayloadKind},
+    error::ScrapeError,
+    metrics, CONCURRENT_REQUESTS,
+};
+
+/// Times fetching a tournament detail may fail before it is given up on.
+const MAX_DETAIL_ATTEMPTS: i32 = 8;
+
+/// How long to wait before fetching a failed detail again, doubled after every further failure.
+const DETAIL_RETRY_BACKOFF: Duration = Duration::minutes(5);
 
 #[derive(Debug)]
 pub struct TournamentTask {
-    client: reqwest::Client,
+    client: Client,
     conn: DatabaseConnection,
     page_size: u64,
+    archive: Option<Archive>,
+    /// Whether open tournaments without a recorded page have been looked for during this run.
+    pages_located: AtomicBool,
 }
 
 impl TournamentTask {
-    pub fn new(client: reqwest::Client, conn: DatabaseConnection, page_size: u64) -> Self {
+    pub fn new(
+        client: Client,
+        conn: DatabaseConnection,
+        page_size: u64,
+        archive: Option<Archive>,
+    ) -> Self {
         Self {
             client,
             conn,
             page_size,
+            archive,
+            pages_located: AtomicBool::new(false),
         }
     }
 
@@ -42,6 +72,10 @@ impl TournamentTask {
         info!("beginning tournament scan");
 
         let _ = self.retry_failed().await;
+        let _ = self.retry_details().await.map_err(|e| {
+            warn!(e = ?e, "could not retry tournament details");
+            e
+        });
 
         let mut next_page_index = self.get_starting_page().await?;
 
@@ -57,7 +91,7 @@ impl TournamentTask {
                     warn!(e = ?e);
 
                     if let Err(e) = self
-                        .insert_failed_page(self.page_size, next_page_index)
+                        .insert_failed_page(self.page_size, next_page_index, &e)
                         .await
                     {
                         warn!(size = ?self.page_size, index = ?next_page_index, e = ?e, "could not register failed page. this page will not be tried again!");
@@ -68,53 +102,32 @@ impl TournamentTask {
                 }
             };
 
-            let batch = TournamentResponse {
-                pagination: batch.pagination,
-                items: batch.items.into_iter().enumerate().filter_map(|(idx, item)| match serde_json::from_value(item) {
-                    Ok(v) => Some(v),
-                    Err(e) => {
-                        warn!(size = ?self.page_size, page = ?next_page_index, index = ?idx, e = ?e, "could not deserialize, skipping. this tournament will not be tried again!");
-                        None
-                    }
-                })
-                .collect()
-            };
+            let batch = self
+                .parse_batch(batch, self.page_size, next_page_index)
+                .await;
 
-            if let Err(e) = self.insert_tournament_batch(batch.items.clone()).await {
+            if let Err(e) = self
+                .insert_tournament_batch(fetched_now(&batch.items))
+                .await
+            {
                 warn!(page = ?batch.pagination, e = ?e, "page failed to insert");
 
                 // If the page insert fails we will try again sometime later
                 if let Err(e) = self
-                    .insert_failed_page(self.page_size, next_page_index)
+                    .insert_failed_page(self.page_size, next_page_index, &e)
                     .await
                 {
                     warn!(size = ?self.page_size, index = ?next_page_index, e = ?e, "could not register failed page. this page will not be tried again!");
                 }
-            }
-
-            let details = stream::iter(batch.items)
-                .filter(|b| future::ready(b.status() != Status::Cancelled))
-                .map(|b| async move {
-                    let id = b.id();
-                    let service_id = b.service_id();
-
-                    self.get_tournament_detail(id, service_id)
-                        .await
-                        .map(|res| (id, service_id, res))
-                })
-                .buffer_unordered(CONCURRENT_REQUESTS)
-                .collect::<Vec<Result<_, _>>>()
+            } else if let Err(e) = self
+                .insert_tournament_pages(&batch.items, self.page_size, next_page_index)
                 .await
-                .into_iter()
-                .filter_map(|x| x.ok())
-                .collect::<Vec<_>>();
-
-            for (id, service_id, detail) in details {
-                if let Err(e) = self.insert_tournament_detail(id, service_id, detail).await {
-                    warn!(e = ?e, id = id, service_id = service_id, "could not insert tournament detail")
-                }
+            {
+                warn!(size = ?self.page_size, index = ?next_page_index, e = ?e, "could not record tournament pages. these tournaments will not be refreshed!");
             }
 
+            self.scrape_details(batch.items).await;
+
             if !batch.pagination.has_next_page {
                 break;
             } else {
@@ -122,11 +135,8 @@ impl TournamentTask {
             }
         }
 
-        self.conn
-            .execute(Statement::from_string(
-                DatabaseBackend::Postgres,
-                "TRUNCATE meta_last_page;".to_owned(),
-            ))
+        meta_last_page::Entity::delete_many()
+            .exec(&self.conn)
             .await?;
 
         meta_last_page::ActiveModel {
@@ -136,6 +146,12 @@ impl TournamentTask {
         .insert(&self.conn)
         .await?;
 
+        metrics::FAILED_PAGES.set(
+            meta_failed_tournament_request::Entity::find()
+                .count(&self.conn)
+                .await? as i64,
+        );
+
         Ok(())
     }
 
@@ -149,33 +165,494 @@ impl TournamentTask {
         Ok(entry as u64 / self.page_size)
     }
 
-    /// Insert a failed page into the database to be tried later.
-    async fn insert_failed_page(&self, page_size: u64, page_index: u64) -> Result<(), DbErr> {
+    /// Deserialize a raw page, storing items that cannot be deserialized as dead letters.
+    async fn parse_batch(
+        &self,
+        batch: RawTournamentResponse,
+        page_size: u64,
+        page_index: u64,
+    ) -> TournamentResponse {
+        let mut items = vec![];
+
+        for (idx, item) in batch.items.into_iter().enumerate() {
+            let reference = match (
+                item.get("service_id").and_then(Value::as_u64),
+                item.get("tournament_id").and_then(Value::as_i64),
+            ) {
+                (Some(service_id), Some(id)) => format!("{service_id}/{id}"),
+                _ => format!("{page_size}/{page_index}/{idx}"),
+            };
+
+            if let Some(archive) = &self.archive {
+                archive
+                    .store(
+                        PayloadKind::Tournament,
+                        reference.clone(),
+                        tournament_page_url(page_size, page_index),
+                        &item,
+                    )
+                    .await;
+            }
+
+            let e = match Deserialize::deserialize(&item) {
+                Ok(v) => {
+                    items.push(v);
+                    continue;
+                }
+                Err(e) => e,
+            };
+
+            warn!(size = ?page_size, page = ?page_index, index = ?idx, e = ?e, "could not deserialize, storing as dead letter");
+
+            if let Err(e) = dead_letter::insert(
+                &self.conn,
+                PayloadKind::Tournament,
+                reference,
+                tournament_page_url(page_size, page_index),
+                item,
+                &e,
+            )
+            .await
+            {
+                warn!(size = ?page_size, page = ?page_index, index = ?idx, e = ?e, "could not store dead letter. this tournament will not be tried again!");
+            }
+        }
+
+        TournamentResponse {
+            pagination: batch.pagination,
+            items,
+        }
+    }
+
+    /// Insert tournaments and fetch their details.
+    pub(crate) async fn ingest_tournaments(&self, tournaments: Vec<Tournament>) -> Result<()> {
+        self.insert_tournament_batch(fetched_now(&tournaments))
+            .await?;
+        self.scrape_details(tournaments).await;
+        Ok(())
+    }
+
+    /// Fetch and insert details for all tournaments that were not cancelled.
+    async fn scrape_details(&self, tournaments: Vec<Tournament>) {
+        let ids = tournaments
+            .into_iter()
+            .filter(|t| t.status() != Status::Cancelled)
+            .map(|t| (t.id(), t.service_id()))
+            .collect();
+
+        self.scrape_detail_ids(ids).await;
+    }
+
+    /// Fetch and insert details for the given tournaments, recording the outcome of each.
+    async fn scrape_detail_ids(&self, ids: Vec<(i64, u64)>) {
+        stream::iter(ids)
+            .for_each_concurrent(CONCURRENT_REQUESTS, |(id, service_id)| async move {
+                let Err(e) = self.scrape_detail(id, service_id).await else {
+                    return;
+                };
+
+                if let Err(e) = self.update_detail_state(id, service_id, Err(e)).await {
+                    warn!(e = ?e, id = id, service_id = service_id, "could not record tournament detail state");
+                }
+            })
+            .await;
+    }
+
+    /// Fetch and insert the detail of a single tournament, returning why that failed if it did.
+    #[instrument(skip_all, fields(tournament_id = id, service_id = service_id))]
+    async fn scrape_detail(&self, id: i64, service_id: u64) -> Result<(), ScrapeError> {
+        let raw = self.get_tournament_detail(id, service_id).await?;
+
+        if let Some(archive) = &self.archive {
+            archive
+                .store(
+                    PayloadKind::TournamentDetail,
+                    format!("{service_id}/{id}"),
+                    tournament_detail_url(id, service_id),
+                    &raw,
+                )
+                .await;
+        }
+
+        let detail = self.parse_detail(id, service_id, raw).await?;
+
+        self.ingest_tournament_detail(id, service_id, detail)
+            .await
+            .map_err(|e| {
+                warn!(e = ?e, id = id, service_id = service_id, "could not insert tournament detail");
+                e.into()
+            })
+    }
+
+    /// Insert a tournament detail, record whether it had any battles in it and add its attacks to
+    /// the combat aggregates.
+    pub(crate) async fn ingest_tournament_detail(
+        &self,
+        id: i64,
+        service_id: u64,
+        detail: TournamentDetailResponse,
+    ) -> Result<(), DbErr> {
+        let state = if detail.battles.is_empty() {
+            TournamentDetailState::Empty
+        } else {
+            TournamentDetailState::Fetched
+        };
+
+        // Counted before inserting, so that only the attacks that are new are added
+        let before = combat::tournament_counts(&self.conn, id, service_id).await;
+
+        self.insert_tournament_detail(id, service_id, detail)
+            .await?;
+        self.update_detail_state(id, service_id, Ok(state)).await?;
+
+        // The detail is stored either way, so a failure only leaves the aggregates behind until
+        // they are recomputed
+        let combat = async { combat::add_tournament(&self.conn, id, service_id, &before?).await };
+        if let Err(e) = combat.await {
+            warn!(e = ?e, id = id, service_id = service_id, "could not update combat aggregates");
+        }
+
+        Ok(())
+    }
+
+    /// Record the outcome of fetching a tournament detail. Only failures count as attempts.
+    async fn update_detail_state(
+        &self,
+        id: i64,
+        service_id: u64,
+        state: Result<TournamentDetailState, ScrapeError>,
+    ) -> Result<(), DbErr> {
+        use meta_tournament_detail::*;
+
+        let (state, error_kind, last_error, failures) = match state {
+            Ok(state) => (state, None, None, 0),
+            Err(e) => (
+                TournamentDetailState::Failed,
+                Some(e.kind().to_owned()),
+                Some(e.to_string()),
+                1,
+            ),
+        };
+
+        Entity::insert(ActiveModel {
+            tournament_id: Set(id),
+            tournament_service_id: Set(service_id as i64),
+            state: Set(state),
+            attempts: Set(failures),
+            last_error: Set(last_error),
+            last_attempt: Set(Some(Utc::now().naive_utc())),
+            error_kind: Set(error_kind),
+        })
+        .on_conflict(
+            OnConflict::columns([Column::TournamentId, Column::TournamentServiceId])
+                .values([
+                    (Column::State, excluded(Column::State)),
+                    (Column::ErrorKind, excluded(Column::ErrorKind)),
+                    (Column::LastError, excluded(Column::LastError)),
+                    (Column::LastAttempt, excluded(Column::LastAttempt)),
+                    (
+                        Column::Attempts,
+                        Expr::col((Entity, Column::Attempts)).add(failures),
+                    ),
+                ])
+                .to_owned(),
+        )
+        .exec(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Fetch the pending and failed details of tournaments that were not cancelled, backing off
+    /// after each failure and giving up after [`MAX_DETAIL_ATTEMPTS`].
+    ///
+    /// A detail fetched while its tournament was open becomes pending again whenever the
+    /// tournament's status changes, so it is fetched until the tournament completes.
+    async fn retry_details(&self) -> Result<()> {
+        let now = Utc::now().naive_utc();
+        let outstanding = meta_tournament_detail::Entity::find()
+            .join(
+                JoinType::InnerJoin,
+                meta_tournament_detail::Relation::Tournament.def(),
+            )
+            .filter(tournament::Column::Status.ne(TournamentStatus::Cancelled))
+            .filter(
+                // `is_in` does not cast to the enum type, so compare each state separately
+                Condition::any()
+                    .add(meta_tournament_detail::Column::State.eq(TournamentDetailState::Pending))
+                    .add(meta_tournament_detail::Column::State.eq(TournamentDetailState::Failed)),
+            )
+            .filter(meta_tournament_detail::Column::Attempts.lt(MAX_DETAIL_ATTEMPTS))
+            .all(&self.conn)
+            .await?
+            .into_iter()
+            .filter(|m| retry_due(m.attempts, m.last_attempt, now))
+            .map(|m| (m.tournament_id, m.tournament_service_id as u64))
+            .collect::<Vec<_>>();
+
+        info!(count = outstanding.len(), "retrying tournament details");
+        self.scrape_detail_ids(outstanding).await;
+
+        Ok(())
+    }
+
+    /// Re-query the pages of tournaments that have not reached a terminal status yet.
+    ///
+    /// Tournaments whose `modified` time moved on are updated and their details fetched again.
+    #[instrument(skip_all)]
+    pub async fn refresh(&self) -> Result<()> {
+        info!("beginning tournament refresh");
+
+        if !self.pages_located.swap(true, Ordering::Relaxed) {
+            if let Err(e) = self.locate_pages().await {
+                warn!(e = ?e, "could not locate the pages of open tournaments");
+                self.pages_located.store(false, Ordering::Relaxed);
+            }
+        }
+
+        let open = meta_tournament_page::Entity::find()
+            .find_also_related(tournament::Entity)
+            .filter(open_status())
+            .all(&self.conn)
+            .await?;
+
+        let mut known = HashMap::new();
+        let mut pages = BTreeSet::new();
+        for (page, tournament) in open {
+            let Some(tournament) = tournament else {
+                continue;
+            };
+
+            known.insert(
+                (tournament.id, tournament.service_id as u64),
+                tournament.modified,
+            );
+            pages.insert((page.page_size as u64, page.page_index as u64));
+        }
+
+        info!(
+            tournaments = known.len(),
+            pages = pages.len(),
+            "refreshing open tournaments"
+        );
+
+        let mut updated = 0;
+        for (page_size, page_index) in pages {
+            let batch = match self.get_tournament_batch(page_size, page_index).await {
+                Ok(v) => v,
+                Err(e) => {
+                    warn!(size = ?page_size, page = ?page_index, e = ?e, "could not refresh page");
+                    continue;
+                }
+            };
+
+            let batch = self.parse_batch(batch, page_size, page_index).await;
+
+            let (cancelled, changed): (Vec<_>, Vec<_>) = batch
+                .items
+                .into_iter()
+                .filter(|t| {
+                    known
+                        .get(&(t.id(), t.service_id()))
+                        .is_some_and(|modified| t.modified().naive_utc() > *modified)
+                })
+                .partition(|t| t.status() == Status::Cancelled);
+
+            updated += cancelled.len() + changed.len();
+
+            // Cancelled tournaments are never inserted, so only their status is updated
+            for t in cancelled {
+                if let Err(e) = (tournament::ActiveModel {
+                    id: Set(t.id()),
+                    service_id: Set(t.service_id() as i32),
+                    status: Set(TournamentStatus::Cancelled),
+                    modified: Set(t.modified().naive_utc()),
+                    meta_last_updated: Set(Utc::now().naive_utc()),
+                    ..Default::default()
+                })
+                .update(&self.conn)
+                .await
+                {
+                    warn!(id = t.id(), service_id = t.service_id(), e = ?e, "could not mark tournament as cancelled");
+                }
+            }
+
+            if let Err(e) = self.insert_tournament_batch(fetched_now(&changed)).await {
+                warn!(size = ?page_size, page = ?page_index, e = ?e, "could not update refreshed tournaments");
+                continue;
+            }
+
+            self.scrape_details(changed).await;
+        }
+
+        info!(updated = updated, "tournament refresh complete");
+        Ok(())
+    }
+
+    /// Walk the tournament pages until every open tournament without a recorded page, such as
+    /// those scraped before pages were recorded, has been seen, and record their pages.
+    async fn locate_pages(&self) -> Result<()> {
+        let mut unpaged = tournament::Entity::find()
+            .join(
+                JoinType::LeftJoin,
+                tournament::Relation::MetaTournamentPage.def(),
+            )
+            .filter(open_status())
+            .filter(meta_tournament_page::Column::TournamentId.is_null())
+            .all(&self.conn)
+            .await?
+            .into_iter()
+            .map(|t| (t.id, t.service_id as u64))
+            .collect::<HashSet<_>>();
+
+        if unpaged.is_empty() {
+            return Ok(());
+        }
+        info!(
+            tournaments = unpaged.len(),
+            "locating pages of open tournaments"
+        );
+
+        let mut page_index = 0;
+        while !unpaged.is_empty() {
+            let batch = self
+                .get_tournament_batch(self.page_size, page_index)
+                .await?;
+            let batch = self.parse_batch(batch, self.page_size, page_index).await;
+
+            self.insert_tournament_pages(&batch.items, self.page_size, page_index)
+                .await?;
+            for t in &batch.items {
+                unpaged.remove(&(t.id(), t.service_id()));
+            }
+
+            if !batch.pagination.has_next_page {
+                break;
+            }
+            page_index += 1;
+        }
+
+        if !unpaged.is_empty() {
+            warn!(
+                tournaments = unpaged.len(),
+                "open tournaments not found on any page. these tournaments will not be refreshed!"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Record which page each tournament was seen on so that it can be refreshed later.
+    async fn insert_tournament_pages(
+        &self,
+        tournaments: &[Tournament],
+        page_size: u64,
+        page_index: u64,
+    ) -> Result<(), DbErr> {
+        use meta_tournament_page::*;
+
+        // Cancelled tournaments are not inserted and never change again
+        let rows = tournaments
+            .iter()
+            .filter(|t| t.status() != Status::Cancelled)
+            .map(|t| ActiveModel {
+                tournament_id: Set(t.id()),
+                tournament_service_id: Set(t.service_id() as i64),
+                page_size: Set(page_size as i64),
+                page_index: Set(page_index as i64),
+            })
+            .collect::<Vec<_>>();
+
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        Entity::insert_many(rows)
+            .on_conflict(
+                OnConflict::columns([Column::TournamentId, Column::TournamentServiceId])
+                    .values([
+                        (Column::PageSize, excluded(Column::PageSize)),
+                        (Column::PageIndex, excluded(Column::PageIndex)),
+                    ])
+                    .to_owned(),
+            )
+            .exec_without_returning(&self.conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Deserialize a raw tournament detail, storing it as a dead letter if that fails.
+    async fn parse_detail(
+        &self,
+        id: i64,
+        service_id: u64,
+        raw: Value,
+    ) -> Result<TournamentDetailResponse, serde_json::Error> {
+        let e = match Deserialize::deserialize(&raw) {
+            Ok(v) => return Ok(v),
+            Err(e) => e,
+        };
+
+        warn!(e = ?e, id = id, service_id = service_id, "could not deserialize tournament detail, storing as dead letter");
+
+        if let Err(e) = dead_letter::insert(
+            &self.conn,
+            PayloadKind::TournamentDetail,
+            format!("{service_id}/{id}"),
+            tournament_detail_url(id, service_id),
+            raw,
+            &e,
+        )
+        .await
+        {
+            warn!(e = ?e, id = id, service_id = service_id, "could not store dead letter. this tournament detail will not be tried again!");
+        }
+
+        Err(e)
+    }
+
+    /// Insert a failed page into the database to be tried later, along with why it failed.
+    async fn insert_failed_page(
+        &self,
+        page_size: u64,
+        page_index: u64,
+        e: &ScrapeError,
+    ) -> Result<(), DbErr> {
         use meta_failed_tournament_request::*;
 
         Entity::insert(meta_failed_tournament_request::ActiveModel {
             page_size: Set(page_size as i64),
             page_index: Set(page_index as i64),
+            error_kind: Set(Some(e.kind().to_owned())),
+            last_error: Set(Some(e.to_string())),
         })
         .on_conflict(
             OnConflict::columns([Column::PageSize, Column::PageIndex])
-                .do_nothing()
+                .values([
+                    (Column::ErrorKind, excluded(Column::ErrorKind)),
+                    (Column::LastError, excluded(Column::LastError)),
+                ])
                 .to_owned(),
         )
-        .exec(&self.conn)
+        .exec_without_returning(&self.conn)
         .await?;
 
         Ok(())
     }
 
     /// Insert a response batch into the database.
-    async fn insert_tournament_batch(&self, tournaments: Vec<Tournament>) -> Result<()> {
+    ///
+    /// Each tournament comes with when it was fetched, which is recorded as its last update.
+    pub(crate) async fn insert_tournament_batch(
+        &self,
+        tournaments: Vec<(Tournament, DateTime<Utc>)>,
+    ) -> Result<(), ScrapeError> {
         let mut tournament_rows = vec![];
         let mut tournament_warrior_rows = vec![];
-        let time = Utc::now();
         // let mut tournament_solo_warrior_rows = vec![];
 
-        for tournament in tournaments {
+        for (tournament, fetched_at) in tournaments {
             if tournament.status() == Status::Cancelled {
                 continue;
             }
@@ -219,7 +696,7 @@ impl TournamentTask {
                         solo_optionals: Set(Some(solo_optionals)),
                         start_time: Set(start_time),
                         status: Set(status.into()),
-                        meta_last_updated: Set(time.naive_utc()),
+                        meta_last_updated: Set(fetched_at.naive_utc()),
                     });
                 }
                 Tournament::Blooding {
@@ -262,7 +739,7 @@ impl TournamentTask {
                         solo_optionals: Set(None),
                         start_time: Set(start_time),
                         status: Set(status.into()),
-                        meta_last_updated: Set(time.naive_utc()),
+                        meta_last_updated: Set(fetched_at.naive_utc()),
                     });
                 }
                 Tournament::Bloodbath {
@@ -305,7 +782,7 @@ impl TournamentTask {
                         solo_optionals: Set(None),
                         start_time: Set(start_time),
                         status: Set(status.into()),
-                        meta_last_updated: Set(time.naive_utc()),
+                        meta_last_updated: Set(fetched_at.naive_utc()),
                     });
                 }
                 Tournament::BloodElo {
@@ -348,7 +825,7 @@ impl TournamentTask {
                         solo_optionals: Set(None),
                         start_time: Set(start_time),
                         status: Set(status.into()),
-                        meta_last_updated: Set(time.naive_utc()),
+                        meta_last_updated: Set(fetched_at.naive_utc()),
                     });
                 }
                 Tournament::DoubleUp {
@@ -381,7 +858,7 @@ impl TournamentTask {
                         solo_optionals: Set(None),
                         start_time: Set(start_time),
                         status: Set(status.into()),
-                        meta_last_updated: Set(time.naive_utc()),
+                        meta_last_updated: Set(fetched_at.naive_utc()),
                     });
                 }
                 Tournament::DoubleUpReverse {
@@ -414,7 +891,7 @@ impl TournamentTask {
                         solo_optionals: Set(None),
                         start_time: Set(start_time),
                         status: Set(status.into()),
-                        meta_last_updated: Set(time.naive_utc()),
+                        meta_last_updated: Set(fetched_at.naive_utc()),
                     });
                 }
                 Tournament::Traditional {
@@ -447,7 +924,7 @@ impl TournamentTask {
                         solo_optionals: Set(None),
                         start_time: Set(start_time),
                         status: Set(status.into()),
-                        meta_last_updated: Set(time.naive_utc()),
+                        meta_last_updated: Set(fetched_at.naive_utc()),
                     });
                 }
             };
@@ -461,6 +938,23 @@ impl TournamentTask {
             .collect::<Vec<_>>();
 
         for chunk in tournament_rows {
+            // Every tournament starts out waiting for its detail
+            let pending = chunk
+                .iter()
+                .map(|t| meta_tournament_detail::ActiveModel {
+                    tournament_id: t.id.clone(),
+                    tournament_service_id: Set(t.service_id.clone().unwrap() as i64),
+                    state: Set(TournamentDetailState::Pending),
+                    attempts: Set(0),
+                    last_error: Set(None),
+                    last_attempt: Set(None),
+                    error_kind: Set(None),
+                })
+                .collect::<Vec<_>>();
+
+            let requeued = self.status_changes(&chunk).await?;
+
+            let rows = chunk.len();
             let _ = tournament::Entity::insert_many(chunk)
                 .on_conflict(
                     OnConflict::columns([tournament::Column::Id, tournament::Column::ServiceId])
@@ -490,6 +984,29 @@ impl TournamentTask {
                     warn!(e = ?e);
                     e
                 })?;
+
+            metrics::upserted("tournament", rows);
+
+            meta_tournament_detail::Entity::insert_many(pending)
+                .on_conflict(
+                    OnConflict::columns([
+                        meta_tournament_detail::Column::TournamentId,
+                        meta_tournament_detail::Column::TournamentServiceId,
+                    ])
+                    .do_nothing()
+                    .to_owned(),
+                )
+                .exec_without_returning(&self.conn)
+                .await
+                .map_err(|e| {
+                    warn!(e = ?e);
+                    e
+                })?;
+
+            self.requeue_details(requeued).await.map_err(|e| {
+                warn!(e = ?e);
+                e
+            })?;
         }
 
         let tournament_warrior_rows = tournament_warrior_rows
@@ -500,6 +1017,7 @@ impl TournamentTask {
             .collect::<Vec<_>>();
 
         for chunk in tournament_warrior_rows {
+            let rows = chunk.len();
             let ids = chunk
                 .iter()
                 .map(|x| {
@@ -553,28 +1071,100 @@ impl TournamentTask {
                     warn!(e = ?e);
                     e
                 })?;
+
+            metrics::upserted("tournament_fighter", rows);
+        }
+
+        Ok(())
+    }
+
+    /// The stored tournaments whose status differs from the one they are about to be updated to.
+    async fn status_changes(
+        &self,
+        tournaments: &[tournament::ActiveModel],
+    ) -> Result<Vec<(i64, i32)>, DbErr> {
+        let statuses = tournaments
+            .iter()
+            .map(|t| {
+                (
+                    (t.id.clone().unwrap(), t.service_id.clone().unwrap()),
+                    t.status.clone().unwrap(),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+
+        let stored = tournament::Entity::find()
+            .filter(
+                statuses
+                    .keys()
+                    .fold(Condition::any(), |cond, (id, service_id)| {
+                        cond.add(
+                            tournament::Column::Id
+                                .eq(*id)
+                                .and(tournament::Column::ServiceId.eq(*service_id)),
+                        )
+                    }),
+            )
+            .all(&self.conn)
+            .await?;
+
+        Ok(stored
+            .into_iter()
+            .filter(|t| statuses.get(&(t.id, t.service_id)) != Some(&t.status))
+            .map(|t| (t.id, t.service_id))
+            .collect())
+    }
+
+    /// Mark details that were fetched while their tournament was open as pending again, after the
+    /// tournament's status changed.
+    async fn requeue_details(&self, tournaments: Vec<(i64, i32)>) -> Result<(), DbErr> {
+        use meta_tournament_detail::*;
+
+        if tournaments.is_empty() {
+            return Ok(());
         }
 
+        Entity::update_many()
+            .set(ActiveModel {
+                state: Set(TournamentDetailState::Pending),
+                ..Default::default()
+            })
+            .filter(
+                tournaments
+                    .into_iter()
+                    .fold(Condition::any(), |cond, (id, service_id)| {
+                        cond.add(
+                            Column::TournamentId
+                                .eq(id)
+                                .and(Column::TournamentServiceId.eq(service_id as i64)),
+                        )
+                    }),
+            )
+            .filter(
+                // `is_in` does not cast to the enum type, so compare each state separately
+                Condition::any()
+                    .add(Column::State.eq(TournamentDetailState::Fetched))
+                    .add(Column::State.eq(TournamentDetailState::Empty)),
+            )
+            .exec(&self.conn)
+            .await?;
+
         Ok(())
     }
 
+    #[instrument(skip_all, fields(page = page_index, page_size = page_size))]
     async fn get_tournament_batch(
         &self,
         page_size: u64,
         page_index: u64,
-    ) -> Result<RawTournamentResponse, reqwest::Error> {
-        backoff::future::retry(ExponentialBackoff::default(), || async {
-            self.client
-                .get("https://federation22.theredvillage.com/api/v2/tournaments".to_string())
-                .query(&[("page_size", page_size), ("page_index", page_index)])
-                .send()
-                .await
-                .and_then(|resp| resp.error_for_status())
-                .map(|resp| resp.json::<RawTournamentResponse>())?
-                .await
-                .map_err(Error::Permanent)
-        })
-        .await
+    ) -> Result<RawTournamentResponse, ScrapeError> {
+        self.client
+            .get_json(
+                "tournament_page",
+                &tournament_page_url(page_size, page_index),
+                &[],
+            )
+            .await
     }
 
     async fn retry_failed(&self) -> Result<()> {
@@ -593,23 +1183,29 @@ impl TournamentTask {
                 }
             };
 
-            let batch = TournamentResponse {
-                pagination: batch.pagination,
-                items: batch.items.into_iter().enumerate().filter_map(|(idx, item)| match serde_json::from_value(item) {
-                    Ok(v) => Some(v),
-                    Err(e) => {
-                        warn!(size = ?page.page_size, page = ?page.page_index, index = ?idx, e = ?e, "could not deserialize, skipping. this tournament will not be tried again!");
-                        None
-                    }
-                })
-                .collect()
-            };
+            let batch = self
+                .parse_batch(batch, page.page_size as u64, page.page_index as u64)
+                .await;
 
-            if let Err(e) = self.insert_tournament_batch(batch.items).await {
+            if let Err(e) = self
+                .insert_tournament_batch(fetched_now(&batch.items))
+                .await
+            {
                 warn!(e = ?e);
                 continue;
             }
 
+            if let Err(e) = self
+                .insert_tournament_pages(
+                    &batch.items,
+                    page.page_size as u64,
+                    page.page_index as u64,
+                )
+                .await
+            {
+                warn!(e = ?e, "could not record tournament pages. these tournaments will not be refreshed!");
+            }
+
             // If it's ok we delete the page.
             let _ = page.delete(&self.conn).await.map_err(|e| {
                 warn!(e = ?e);
@@ -619,25 +1215,18 @@ impl TournamentTask {
         Ok(())
     }
 
-    async fn get_tournament_detail(
-        &self,
-        id: i64,
-        service_id: u64,
-    ) -> Result<TournamentDetailResponse, reqwest::Error> {
-        backoff::future::retry(ExponentialBackoff::default(), || async {
-            self.client
-                .get(format!("https://federation22.theredvillage.com/api/v2/battles/service/{service_id}/tournament/{id}"))
-                .send()
-                .await
-                .and_then(|resp| resp.error_for_status())
-                .map(|resp| resp.json::<TournamentDetailResponse>())?
-                .await
-                .map_err(|e| {
-                    warn!(e = ?e, id = id, service_id = service_id, "could not get tournament detail");
-                    Error::Permanent(e)
-                })
-        })
-        .await
+    async fn get_tournament_detail(&self, id: i64, service_id: u64) -> Result<Value, ScrapeError> {
+        self.client
+            .get_json(
+                "tournament_detail",
+                &tournament_detail_url(id, service_id),
+                &[],
+            )
+            .await
+            .map_err(|e| {
+                warn!(e = ?e, id = id, service_id = service_id, "could not get tournament detail");
+                e
+            })
     }
 
     async fn insert_tournament_detail(
@@ -660,37 +1249,44 @@ impl TournamentTask {
             })
             .collect::<Vec<_>>();
 
-        let _ = tournament_detail_champion::Entity::insert_many(champions)
-            .on_conflict(
-                OnConflict::columns([
-                    tournament_detail_champion::Column::TournamentId,
-                    tournament_detail_champion::Column::TournamentServiceId,
-                    tournament_detail_champion::Column::FighterId,
-                ])
-                .update_columns([
-                    tournament_detail_champion::Column::TournamentId,
-                    tournament_detail_champion::Column::TournamentServiceId,
-                    tournament_detail_champion::Column::FighterId,
-                    tournament_detail_champion::Column::Stance,
-                ])
-                .to_owned(),
-            )
-            .exec(&self.conn)
-            .await
-            .map_err(|e| {
-                warn!(e = ?e, id = id, service_id = service_id, "tournament_detail_champion");
-                e
-            });
+        if !champions.is_empty() {
+            let rows = champions.len();
+            tournament_detail_champion::Entity::insert_many(champions)
+                .on_conflict(
+                    OnConflict::columns([
+                        tournament_detail_champion::Column::TournamentId,
+                        tournament_detail_champion::Column::TournamentServiceId,
+                        tournament_detail_champion::Column::FighterId,
+                    ])
+                    .update_columns([
+                        tournament_detail_champion::Column::TournamentId,
+                        tournament_detail_champion::Column::TournamentServiceId,
+                        tournament_detail_champion::Column::FighterId,
+                        tournament_detail_champion::Column::Stance,
+                    ])
+                    .to_owned(),
+                )
+                .exec(&self.conn)
+                .await
+                .map_err(|e| {
+                    warn!(e = ?e, id = id, service_id = service_id, "tournament_detail_champion");
+                    e
+                })?;
+
+            metrics::upserted("tournament_detail_champion", rows);
+        }
 
         // Attack
+        let offsets = battle_offsets(&detail.battles);
         let attacks = detail
             .battles
             .iter()
-            .flat_map(|battle| {
-                battle.champions.iter().flat_map(|ca| {
+            .zip(offsets)
+            .flat_map(|(battle, offset)| {
+                battle.champions.iter().flat_map(move |ca| {
                     ca.attack
                         .iter()
-                        .map(|a| tournament_detail_attack::ActiveModel {
+                        .map(move |a| tournament_detail_attack::ActiveModel {
                             tournament_id: Set(id),
                             tournament_service_id: Set(service_id as i64),
                             fighter_id: Set(ca.id as i64),
@@ -698,14 +1294,14 @@ impl TournamentTask {
                             special_attack: Set(a.special_attack),
                             speical_defend: Set(a.special_defend),
                             damage: Set(a.damage as i32),
-                            order: Set(a.order as i32),
+                            order: Set(offset + a.order as i32),
                         })
                 })
             })
             .collect::<Vec<_>>();
 
         if !attacks.is_empty() {
-            let _ = tournament_detail_attack::Entity::insert_many(attacks)
+            let rows = tournament_detail_attack::Entity::insert_many(attacks)
                 .on_conflict(
                     OnConflict::columns([
                         tournament_detail_attack::Column::TournamentId,
@@ -716,14 +1312,304 @@ impl TournamentTask {
                     .do_nothing()
                     .to_owned(),
                 )
-                .exec(&self.conn)
+                .exec_without_returning(&self.conn)
                 .await
                 .map_err(|e| {
                     warn!(e = ?e, id = id, service_id = service_id, "tournament_detail_attack");
                     e
-                });
+                })?;
+
+            metrics::upserted("tournament_detail_attack", rows as usize);
         }
 
         Ok(())
     }
 }
+
+fn tournament_page_url(page_size: u64, page_index: u64) -> String {
+    format!("https://{FEDERATION_HOST}/api/v2/tournaments?page_size={page_size}&page_index={page_index}")
+}
+
+/// Where the attacks of each battle start in their round's `order`. Every battle numbers its
+/// attacks from 0, so a round's battles are stored one after another, a gap of one apart so that
+/// they can be told apart again.
+fn battle_offsets(battles: &[Battle]) -> Vec<i32> {
+    let mut next = HashMap::new();
+
+    battles
+        .iter()
+        .map(|battle| {
+            let attacks = battle
+                .champions
+                .iter()
+                .flat_map(|ca| &ca.attack)
+                .map(|a| a.order as i32 + 1)
+                .max()
+                .unwrap_or(0);
+            let next = next.entry(battle.round).or_insert(0);
+            let offset = *next;
+            *next += attacks + 1;
+            offset
+        })
+        .collect()
+}
+
+/// Tournaments that have not reached a terminal status yet.
+fn open_status() -> Condition {
+    // `is_in` does not cast to the enum type, so compare each status separately
+    Condition::any()
+        .add(tournament::Column::Status.eq(TournamentStatus::Created))
+        .add(tournament::Column::Status.eq(TournamentStatus::Fought))
+}
+
+/// Whether a detail that failed `attempts` times, last at `last_attempt`, has waited long enough.
+fn retry_due(attempts: i32, last_attempt: Option<NaiveDateTime>, now: NaiveDateTime) -> bool {
+    let Some(last_attempt) = last_attempt.filter(|_| attempts > 0) else {
+        return true;
+    };
+    if attempts >= MAX_DETAIL_ATTEMPTS {
+        return false;
+    }
+
+    now - last_attempt >= DETAIL_RETRY_BACKOFF * 2i32.pow(attempts as u32 - 1)
+}
+
+/// Tournaments that were just fetched, stamped with the current time.
+fn fetched_now(tournaments: &[Tournament]) -> Vec<(Tournament, DateTime<Utc>)> {
+    let now = Utc::now();
+    tournaments.iter().map(|t| (t.clone(), now)).collect()
+}
+
+fn tournament_detail_url(id: i64, service_id: u64) -> String {
+    format!("https://{FEDERATION_HOST}/api/v2/battles/service/{service_id}/tournament/{id}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_details_back_off_until_given_up() {
+        let now = NaiveDateTime::default() + Duration::days(1);
+        let ago = |minutes| Some(now - Duration::minutes(minutes));
+
+        assert!(retry_due(0, None, now));
+        assert!(retry_due(0, ago(0), now));
+        assert!(!retry_due(1, ago(4), now));
+        assert!(retry_due(1, ago(5), now));
+        assert!(!retry_due(3, ago(19), now));
+        assert!(retry_due(3, ago(20), now));
+        assert!(!retry_due(MAX_DETAIL_ATTEMPTS, ago(60 * 24), now));
+    }
+
+    #[test]
+    fn battles_of_a_round_are_stored_apart() {
+        let detail = serde_json::from_str::<TournamentDetailResponse>(include_str!(
+            "../../api/src/tests/tournament_detail_1.json"
+        ))
+        .unwrap();
+
+        // Four battles in the first round, two in the second and the final
+        assert_eq!(battle_offsets(&detail.battles), [0, 18, 33, 57, 0, 22, 0]);
+    }
+
+    /// A single page of blooding tournaments of service 1 with the given ids and statuses.
+    #[cfg(feature = "sqlite")]
+    fn page(tournaments: &[(i64, &str)]) -> Value {
+        let mut page =
+            serde_json::from_str::<Value>(include_str!("../../api/src/tests/blooding.json"))
+                .unwrap();
+        let item = page["items"][0].clone();
+
+        page["has_next_page"] = false.into();
+        page["items"] = tournaments
+            .iter()
+            .map(|(id, status)| {
+                let mut item = item.clone();
+                item["tournament_id"] = (*id).into();
+                item["status"] = (*status).into();
+                item
+            })
+            .collect();
+        page
+    }
+
+    /// Serve `page` as every tournament page and the first test detail for every tournament.
+    #[cfg(feature = "sqlite")]
+    async fn federation(page: Value) -> Client {
+        use axum::{routing::get, Json, Router};
+
+        let detail = serde_json::from_str::<Value>(include_str!(
+            "../../api/src/tests/tournament_detail_1.json"
+        ))
+        .unwrap();
+        let router = Router::new()
+            .route("/api/v2/tournaments", get(|| async { Json(page) }))
+            .route(
+                "/api/v2/battles/service/:service_id/tournament/:id",
+                get(|| async { Json(detail) }),
+            );
+
+        let server =
+            axum::Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(router.into_make_service());
+        let origin = format!("http://{}", server.local_addr()).parse().unwrap();
+        tokio::spawn(server);
+
+        crate::testing::client().with_origin(FEDERATION_HOST, origin)
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn refresh_updates_open_tournaments() {
+        use crate::testing;
+        use sea_orm::IntoActiveModel;
+
+        let conn = testing::database().await;
+        testing::without_foreign_keys(&conn).await;
+
+        // Three open tournaments, the first of which was scraped before pages were recorded
+        for id in [1, 2, 3] {
+            testing::tournament(id, 1, TournamentStatus::Created)
+                .into_active_model()
+                .insert(&conn)
+                .await
+                .unwrap();
+        }
+        meta_tournament_page::Entity::insert_many([2, 3].map(|id| {
+            meta_tournament_page::ActiveModel {
+                tournament_id: Set(id),
+                tournament_service_id: Set(1),
+                page_size: Set(3),
+                page_index: Set(0),
+            }
+        }))
+        .exec(&conn)
+        .await
+        .unwrap();
+
+        // Since then the first has been fought, the second cancelled and the third completed
+        let page = page(&[
+            (1, "FOUGHT_SUCCEED"),
+            (2, "CANCEL_SUCCEED"),
+            (3, "COMPLETE_SUCCEED"),
+        ]);
+
+        let task = TournamentTask::new(federation(page).await, conn.clone(), 3, None);
+        task.refresh().await.unwrap();
+
+        let tournaments = tournament::Entity::find().all(&conn).await.unwrap();
+        assert_eq!(
+            tournaments
+                .iter()
+                .map(|t| t.status.clone())
+                .collect::<Vec<_>>(),
+            [
+                TournamentStatus::Fought,
+                TournamentStatus::Cancelled,
+                TournamentStatus::Completed
+            ]
+        );
+        for t in &tournaments {
+            assert!(t.modified > NaiveDateTime::default());
+            assert!(t.meta_last_updated > NaiveDateTime::default());
+        }
+
+        // The first tournament's page was located on the way
+        assert_eq!(
+            meta_tournament_page::Entity::find()
+                .count(&conn)
+                .await
+                .unwrap(),
+            3
+        );
+
+        // Details were fetched for the tournaments that were not cancelled, and succeeding does
+        // not count as an attempt
+        let details = meta_tournament_detail::Entity::find()
+            .all(&conn)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| (m.tournament_id, m.state, m.attempts))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            details,
+            [
+                (1, TournamentDetailState::Fetched, 0),
+                (3, TournamentDetailState::Fetched, 0)
+            ]
+        );
+        assert!(
+            tournament_detail_attack::Entity::find()
+                .count(&conn)
+                .await
+                .unwrap()
+                > 0
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn details_are_requeued_when_the_status_changes() {
+        use crate::testing;
+        use sea_orm::IntoActiveModel;
+
+        let conn = testing::database().await;
+        testing::without_foreign_keys(&conn).await;
+
+        // The first detail was fetched before the tournament was fought, the second once it had
+        // completed
+        for (id, status, state) in [
+            (1, TournamentStatus::Created, TournamentDetailState::Empty),
+            (
+                2,
+                TournamentStatus::Completed,
+                TournamentDetailState::Fetched,
+            ),
+        ] {
+            testing::tournament(id, 1, status)
+                .into_active_model()
+                .insert(&conn)
+                .await
+                .unwrap();
+            meta_tournament_detail::ActiveModel {
+                tournament_id: Set(id),
+                tournament_service_id: Set(1),
+                state: Set(state),
+                attempts: Set(0),
+                last_error: Set(None),
+                last_attempt: Set(Some(NaiveDateTime::default())),
+                error_kind: Set(None),
+            }
+            .insert(&conn)
+            .await
+            .unwrap();
+        }
+
+        let page = serde_json::from_value::<RawTournamentResponse>(page(&[
+            (1, "FOUGHT_SUCCEED"),
+            (2, "COMPLETE_SUCCEED"),
+        ]))
+        .unwrap();
+        let task = TournamentTask::new(testing::client(), conn.clone(), 2, None);
+        let batch = task.parse_batch(page, 2, 0).await;
+        task.insert_tournament_batch(fetched_now(&batch.items))
+            .await
+            .unwrap();
+
+        let states = meta_tournament_detail::Entity::find()
+            .all(&conn)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| (m.tournament_id, m.state))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            states,
+            [
+                (1, TournamentDetailState::Pending),
+                (2, TournamentDetailState::Fetched)
+            ]
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use anyhow::{bail, Result};
+use std::env;
+use tracing_subscriber::{
+    filter::LevelFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer,
+    Registry,
+};
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// Installed logging and tracing. Flushes any pending spans when dropped.
+pub struct Telemetry {
+    _private: (),
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        #[cfg(feature = "otlp")]
+        opentelemetry::global::shutdown_tracer_provider();
+    }
+}
+
+/// Set up logging according to `LOG_FORMAT` (`plain`, `pretty` or `json`) and `RUST_LOG`.
+///
+/// With the `otlp` feature spans are also exported over OTLP, as long as
+/// `OTEL_EXPORTER_OTLP_ENDPOINT` is set.
+pub fn init() -> Result<Telemetry> {
+    let format = env::var("LOG_FORMAT").unwrap_or_default();
+    let fmt: BoxedLayer = match format.as_str() {
+        "" | "plain" => fmt::layer().boxed(),
+        "pretty" => fmt::layer().pretty().boxed(),
+        "json" => fmt::layer().json().with_current_span(true).boxed(),
+        format => bail!("invalid LOG_FORMAT {format}, expected plain, pretty or json"),
+    };
+
+    tracing_subscriber::registry()
+        .with(
+            [Some(fmt), otlp()?]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>(),
+        )
+        .with(
+            // Log at INFO unless RUST_LOG says otherwise
+            EnvFilter::builder()
+                .with_default_directive(LevelFilter::INFO.into())
+                .from_env_lossy(),
+        )
+        .init();
+
+    Ok(Telemetry { _private: () })
+}
+
+#[cfg(not(feature = "otlp"))]
+fn otlp() -> Result<Option<BoxedLayer>> {
+    Ok(None)
+}
+
+#[cfg(feature = "otlp")]
+fn otlp() -> Result<Option<BoxedLayer>> {
+    use opentelemetry::{sdk::trace, sdk::Resource, KeyValue};
+
+    if env::var("OTEL_EXPORTER_OTLP_ENDPOINT").map_or(true, |v| v.is_empty()) {
+        return Ok(None);
+    }
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic())
+        .with_trace_config(
+            trace::config().with_resource(Resource::new(vec![KeyValue::new(
+                "service.name",
+                env!("CARGO_PKG_NAME"),
+            )])),
+        )
+        .install_batch(opentelemetry::runtime::Tokio)?;
+
+    Ok(Some(
+        tracing_opentelemetry::layer().with_tracer(tracer).boxed(),
+    ))
+}
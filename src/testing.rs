@@ -0,0 +1,11 @@
+//! Helpers for tests that need a database, run against in-memory SQLite.
+
+pub use migration::testing::*;
+use std::collections::HashMap;
+
+use crate::client::Client;
+
+/// A client without any host limits, for tasks that are not expected to make requests.
+pub fn client() -> Client {
+    Client::new(reqwest::Client::new(), HashMap::new())
+}